@@ -1,16 +1,21 @@
 use std::io::BufReader;
 
+use crate::{Error, Result};
+
 pub struct Music {
     // stream: OutputStreamHandle
 }
 
 impl Music {
-    pub fn new(filename: &str) -> Self {
-        let (_stream, handle) = rodio::OutputStream::try_default().unwrap();
-        let sink = rodio::Sink::try_new(&handle).unwrap();
+    /// Play the sound file `filename` on the default output device.
+    ///
+    /// Fails if there is no audio output or the file cannot be decoded.
+    pub fn new(filename: &str) -> Result<Self> {
+        let (_stream, handle) = rodio::OutputStream::try_default()?;
+        let sink = rodio::Sink::try_new(&handle)?;
 
-        let file = std::fs::File::open(filename).unwrap();
-        sink.append(rodio::Decoder::new(BufReader::new(file)).unwrap());
+        let file = std::fs::File::open(filename).map_err(|err| Error::io(filename, err))?;
+        sink.append(rodio::Decoder::new(BufReader::new(file))?);
 
         sink.detach();
         // sink
@@ -24,7 +29,7 @@ impl Music {
 
         // s
 
-        Self {}
+        Ok(Self {})
     }
 
     // pub fn play(&self) {
@@ -0,0 +1,187 @@
+use std::{fmt, path::PathBuf};
+
+/// Errors returned by the fallible operations of rusty-core.
+///
+/// Games can match on the variants to show a message or fall back, e.g. to
+/// a placeholder texture when an image is missing.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// No graphics adapter is available, or none can present to the window.
+    NoAdapter,
+    /// The window surface could not be created.
+    Surface(wgpu::CreateSurfaceError),
+    /// The adapter refused to create a device.
+    RequestDevice(wgpu::RequestDeviceError),
+    /// The device was lost, e.g. after a driver reset, before an operation
+    /// waiting for it completed.
+    DeviceLost,
+    /// A shader or a post-processing effect does not compile.
+    InvalidShader { name: String, message: String },
+    /// A file could not be read or written.
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// An image could not be decoded or encoded.
+    Image(image::ImageError),
+    /// Support for an image format is behind a cargo feature of rusty-core
+    /// which is not enabled.
+    MissingFeature {
+        format: &'static str,
+        feature: &'static str,
+    },
+    /// The image, the pixel format of a texture or the content of a file is
+    /// not supported.
+    UnsupportedFormat(String),
+    /// The adapter cannot sample textures in this compressed format.
+    UnsupportedByAdapter(wgpu::TextureFormat),
+    /// A KTX2 or DDS container is malformed.
+    InvalidTexture(String),
+    /// A TrueType or OpenType font is malformed.
+    InvalidFont(String),
+    /// A description file, such as an atlas or a sprite sheet, is malformed.
+    Parse { path: PathBuf, message: String },
+    /// The arguments of a call are out of range or inconsistent, e.g. an
+    /// image does not fit in the texture it is copied to.
+    InvalidArgument(String),
+    /// No audio output device is available.
+    #[cfg(feature = "audio")]
+    AudioDevice(rodio::StreamError),
+    /// Sounds cannot be played on the audio output device.
+    #[cfg(feature = "audio")]
+    AudioPlayback(rodio::PlayError),
+    /// A sound could not be decoded.
+    #[cfg(feature = "audio")]
+    AudioDecode(rodio::decoder::DecoderError),
+}
+
+/// Result of the fallible operations of rusty-core.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// Error for an IO failure on the file at `path`.
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Self::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Error for the malformed description file at `path`.
+    pub fn parse(path: impl Into<PathBuf>, message: impl fmt::Display) -> Self {
+        Self::Parse {
+            path: path.into(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoAdapter => write!(f, "no compatible graphics adapter available"),
+            Self::Surface(err) => write!(f, "cannot create the window surface: {err}"),
+            Self::RequestDevice(err) => write!(f, "cannot create the graphics device: {err}"),
+            Self::DeviceLost => write!(f, "the graphics device was lost"),
+            Self::InvalidShader { name, message } => write!(f, "invalid shader {name}: {message}"),
+            Self::Io { path, source } => write!(f, "cannot access {}: {source}", path.display()),
+            Self::Image(err) => write!(f, "cannot process image: {err}"),
+            Self::MissingFeature { format, feature } => write!(
+                f,
+                "{format} textures need the `{feature}` feature of rusty-core"
+            ),
+            Self::UnsupportedFormat(format) => write!(f, "unsupported format: {format}"),
+            Self::UnsupportedByAdapter(format) => {
+                write!(f, "{format:?} textures are not supported by the adapter")
+            }
+            Self::InvalidTexture(reason) => write!(f, "invalid texture: {reason}"),
+            Self::InvalidFont(reason) => write!(f, "invalid font: {reason}"),
+            Self::Parse { path, message } => write!(f, "invalid {}: {message}", path.display()),
+            Self::InvalidArgument(reason) => write!(f, "{reason}"),
+            #[cfg(feature = "audio")]
+            Self::AudioDevice(err) => write!(f, "no audio output available: {err}"),
+            #[cfg(feature = "audio")]
+            Self::AudioPlayback(err) => write!(f, "cannot play audio: {err}"),
+            #[cfg(feature = "audio")]
+            Self::AudioDecode(err) => write!(f, "cannot decode sound: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Surface(err) => Some(err),
+            Self::RequestDevice(err) => Some(err),
+            Self::Io { source, .. } => Some(source),
+            Self::Image(err) => Some(err),
+            #[cfg(feature = "audio")]
+            Self::AudioDevice(err) => Some(err),
+            #[cfg(feature = "audio")]
+            Self::AudioPlayback(err) => Some(err),
+            #[cfg(feature = "audio")]
+            Self::AudioDecode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<wgpu::CreateSurfaceError> for Error {
+    fn from(err: wgpu::CreateSurfaceError) -> Self {
+        Self::Surface(err)
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for Error {
+    fn from(err: wgpu::RequestDeviceError) -> Self {
+        Self::RequestDevice(err)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Self {
+        Self::Image(err)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<rodio::StreamError> for Error {
+    fn from(err: rodio::StreamError) -> Self {
+        Self::AudioDevice(err)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<rodio::PlayError> for Error {
+    fn from(err: rodio::PlayError) -> Self {
+        Self::AudioPlayback(err)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<rodio::decoder::DecoderError> for Error {
+    fn from(err: rodio::decoder::DecoderError) -> Self {
+        Self::AudioDecode(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+
+    #[test]
+    fn file_errors_name_the_file() {
+        let err = Error::io(
+            "assets/missing.png",
+            std::io::Error::from(std::io::ErrorKind::NotFound),
+        );
+        assert!(err
+            .to_string()
+            .starts_with("cannot access assets/missing.png"));
+        assert!(std::error::Error::source(&err).is_some());
+
+        let err = Error::parse("atlas.ron", "expected `(`");
+        assert_eq!(err.to_string(), "invalid atlas.ron: expected `(`");
+    }
+}
@@ -0,0 +1,748 @@
+use std::{collections::HashMap, rc::Rc};
+
+use glam::Vec2;
+use image::RgbaImage;
+
+use super::{
+    sprite::Sprite,
+    texture::{Texture, TextureOptions},
+};
+use crate::{math::Rect, Ctx, Error, Result};
+
+/// Default maximum size of a page, supported by every GPU.
+const DEFAULT_PAGE_SIZE: u32 = 2048;
+
+/// Named part of a page of a [`TextureAtlas`].
+#[derive(Clone, Debug)]
+pub struct AtlasRegion {
+    pub texture: Rc<Texture>,
+    /// Index of the page in [`TextureAtlas::pages`].
+    pub page: usize,
+    /// Area of the page covered by the image, in pixels.
+    pub rect: Rect,
+    /// Size of the image before transparent borders were trimmed, if they
+    /// were.
+    pub source_size: Vec2,
+    /// Position of `rect` in the untrimmed image.
+    pub offset: Vec2,
+}
+
+impl AtlasRegion {
+    /// Sprite displaying the region.
+    pub fn sprite(&self, ctx: &Ctx) -> Sprite {
+        let mut sprite = Sprite::new(ctx, self.texture.clone());
+        sprite.set_texture_rect(self.rect);
+
+        sprite
+    }
+}
+
+/// Images packed into a few large textures, called pages, so that drawing
+/// them does not require switching textures.
+///
+/// Atlases are either packed at runtime with a [`TextureAtlasBuilder`] or
+/// loaded from a description written by [`PackedAtlas::save`] or by
+/// TexturePacker (JSON hash format).
+///
+/// ```ignore
+/// let mut builder = TextureAtlasBuilder::new().padding(2).extrude(1);
+/// builder.add("player", image::open("player.png")?.to_rgba8());
+/// builder.add("enemy", image::open("enemy.png")?.to_rgba8());
+/// let atlas = builder.build(ctx)?;
+///
+/// let player = atlas.region("player").unwrap().sprite(ctx);
+/// ```
+#[derive(Debug)]
+pub struct TextureAtlas {
+    pages: Vec<Rc<Texture>>,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.get(name)
+    }
+
+    pub fn regions(&self) -> impl Iterator<Item = (&str, &AtlasRegion)> {
+        self.regions
+            .iter()
+            .map(|(name, region)| (name.as_str(), region))
+    }
+
+    pub fn pages(&self) -> &[Rc<Texture>] {
+        &self.pages
+    }
+}
+
+/// Placement of an image in a [`PackedAtlas`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackedRegion {
+    pub page: usize,
+    /// Area of the page covered by the image, in pixels.
+    pub rect: Rect,
+    pub source_size: Vec2,
+    pub offset: Vec2,
+}
+
+/// Atlas packed on the CPU, which can be saved to disk or uploaded to the GPU.
+#[derive(Clone, Debug)]
+pub struct PackedAtlas {
+    pub pages: Vec<RgbaImage>,
+    pub regions: HashMap<String, PackedRegion>,
+}
+
+impl PackedAtlas {
+    /// Create a texture for every page, sampled according to `options`.
+    pub fn upload(&self, ctx: &Ctx, options: TextureOptions) -> Result<TextureAtlas> {
+        let pages = self
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(i, page)| {
+                let image = image::DynamicImage::ImageRgba8(page.clone());
+                Texture::from_image(ctx, &image, Some(&format!("atlas page {i}")), options)
+                    .map(Rc::new)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let regions = self
+            .regions
+            .iter()
+            .map(|(name, region)| {
+                let region = AtlasRegion {
+                    texture: pages[region.page].clone(),
+                    page: region.page,
+                    rect: region.rect,
+                    source_size: region.source_size,
+                    offset: region.offset,
+                };
+
+                (name.clone(), region)
+            })
+            .collect();
+
+        Ok(TextureAtlas { pages, regions })
+    }
+}
+
+/// Packs images into the pages of a [`TextureAtlas`].
+///
+/// Images are sorted by height and placed with a skyline bottom-left
+/// heuristic, opening a new page when one is full.
+pub struct TextureAtlasBuilder {
+    images: Vec<(String, RgbaImage)>,
+    max_size: (u32, u32),
+    padding: u32,
+    extrude: u32,
+    texture_options: TextureOptions,
+}
+
+impl Default for TextureAtlasBuilder {
+    fn default() -> Self {
+        Self {
+            images: Vec::new(),
+            max_size: (DEFAULT_PAGE_SIZE, DEFAULT_PAGE_SIZE),
+            padding: 0,
+            extrude: 0,
+            texture_options: TextureOptions::default(),
+        }
+    }
+}
+
+impl TextureAtlasBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum size of a page in pixels, 2048x2048 by default.
+    pub fn max_size(mut self, width: u32, height: u32) -> Self {
+        self.max_size = (width, height);
+        self
+    }
+
+    /// Transparent pixels left between images.
+    pub fn padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Repeat the border pixels of every image around it, so that filtering
+    /// at the edge of a region does not sample its neighbours.
+    pub fn extrude(mut self, extrude: u32) -> Self {
+        self.extrude = extrude;
+        self
+    }
+
+    /// Options of the page textures created by [`TextureAtlasBuilder::build`],
+    /// e.g. [`TextureOptions::pixel_art`].
+    pub fn texture_options(mut self, options: TextureOptions) -> Self {
+        self.texture_options = options;
+        self
+    }
+
+    /// Add an image, replacing any previous one with the same name.
+    pub fn add(&mut self, name: &str, image: RgbaImage) -> &mut Self {
+        self.images.retain(|(other, _)| other != name);
+        self.images.push((name.to_owned(), image));
+        self
+    }
+
+    /// Place every image, failing if one does not fit in a page.
+    pub fn pack(&self) -> Result<PackedAtlas> {
+        let border = 2 * self.extrude;
+        // Padding is only needed between images, not after the last one
+        let bin = (
+            self.max_size.0 + self.padding,
+            self.max_size.1 + self.padding,
+        );
+
+        let mut order: Vec<usize> = (0..self.images.len()).collect();
+        order.sort_by_key(|i| {
+            let (width, height) = self.images[*i].1.dimensions();
+            (std::cmp::Reverse(height), std::cmp::Reverse(width))
+        });
+
+        let mut skylines: Vec<Skyline> = Vec::new();
+        let mut placements = vec![(0, 0, 0); self.images.len()];
+        for i in order {
+            let (name, image) = &self.images[i];
+            let size = (
+                image.width() + border + self.padding,
+                image.height() + border + self.padding,
+            );
+            if size.0 > bin.0 || size.1 > bin.1 {
+                return Err(Error::InvalidArgument(format!(
+                    "{name} ({}x{}) does not fit in a {}x{} atlas page",
+                    image.width(),
+                    image.height(),
+                    self.max_size.0,
+                    self.max_size.1
+                )));
+            }
+
+            let placed = skylines
+                .iter_mut()
+                .enumerate()
+                .find_map(|(page, skyline)| skyline.insert(size).map(|(x, y)| (page, x, y)));
+            placements[i] = match placed {
+                Some(placement) => placement,
+                None => {
+                    let mut skyline = Skyline::new(bin);
+                    let (x, y) = skyline.insert(size).unwrap();
+                    skylines.push(skyline);
+                    (skylines.len() - 1, x, y)
+                }
+            };
+        }
+
+        // Pages are cropped to the area actually used
+        let mut page_sizes = vec![(1, 1); skylines.len()];
+        for ((_, image), (page, x, y)) in self.images.iter().zip(&placements) {
+            let size = &mut page_sizes[*page];
+            size.0 = size.0.max(x + image.width() + border);
+            size.1 = size.1.max(y + image.height() + border);
+        }
+        let mut pages: Vec<RgbaImage> = page_sizes
+            .iter()
+            .map(|(width, height)| RgbaImage::new(*width, *height))
+            .collect();
+
+        let mut regions = HashMap::with_capacity(self.images.len());
+        for ((name, image), (page, x, y)) in self.images.iter().zip(placements) {
+            blit_extruded(&mut pages[page], image, x, y, self.extrude);
+
+            let size = Vec2::new(image.width() as f32, image.height() as f32);
+            regions.insert(
+                name.clone(),
+                PackedRegion {
+                    page,
+                    rect: Rect {
+                        x: (x + self.extrude) as f32,
+                        y: (y + self.extrude) as f32,
+                        width: size.x,
+                        height: size.y,
+                    },
+                    source_size: size,
+                    offset: Vec2::ZERO,
+                },
+            );
+        }
+
+        Ok(PackedAtlas { pages, regions })
+    }
+
+    /// Pack the images and upload the pages.
+    pub fn build(&self, ctx: &Ctx) -> Result<TextureAtlas> {
+        self.pack()?.upload(ctx, self.texture_options)
+    }
+}
+
+/// Copy `image` at `x + extrude`, `y + extrude`, repeating its border pixels
+/// `extrude` times around it.
+fn blit_extruded(page: &mut RgbaImage, image: &RgbaImage, x: u32, y: u32, extrude: u32) {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    for dy in 0..height + 2 * extrude {
+        for dx in 0..width + 2 * extrude {
+            let source_x = dx.saturating_sub(extrude).min(width - 1);
+            let source_y = dy.saturating_sub(extrude).min(height - 1);
+            page.put_pixel(x + dx, y + dy, *image.get_pixel(source_x, source_y));
+        }
+    }
+}
+
+/// Top edge of the packed area of a page, as horizontal segments from left
+/// to right.
+pub(super) struct Skyline {
+    size: (u32, u32),
+    /// `(x, y, width)` of each segment.
+    segments: Vec<(u32, u32, u32)>,
+}
+
+impl Skyline {
+    pub(super) fn new(size: (u32, u32)) -> Self {
+        Self {
+            size,
+            segments: vec![(0, 0, size.0)],
+        }
+    }
+
+    /// Place a rectangle as low as possible, then as far left as possible.
+    pub(super) fn insert(&mut self, (width, height): (u32, u32)) -> Option<(u32, u32)> {
+        let (index, x, y) = (0..self.segments.len())
+            .filter_map(|i| {
+                self.fit(i, width, height)
+                    .map(|y| (i, self.segments[i].0, y))
+            })
+            .min_by_key(|(_, x, y)| (y + height, *x))?;
+
+        self.segments.insert(index, (x, y + height, width));
+        // Shorten or remove the segments now under the rectangle
+        let end = x + width;
+        let next = index + 1;
+        while next < self.segments.len() {
+            let (segment_x, segment_y, segment_width) = self.segments[next];
+            if segment_x >= end {
+                break;
+            }
+            let segment_end = segment_x + segment_width;
+            if segment_end <= end {
+                self.segments.remove(next);
+            } else {
+                self.segments[next] = (end, segment_y, segment_end - end);
+                break;
+            }
+        }
+        self.segments.dedup_by(|next, previous| {
+            let merge = previous.1 == next.1;
+            if merge {
+                previous.2 += next.2;
+            }
+            merge
+        });
+
+        Some((x, y))
+    }
+
+    /// Height at which a rectangle starting at segment `index` rests, if it
+    /// fits in the page.
+    fn fit(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.segments[index].0;
+        if x + width > self.size.0 {
+            return None;
+        }
+
+        let mut y = 0;
+        let mut covered = 0;
+        for (_, segment_y, segment_width) in &self.segments[index..] {
+            y = y.max(*segment_y);
+            covered += segment_width;
+            if covered >= width {
+                break;
+            }
+        }
+
+        (y + height <= self.size.1).then_some(y)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod sheet {
+    //! Atlas descriptions in TexturePacker's JSON hash format, one file per
+    //! page, also readable and writable as RON.
+
+    use std::{
+        collections::{BTreeMap, HashMap},
+        path::{Path, PathBuf},
+    };
+
+    use glam::Vec2;
+    use serde::{Deserialize, Serialize};
+
+    use super::{PackedAtlas, PackedRegion, TextureAtlas, TextureOptions};
+    use crate::{math::Rect, Ctx, Error, Result};
+
+    #[derive(Serialize, Deserialize)]
+    struct Sheet {
+        frames: BTreeMap<String, SheetFrame>,
+        meta: SheetMeta,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct SheetFrame {
+        frame: SheetRect,
+        #[serde(default)]
+        rotated: bool,
+        #[serde(default)]
+        trimmed: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sprite_source_size: Option<SheetRect>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source_size: Option<SheetSize>,
+    }
+
+    #[derive(Copy, Clone, Serialize, Deserialize)]
+    struct SheetRect {
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+    }
+
+    #[derive(Copy, Clone, Serialize, Deserialize)]
+    struct SheetSize {
+        w: u32,
+        h: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct SheetMeta {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        app: Option<String>,
+        image: String,
+        size: SheetSize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        format: Option<String>,
+        /// Descriptions of the other pages, relative to this one.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        related_multi_packs: Vec<String>,
+    }
+
+    fn is_ron(path: &Path) -> bool {
+        path.extension().is_some_and(|extension| extension == "ron")
+    }
+
+    fn read_sheet(path: &Path) -> Result<Sheet> {
+        let contents = std::fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+        let sheet = if is_ron(path) {
+            ron::from_str(&contents).map_err(|err| Error::parse(path, err))?
+        } else {
+            serde_json::from_str(&contents).map_err(|err| Error::parse(path, err))?
+        };
+
+        Ok(sheet)
+    }
+
+    /// Path of the n-th page, `atlas.json` giving `atlas.png`, then
+    /// `atlas-1.json` and `atlas-1.png`...
+    fn page_path(path: &Path, page: usize, extension: &str) -> PathBuf {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match page {
+            0 => format!("{stem}.{extension}"),
+            _ => format!("{stem}-{page}.{extension}"),
+        };
+
+        path.with_file_name(name)
+    }
+
+    impl PackedAtlas {
+        /// Read an atlas description and its page images.
+        ///
+        /// The format is chosen from the extension, RON for `.ron` and
+        /// TexturePacker's JSON hash otherwise. Pages listed in
+        /// `related_multi_packs` are loaded too. Rotated frames are not
+        /// supported.
+        pub fn load(path: &Path) -> Result<Self> {
+            let mut sheets = vec![(path.to_path_buf(), read_sheet(path)?)];
+            let related: Vec<PathBuf> = sheets[0]
+                .1
+                .meta
+                .related_multi_packs
+                .iter()
+                .map(|related| path.with_file_name(related))
+                .collect();
+            for related in related {
+                let sheet = read_sheet(&related)?;
+                sheets.push((related, sheet));
+            }
+
+            let mut pages = Vec::with_capacity(sheets.len());
+            let mut regions = HashMap::new();
+            for (page, (path, sheet)) in sheets.into_iter().enumerate() {
+                let image_path = path.with_file_name(&sheet.meta.image);
+                let bytes =
+                    std::fs::read(&image_path).map_err(|err| Error::io(&image_path, err))?;
+                let image = image::load_from_memory(&bytes)?;
+                pages.push(image.to_rgba8());
+
+                for (name, frame) in sheet.frames {
+                    if frame.rotated {
+                        return Err(Error::UnsupportedFormat(format!("rotated frame {name}")));
+                    }
+
+                    let rect = frame.frame;
+                    let source = frame.sprite_source_size.unwrap_or(SheetRect {
+                        x: 0,
+                        y: 0,
+                        w: rect.w,
+                        h: rect.h,
+                    });
+                    let source_size = frame.source_size.unwrap_or(SheetSize {
+                        w: rect.w,
+                        h: rect.h,
+                    });
+                    regions.insert(
+                        name,
+                        PackedRegion {
+                            page,
+                            rect: Rect {
+                                x: rect.x as f32,
+                                y: rect.y as f32,
+                                width: rect.w as f32,
+                                height: rect.h as f32,
+                            },
+                            source_size: Vec2::new(source_size.w as f32, source_size.h as f32),
+                            offset: Vec2::new(source.x as f32, source.y as f32),
+                        },
+                    );
+                }
+            }
+
+            Ok(Self { pages, regions })
+        }
+
+        /// Write the page images next to their descriptions, in the format
+        /// given by the extension of `path` like [`PackedAtlas::load`].
+        ///
+        /// `atlas.json` is the description of the first page, the others
+        /// being written to `atlas-1.json`, `atlas-2.json`...
+        pub fn save(&self, path: &Path) -> Result<()> {
+            let extension = if is_ron(path) { "ron" } else { "json" };
+            let related: Vec<String> = (1..self.pages.len())
+                .map(|page| {
+                    page_path(path, page, extension)
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect();
+
+            for (page, image) in self.pages.iter().enumerate() {
+                let image_path = page_path(path, page, "png");
+                image.save(&image_path)?;
+
+                let frames = self
+                    .regions
+                    .iter()
+                    .filter(|(_, region)| region.page == page)
+                    .map(|(name, region)| {
+                        let rect = SheetRect {
+                            x: region.rect.x as u32,
+                            y: region.rect.y as u32,
+                            w: region.rect.width as u32,
+                            h: region.rect.height as u32,
+                        };
+                        let source_size = SheetSize {
+                            w: region.source_size.x as u32,
+                            h: region.source_size.y as u32,
+                        };
+                        let frame = SheetFrame {
+                            frame: rect,
+                            rotated: false,
+                            trimmed: source_size.w != rect.w || source_size.h != rect.h,
+                            sprite_source_size: Some(SheetRect {
+                                x: region.offset.x as u32,
+                                y: region.offset.y as u32,
+                                ..rect
+                            }),
+                            source_size: Some(source_size),
+                        };
+
+                        (name.clone(), frame)
+                    })
+                    .collect();
+                let sheet = Sheet {
+                    frames,
+                    meta: SheetMeta {
+                        app: Some("rusty-engine".to_owned()),
+                        image: page_path(path, page, "png")
+                            .file_name()
+                            .unwrap()
+                            .to_string_lossy()
+                            .into_owned(),
+                        size: SheetSize {
+                            w: image.width(),
+                            h: image.height(),
+                        },
+                        format: Some("RGBA8888".to_owned()),
+                        related_multi_packs: if page == 0 {
+                            related.clone()
+                        } else {
+                            Vec::new()
+                        },
+                    },
+                };
+
+                let sheet_path = page_path(path, page, extension);
+                let contents = if is_ron(path) {
+                    ron::ser::to_string_pretty(&sheet, ron::ser::PrettyConfig::default())
+                        .map_err(|err| Error::parse(&sheet_path, err))?
+                } else {
+                    serde_json::to_string_pretty(&sheet)
+                        .map_err(|err| Error::parse(&sheet_path, err))?
+                };
+                std::fs::write(&sheet_path, contents).map_err(|err| Error::io(&sheet_path, err))?;
+            }
+
+            Ok(())
+        }
+    }
+
+    impl TextureAtlas {
+        /// Load an atlas saved by [`PackedAtlas::save`] or TexturePacker, see
+        /// [`PackedAtlas::load`], its pages being sampled according to
+        /// `options`.
+        pub fn load(ctx: &Ctx, path: &Path, options: TextureOptions) -> Result<Self> {
+            PackedAtlas::load(path)?.upload(ctx, options)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, value: u8) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, image::Rgba([value, value, value, 255]))
+    }
+
+    fn overlaps(a: &Rect, b: &Rect) -> bool {
+        a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+    }
+
+    #[test]
+    fn regions_do_not_overlap() {
+        let mut builder = TextureAtlasBuilder::new().max_size(64, 64).padding(1);
+        for i in 0..12 {
+            builder.add(&format!("{i}"), image(8 + i % 5, 6 + i % 7, i as u8));
+        }
+        let atlas = builder.pack().unwrap();
+
+        assert_eq!(atlas.pages.len(), 1);
+        let regions: Vec<&PackedRegion> = atlas.regions.values().collect();
+        for (i, a) in regions.iter().enumerate() {
+            let (width, height) = atlas.pages[0].dimensions();
+            assert!(a.rect.x + a.rect.width <= width as f32);
+            assert!(a.rect.y + a.rect.height <= height as f32);
+            for b in &regions[i + 1..] {
+                assert!(!overlaps(&a.rect, &b.rect), "{a:?} overlaps {b:?}");
+            }
+        }
+
+        // Images are copied into their region
+        let region = &atlas.regions["7"];
+        let pixel = atlas.pages[0].get_pixel(region.rect.x as u32, region.rect.y as u32);
+        assert_eq!(pixel.0, [7, 7, 7, 255]);
+    }
+
+    #[test]
+    fn full_pages_open_new_ones() {
+        let mut builder = TextureAtlasBuilder::new().max_size(16, 16);
+        for name in ["a", "b", "c", "d", "e"] {
+            builder.add(name, image(8, 8, 0));
+        }
+        let atlas = builder.pack().unwrap();
+
+        assert_eq!(atlas.pages.len(), 2);
+        assert_eq!(atlas.pages[0].dimensions(), (16, 16));
+        assert_eq!(atlas.pages[1].dimensions(), (8, 8));
+    }
+
+    #[test]
+    fn images_are_extruded() {
+        let mut source = image(2, 2, 10);
+        source.put_pixel(1, 1, image::Rgba([200, 0, 0, 255]));
+        let mut builder = TextureAtlasBuilder::new().extrude(2);
+        builder.add("image", source);
+        let atlas = builder.pack().unwrap();
+
+        let region = &atlas.regions["image"];
+        assert_eq!((region.rect.x, region.rect.y), (2., 2.));
+        assert_eq!(atlas.pages[0].dimensions(), (6, 6));
+        assert_eq!(atlas.pages[0].get_pixel(0, 0).0, [10, 10, 10, 255]);
+        assert_eq!(atlas.pages[0].get_pixel(5, 5).0, [200, 0, 0, 255]);
+    }
+
+    #[test]
+    fn oversized_images_are_rejected() {
+        let mut builder = TextureAtlasBuilder::new().max_size(16, 16).padding(4);
+        builder.add("fits", image(16, 16, 0));
+        assert!(builder.pack().is_ok());
+
+        builder.add("too big", image(17, 4, 0));
+        assert!(builder.pack().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn texture_packer_json_hash() {
+        let directory = std::env::temp_dir().join(format!("rusty-atlas-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        image(32, 16, 0).save(directory.join("sheet.png")).unwrap();
+        let json = r#"{
+            "frames": {
+                "hero.png": {
+                    "frame": {"x": 2, "y": 3, "w": 10, "h": 12},
+                    "rotated": false,
+                    "trimmed": true,
+                    "spriteSourceSize": {"x": 1, "y": 2, "w": 10, "h": 12},
+                    "sourceSize": {"w": 12, "h": 16}
+                }
+            },
+            "meta": {
+                "app": "https://www.codeandweb.com/texturepacker",
+                "version": "1.0",
+                "image": "sheet.png",
+                "format": "RGBA8888",
+                "size": {"w": 32, "h": 16},
+                "scale": "1"
+            }
+        }"#;
+        std::fs::write(directory.join("sheet.json"), json).unwrap();
+
+        let atlas = PackedAtlas::load(&directory.join("sheet.json")).unwrap();
+        let hero = &atlas.regions["hero.png"];
+        assert_eq!(hero.rect.x, 2.);
+        assert_eq!(hero.rect.height, 12.);
+        assert_eq!(hero.source_size, Vec2::new(12., 16.));
+        assert_eq!(hero.offset, Vec2::new(1., 2.));
+
+        // Round trip through two pages in RON
+        let mut builder = TextureAtlasBuilder::new().max_size(8, 8);
+        builder.add("a", image(8, 8, 1)).add("b", image(4, 4, 2));
+        let packed = builder.pack().unwrap();
+        packed.save(&directory.join("packed.ron")).unwrap();
+        assert!(directory.join("packed-1.png").exists());
+
+        let loaded = PackedAtlas::load(&directory.join("packed.ron")).unwrap();
+        assert_eq!(loaded.pages.len(), 2);
+        assert_eq!(loaded.regions["b"], packed.regions["b"]);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,238 @@
+use std::{ops::Range, rc::Rc};
+
+use glam::{Mat4, Vec4};
+use wgpu::util::DeviceExt;
+
+use super::{
+    shape::{RectangleShape, ShapeVertex},
+    sprite::Sprite,
+    texture::Texture,
+    QUAD_INDICES,
+};
+use crate::Ctx;
+
+/// Initial number of vertices the GPU buffers can hold.
+const INITIAL_CAPACITY: usize = 1024;
+
+/// Statistics of the last batch submitted with [`SpriteBatch::end`].
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct BatchStats {
+    /// Number of `draw_indexed` calls issued when drawing the batch.
+    pub draw_calls: u32,
+    pub vertices: u32,
+    pub triangles: u32,
+}
+
+/// Consecutive triangles sharing the same texture.
+pub(crate) struct Run {
+    pub(crate) texture: Option<Rc<Texture>>,
+    pub(crate) indices: Range<u32>,
+}
+
+/// Accumulates sprites and shapes into a single vertex buffer and draws them
+/// with one draw call per texture change.
+///
+/// Vertices are transformed on the CPU, so every object of the batch shares
+/// the same buffers and identity transform. Draw order is preserved: drawing
+/// objects sorted by texture keeps the number of draw calls minimal.
+///
+/// ```ignore
+/// batch.begin();
+/// for sprite in &sprites {
+///     batch.draw_sprite(sprite);
+/// }
+/// batch.end();
+///
+/// render_pass.draw_batch(&batch);
+/// ```
+pub struct SpriteBatch {
+    pub(crate) ctx: Ctx,
+    vertices: Vec<ShapeVertex>,
+    indices: Vec<u32>,
+    pub(crate) batches: Vec<Run>,
+    pub(crate) vertex_buffer: wgpu::Buffer,
+    pub(crate) index_buffer: wgpu::Buffer,
+    pub(crate) transform_bind_group: wgpu::BindGroup,
+    stats: BatchStats,
+}
+
+impl SpriteBatch {
+    pub fn new(ctx: &Ctx) -> Self {
+        let transform_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("batch transform buffer"),
+                contents: bytemuck::cast_slice(&[Mat4::IDENTITY]),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let transform_bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &ctx.bind_group_layouts.transform,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: transform_buffer.as_entire_binding(),
+            }],
+            label: Some("batch transform bind group"),
+        });
+
+        Self {
+            ctx: ctx.clone(),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            batches: Vec::new(),
+            vertex_buffer: create_buffer::<ShapeVertex>(
+                ctx,
+                "batch vertex buffer",
+                INITIAL_CAPACITY,
+                wgpu::BufferUsages::VERTEX,
+            ),
+            index_buffer: create_buffer::<u32>(
+                ctx,
+                "batch index buffer",
+                INITIAL_CAPACITY / 4 * 6,
+                wgpu::BufferUsages::INDEX,
+            ),
+            transform_bind_group,
+            stats: BatchStats::default(),
+        }
+    }
+
+    /// Discard the content of the previous frame.
+    pub fn begin(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.batches.clear();
+    }
+
+    pub fn draw_sprite(&mut self, sprite: &Sprite) {
+        self.push(
+            Some(sprite.texture()),
+            sprite.mesh().model_matrix(),
+            sprite.vertices(),
+            &QUAD_INDICES,
+        );
+    }
+
+    /// Add the fill of `rectangle` then its outline, if any.
+    pub fn draw_rectangle(&mut self, rectangle: &RectangleShape) {
+        let transform = rectangle.mesh().model_matrix();
+        let fill = rectangle.fill();
+        self.push(
+            rectangle.mesh().texture(),
+            transform,
+            &fill.vertices,
+            &fill.indices,
+        );
+
+        let outline = rectangle.outline();
+        if !outline.indices.is_empty() {
+            self.push(None, transform, &outline.vertices, &outline.indices);
+        }
+    }
+
+    /// Append triangles to the batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `texture` - Texture sampled by the triangles, a white one when
+    ///   `None`
+    /// * `transform` - Model matrix applied to `vertices`
+    /// * `vertices` - Vertices in local coordinates
+    /// * `indices` - Triangle list indexing `vertices`
+    pub fn push(
+        &mut self,
+        texture: Option<&Rc<Texture>>,
+        transform: Mat4,
+        vertices: &[ShapeVertex],
+        indices: &[u32],
+    ) {
+        let base = self.vertices.len() as u32;
+        let start = self.indices.len() as u32;
+
+        self.vertices.extend(vertices.iter().map(|vertex| {
+            let [x, y, z] = vertex.position;
+            let position = transform * Vec4::new(x, y, z, 1.);
+
+            ShapeVertex {
+                position: position.truncate().into(),
+                ..*vertex
+            }
+        }));
+        self.indices
+            .extend(indices.iter().map(|index| base + index));
+
+        let end = self.indices.len() as u32;
+        match self.batches.last_mut() {
+            Some(run) if same_texture(run.texture.as_ref(), texture) => run.indices.end = end,
+            _ => self.batches.push(Run {
+                texture: texture.cloned(),
+                indices: start..end,
+            }),
+        }
+    }
+
+    /// Upload the accumulated geometry, the batch can then be drawn with
+    /// [`super::Drawable::draw_batch`].
+    pub fn end(&mut self) {
+        self.stats = BatchStats {
+            draw_calls: self.batches.len() as u32,
+            vertices: self.vertices.len() as u32,
+            triangles: self.indices.len() as u32 / 3,
+        };
+
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        if self.vertices.len() * std::mem::size_of::<ShapeVertex>()
+            > self.vertex_buffer.size() as usize
+        {
+            self.vertex_buffer = create_buffer::<ShapeVertex>(
+                &self.ctx,
+                "batch vertex buffer",
+                self.vertices.len().next_power_of_two(),
+                wgpu::BufferUsages::VERTEX,
+            );
+        }
+        if self.indices.len() * std::mem::size_of::<u32>() > self.index_buffer.size() as usize {
+            self.index_buffer = create_buffer::<u32>(
+                &self.ctx,
+                "batch index buffer",
+                self.indices.len().next_power_of_two(),
+                wgpu::BufferUsages::INDEX,
+            );
+        }
+
+        self.ctx
+            .queue
+            .write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&self.vertices));
+        self.ctx
+            .queue
+            .write_buffer(&self.index_buffer, 0, bytemuck::cast_slice(&self.indices));
+    }
+
+    pub fn stats(&self) -> BatchStats {
+        self.stats
+    }
+}
+
+fn same_texture(a: Option<&Rc<Texture>>, b: Option<&Rc<Texture>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn create_buffer<T>(
+    ctx: &Ctx,
+    label: &str,
+    capacity: usize,
+    usage: wgpu::BufferUsages,
+) -> wgpu::Buffer {
+    ctx.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: (capacity * std::mem::size_of::<T>()) as wgpu::BufferAddress,
+        usage: usage | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
@@ -0,0 +1,352 @@
+use glam::{Mat4, Vec2, Vec3};
+use wgpu::util::DeviceExt;
+
+use crate::{math::Rect, Ctx};
+
+/// 2D view of the world, bound to a render pass with
+/// [`super::Renderer::set_camera`].
+///
+/// The camera looks at `center` and shows an area of `size / zoom` world
+/// units, rotated by `rotation`, into its viewport. Several cameras can draw
+/// into the same frame with different viewports, e.g. for split screen or a
+/// minimap.
+///
+/// ```ignore
+/// camera.follow(player.position(), dt);
+/// camera.update(dt);
+///
+/// let mut render_pass = renderer.begin_pass(&mut frame, Some(wgpu::Color::BLACK));
+/// renderer.set_camera(&mut render_pass, &camera, renderer.size());
+/// render_pass.draw_mesh(player.mesh());
+/// ```
+pub struct Camera2D {
+    ctx: Ctx,
+    center: Vec2,
+    size: Vec2,
+    zoom: f32,
+    rotation: f32,
+    viewport: Rect,
+    bounds: Option<Rect>,
+    dead_zone: Vec2,
+    follow_speed: f32,
+    shake: Shake,
+    buffer: wgpu::Buffer,
+    pub(crate) bind_group: wgpu::BindGroup,
+}
+
+/// Remaining screen shake, decreasing linearly over its duration.
+#[derive(Default)]
+struct Shake {
+    intensity: f32,
+    duration: f32,
+    remaining: f32,
+    offset: Vec2,
+    seed: u32,
+}
+
+impl Camera2D {
+    /// Create a camera showing `size` world units, with its top-left corner at
+    /// the origin, over the whole render target.
+    ///
+    /// With `size` set to the size of the target this matches the default
+    /// projection of the renderer.
+    pub fn new(ctx: &Ctx, size: Vec2) -> Self {
+        let buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("camera buffer"),
+                contents: bytemuck::cast_slice(&[Mat4::IDENTITY]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &ctx.bind_group_layouts.projection,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("camera bind group"),
+        });
+
+        let camera = Self {
+            ctx: ctx.clone(),
+            center: size / 2.,
+            size,
+            zoom: 1.,
+            rotation: 0.,
+            viewport: Rect {
+                x: 0.,
+                y: 0.,
+                width: 1.,
+                height: 1.,
+            },
+            bounds: None,
+            dead_zone: Vec2::ZERO,
+            follow_speed: 0.,
+            shake: Shake {
+                seed: 0x9e37_79b9,
+                ..Default::default()
+            },
+            buffer,
+            bind_group,
+        };
+        camera.update_uniform();
+
+        camera
+    }
+
+    /// Set the point of the world displayed at the center of the viewport.
+    pub fn set_center(&mut self, center: Vec2) {
+        self.center = center;
+        self.clamp_to_bounds();
+        self.update_uniform();
+    }
+
+    pub fn center(&self) -> Vec2 {
+        self.center
+    }
+
+    pub fn r#move(&mut self, offset: Vec2) {
+        self.set_center(self.center + offset);
+    }
+
+    /// Set the area of the world displayed at a zoom of 1, in world units.
+    pub fn set_size(&mut self, size: Vec2) {
+        self.size = size;
+        self.clamp_to_bounds();
+        self.update_uniform();
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.size
+    }
+
+    /// Set the magnification, 2 showing objects twice as big.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom.max(f32::EPSILON);
+        self.clamp_to_bounds();
+        self.update_uniform();
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Set the rotation of the view in radians, the world appearing rotated
+    /// the other way.
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+        self.update_uniform();
+    }
+
+    pub fn rotation(&self) -> f32 {
+        self.rotation
+    }
+
+    /// Set the part of the render target drawn into, as fractions of its size
+    /// (`0..1` on both axes).
+    pub fn set_viewport(&mut self, viewport: Rect) {
+        self.viewport = viewport;
+    }
+
+    pub fn viewport(&self) -> &Rect {
+        &self.viewport
+    }
+
+    /// Viewport in pixels for a render target of `target_size`.
+    pub fn viewport_pixels(&self, target_size: Vec2) -> Rect {
+        Rect {
+            x: self.viewport.x * target_size.x,
+            y: self.viewport.y * target_size.y,
+            width: self.viewport.width * target_size.x,
+            height: self.viewport.height * target_size.y,
+        }
+    }
+
+    /// Draw the next objects of `render_pass`, into a target of `target_size`
+    /// pixels, through the camera.
+    pub(crate) fn bind<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        target_size: (u32, u32),
+    ) {
+        let (width, height) = target_size;
+        let viewport = self.viewport_pixels(Vec2::new(width as f32, height as f32));
+
+        render_pass.set_viewport(
+            viewport.x,
+            viewport.y,
+            viewport.width,
+            viewport.height,
+            0.,
+            1.,
+        );
+        render_pass.set_bind_group(2, &self.bind_group, &[]);
+    }
+
+    /// Keep the visible area inside `bounds`, or let the camera move freely
+    /// when `None`.
+    ///
+    /// When the visible area is larger than the bounds, the camera is
+    /// centered on them.
+    pub fn set_bounds(&mut self, bounds: Option<Rect>) {
+        self.bounds = bounds;
+        self.clamp_to_bounds();
+        self.update_uniform();
+    }
+
+    pub fn bounds(&self) -> Option<&Rect> {
+        self.bounds.as_ref()
+    }
+
+    /// Set the size, in world units, of the area around the center in which
+    /// a followed target can move without the camera moving.
+    pub fn set_dead_zone(&mut self, dead_zone: Vec2) {
+        self.dead_zone = dead_zone;
+    }
+
+    pub fn dead_zone(&self) -> Vec2 {
+        self.dead_zone
+    }
+
+    /// Set how fast the camera catches up with a followed target. Higher is
+    /// snappier, 0 moves the camera instantly.
+    pub fn set_follow_speed(&mut self, speed: f32) {
+        self.follow_speed = speed;
+    }
+
+    pub fn follow_speed(&self) -> f32 {
+        self.follow_speed
+    }
+
+    /// Move the camera towards `target` when it leaves the dead zone.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Position of the followed object, in world units
+    /// * `dt` - Time elapsed since the last call, in seconds
+    pub fn follow(&mut self, target: Vec2, dt: f32) {
+        let half = self.dead_zone / 2.;
+        let offset = target - self.center;
+        let excess = offset - offset.clamp(-half, half);
+
+        let t = if self.follow_speed > 0. {
+            1. - (-self.follow_speed * dt).exp()
+        } else {
+            1.
+        };
+        self.set_center(self.center + excess * t);
+    }
+
+    /// Shake the view by up to `intensity` world units, fading out over
+    /// `duration` seconds.
+    pub fn shake(&mut self, intensity: f32, duration: f32) {
+        self.shake.intensity = intensity;
+        self.shake.duration = duration;
+        self.shake.remaining = duration;
+    }
+
+    /// Advance the screen shake by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        let shake = &mut self.shake;
+        if shake.remaining <= 0. && shake.offset == Vec2::ZERO {
+            return;
+        }
+
+        shake.remaining = (shake.remaining - dt).max(0.);
+        shake.offset = if shake.remaining > 0. {
+            let amplitude = shake.intensity * shake.remaining / shake.duration;
+            Vec2::new(shake.next_random(), shake.next_random()) * amplitude
+        } else {
+            Vec2::ZERO
+        };
+        self.update_uniform();
+    }
+
+    /// Matrix converting world coordinates to the camera's coordinates, with
+    /// its center at the origin.
+    pub fn view_matrix(&self) -> Mat4 {
+        let center = self.center + self.shake.offset;
+
+        Mat4::from_rotation_z(-self.rotation)
+            * Mat4::from_translation(Vec3::new(-center.x, -center.y, 0.))
+    }
+
+    /// Matrix converting world coordinates to clip space.
+    pub fn view_projection_matrix(&self) -> Mat4 {
+        let half = self.visible_size() / 2.;
+
+        Mat4::orthographic_rh(-half.x, half.x, half.y, -half.y, -1., 0.) * self.view_matrix()
+    }
+
+    /// Convert a position in pixels on a render target of `target_size` to
+    /// world coordinates.
+    pub fn screen_to_world(&self, point: Vec2, target_size: Vec2) -> Vec2 {
+        let viewport = self.viewport_pixels(target_size);
+        let ndc = Vec2::new(
+            (point.x - viewport.x) / viewport.width * 2. - 1.,
+            1. - (point.y - viewport.y) / viewport.height * 2.,
+        );
+
+        self.view_projection_matrix()
+            .inverse()
+            .transform_point3(ndc.extend(0.))
+            .truncate()
+    }
+
+    /// Convert world coordinates to a position in pixels on a render target
+    /// of `target_size`.
+    pub fn world_to_screen(&self, point: Vec2, target_size: Vec2) -> Vec2 {
+        let viewport = self.viewport_pixels(target_size);
+        let ndc = self
+            .view_projection_matrix()
+            .transform_point3(point.extend(0.));
+
+        Vec2::new(
+            viewport.x + (ndc.x + 1.) / 2. * viewport.width,
+            viewport.y + (1. - ndc.y) / 2. * viewport.height,
+        )
+    }
+
+    fn visible_size(&self) -> Vec2 {
+        self.size / self.zoom
+    }
+
+    fn clamp_to_bounds(&mut self) {
+        let Some(bounds) = self.bounds else { return };
+        let half = self.visible_size() / 2.;
+        let min = bounds.position() + half;
+        let max = bounds.position() + Vec2::new(bounds.width, bounds.height) - half;
+
+        self.center = Vec2::new(
+            clamp_or_center(self.center.x, min.x, max.x),
+            clamp_or_center(self.center.y, min.y, max.y),
+        );
+    }
+
+    fn update_uniform(&self) {
+        self.ctx.queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[self.view_projection_matrix()]),
+        );
+    }
+}
+
+impl Shake {
+    /// Pseudo-random value in `-1..1` (xorshift).
+    fn next_random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+
+        self.seed as f32 / u32::MAX as f32 * 2. - 1.
+    }
+}
+
+fn clamp_or_center(value: f32, min: f32, max: f32) -> f32 {
+    if min > max {
+        (min + max) / 2.
+    } else {
+        value.clamp(min, max)
+    }
+}
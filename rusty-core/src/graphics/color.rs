@@ -1,40 +1,490 @@
-use derive_more::From;
-
-/// Describe color as RGB format
-#[derive(From, Clone, Copy, Debug)]
-pub struct Color(u8, u8, u8);
-
-#[allow(dead_code)]
-pub const BLACK: Color = Color(0, 0, 0);
-#[allow(dead_code)]
-pub const WHITE: Color = Color(255, 255, 255);
-#[allow(dead_code)]
-pub const RED: Color = Color(255, 0, 0);
-#[allow(dead_code)]
-pub const GREEN: Color = Color(0, 255, 0);
-#[allow(dead_code)]
-pub const BLUE: Color = Color(0, 0, 255);
+use std::{fmt, str::FromStr};
 
-impl From<Color> for [f32; 4] {
-    fn from(val: Color) -> Self {
+/// RGBA color with 8 bits per channel, in the sRGB color space.
+///
+/// This is the space colors are usually picked in (image editors, CSS hex
+/// codes). Use [`Color::to_linear`] to get the values shaders work with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "ColorRepr")
+)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+pub const BLACK: Color = Color::rgb(0, 0, 0);
+pub const WHITE: Color = Color::rgb(255, 255, 255);
+pub const GRAY: Color = Color::rgb(128, 128, 128);
+pub const DARK_GRAY: Color = Color::rgb(64, 64, 64);
+pub const LIGHT_GRAY: Color = Color::rgb(192, 192, 192);
+pub const RED: Color = Color::rgb(255, 0, 0);
+pub const GREEN: Color = Color::rgb(0, 255, 0);
+pub const BLUE: Color = Color::rgb(0, 0, 255);
+pub const YELLOW: Color = Color::rgb(255, 255, 0);
+pub const CYAN: Color = Color::rgb(0, 255, 255);
+pub const MAGENTA: Color = Color::rgb(255, 0, 255);
+pub const ORANGE: Color = Color::rgb(255, 165, 0);
+pub const PURPLE: Color = Color::rgb(128, 0, 128);
+pub const PINK: Color = Color::rgb(255, 192, 203);
+pub const BROWN: Color = Color::rgb(165, 42, 42);
+pub const MAROON: Color = Color::rgb(128, 0, 0);
+pub const OLIVE: Color = Color::rgb(128, 128, 0);
+pub const LIME: Color = Color::rgb(50, 205, 50);
+pub const TEAL: Color = Color::rgb(0, 128, 128);
+pub const NAVY: Color = Color::rgb(0, 0, 128);
+pub const SKY_BLUE: Color = Color::rgb(135, 206, 235);
+pub const GOLD: Color = Color::rgb(255, 215, 0);
+
+impl Color {
+    /// Opaque color.
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Create a color from sRGB channels in `0..=1`, out of range values are
+    /// clamped.
+    pub fn from_f32(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self {
+            r: to_u8(r),
+            g: to_u8(g),
+            b: to_u8(b),
+            a: to_u8(a),
+        }
+    }
+
+    /// sRGB channels in `0..=1`.
+    pub fn to_f32(self) -> [f32; 4] {
         [
-            (val.0 / 255) as f32,
-            (val.1 / 255) as f32,
-            (val.2 / 255) as f32,
-            1.0,
+            self.r as f32 / 255.,
+            self.g as f32 / 255.,
+            self.b as f32 / 255.,
+            self.a as f32 / 255.,
         ]
     }
+
+    /// Create a color from linear channels in `0..=1`, alpha being left
+    /// untouched.
+    pub fn from_linear([r, g, b, a]: [f32; 4]) -> Self {
+        Self::from_f32(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b), a)
+    }
+
+    /// Linear channels in `0..=1`, as expected by shaders writing to an sRGB
+    /// render target. Alpha is already linear and left untouched.
+    pub fn to_linear(self) -> [f32; 4] {
+        let [r, g, b, a] = self.to_f32();
+
+        [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a]
+    }
+
+    /// Create an opaque color from its hue, saturation and value.
+    ///
+    /// # Arguments
+    ///
+    /// * `hue` - Angle on the color wheel in degrees, wrapped to `0..360`
+    /// * `saturation` - `0..=1`
+    /// * `value` - `0..=1`
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let chroma = value * saturation;
+
+        from_hue(hue, chroma, value - chroma)
+    }
+
+    /// Create an opaque color from its hue, saturation and lightness.
+    ///
+    /// # Arguments
+    ///
+    /// * `hue` - Angle on the color wheel in degrees, wrapped to `0..360`
+    /// * `saturation` - `0..=1`
+    /// * `lightness` - `0..=1`
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let chroma = (1. - (2. * lightness - 1.).abs()) * saturation;
+
+        from_hue(hue, chroma, lightness - chroma / 2.)
+    }
+
+    /// Hue in degrees, saturation and value in `0..=1`.
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let [r, g, b, _] = self.to_f32();
+        let max = r.max(g).max(b);
+        let chroma = max - r.min(g).min(b);
+        let saturation = if max == 0. { 0. } else { chroma / max };
+
+        (hue(r, g, b, max, chroma), saturation, max)
+    }
+
+    /// Hue in degrees, saturation and lightness in `0..=1`.
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let [r, g, b, _] = self.to_f32();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let lightness = (max + min) / 2.;
+        let saturation = if lightness == 0. || lightness == 1. {
+            0.
+        } else {
+            chroma / (1. - (2. * lightness - 1.).abs())
+        };
+
+        (hue(r, g, b, max, chroma), saturation, lightness)
+    }
+
+    /// Parse a color written as `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, the
+    /// `#` being optional.
+    pub fn from_hex(hex: &str) -> Result<Self, ParseColorError> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        // Also rejects the signs accepted by `from_str_radix`, and characters
+        // which could not be sliced at byte offsets
+        if !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(ParseColorError::InvalidDigit);
+        }
+        let channel = |i: usize, len: usize| {
+            let value = u8::from_str_radix(&digits[i * len..(i + 1) * len], 16)
+                .map_err(|_| ParseColorError::InvalidDigit)?;
+
+            // #f80 is a shorthand for #ff8800
+            Ok(if len == 1 { value * 17 } else { value })
+        };
+
+        match digits.len() {
+            3 | 4 => Ok(Self {
+                r: channel(0, 1)?,
+                g: channel(1, 1)?,
+                b: channel(2, 1)?,
+                a: if digits.len() == 4 {
+                    channel(3, 1)?
+                } else {
+                    255
+                },
+            }),
+            6 | 8 => Ok(Self {
+                r: channel(0, 2)?,
+                g: channel(1, 2)?,
+                b: channel(2, 2)?,
+                a: if digits.len() == 8 {
+                    channel(3, 2)?
+                } else {
+                    255
+                },
+            }),
+            len => Err(ParseColorError::InvalidLength(len)),
+        }
+    }
+
+    /// `#rrggbbaa` representation of the color.
+    pub fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+    }
+
+    /// Same color with another alpha.
+    pub fn with_alpha(self, a: u8) -> Self {
+        Self { a, ..self }
+    }
+
+    /// Interpolate between `self` and `other`, `t` being clamped to `0..=1`.
+    ///
+    /// Channels are interpolated in sRGB space, like most image editors do.
+    pub fn lerp(self, other: Color, t: f32) -> Self {
+        let t = t.clamp(0., 1.);
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+
+        Self {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+            a: channel(self.a, other.a),
+        }
+    }
+
+    /// Color with its channels multiplied by its alpha, for premultiplied
+    /// alpha blending.
+    pub fn premultiply(self) -> Self {
+        let channel = |c: u8| (c as u16 * self.a as u16 + 127) / 255;
+
+        Self {
+            r: channel(self.r) as u8,
+            g: channel(self.g) as u8,
+            b: channel(self.b) as u8,
+            a: self.a,
+        }
+    }
+
+    /// Reverse [`Color::premultiply`]. Fully transparent colors stay black.
+    pub fn unpremultiply(self) -> Self {
+        if self.a == 0 {
+            return TRANSPARENT;
+        }
+        let channel = |c: u8| ((c as u16 * 255 + self.a as u16 / 2) / self.a as u16).min(255);
+
+        Self {
+            r: channel(self.r) as u8,
+            g: channel(self.g) as u8,
+            b: channel(self.b) as u8,
+            a: self.a,
+        }
+    }
+}
+
+/// Error returned when parsing a hex color fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseColorError {
+    /// The number of digits is not 3, 4, 6 or 8.
+    InvalidLength(usize),
+    InvalidDigit,
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidLength(len) => {
+                write!(f, "expected 3, 4, 6 or 8 hexadecimal digits, got {len}")
+            }
+            Self::InvalidDigit => write!(f, "invalid hexadecimal digit"),
+        }
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Self::rgb(r, g, b)
+    }
+}
+
+impl From<(u8, u8, u8, u8)> for Color {
+    fn from((r, g, b, a): (u8, u8, u8, u8)) -> Self {
+        Self::rgba(r, g, b, a)
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    fn from(val: Color) -> Self {
+        val.to_f32()
+    }
+}
+
+/// Clear colors are written as is to the target, so they are converted to
+/// linear space like shader outputs.
+impl From<Color> for wgpu::Color {
+    fn from(val: Color) -> Self {
+        let [r, g, b, a] = val.to_linear();
+
+        Self {
+            r: r as f64,
+            g: g as f64,
+            b: b as f64,
+            a: a as f64,
+        }
+    }
+}
+
+/// Convert an sRGB channel in `0..=1` to linear space.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Convert a linear channel in `0..=1` to sRGB space.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1. / 2.4) - 0.055
+    }
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0., 1.) * 255.).round() as u8
+}
+
+fn from_hue(hue: f32, chroma: f32, m: f32) -> Color {
+    let sector = hue.rem_euclid(360.) / 60.;
+    let x = chroma * (1. - (sector % 2. - 1.).abs());
+    let (r, g, b) = match sector as u8 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    };
+
+    Color::from_f32(r + m, g + m, b + m, 1.)
+}
+
+fn hue(r: f32, g: f32, b: f32, max: f32, chroma: f32) -> f32 {
+    if chroma == 0. {
+        0.
+    } else if max == r {
+        (60. * (g - b) / chroma).rem_euclid(360.)
+    } else if max == g {
+        60. * (b - r) / chroma + 120.
+    } else {
+        60. * (r - g) / chroma + 240.
+    }
+}
+
+/// Accepted serialized forms: a hex string or the channels, alpha being
+/// optional.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum ColorRepr {
+    Hex(String),
+    Channels {
+        r: u8,
+        g: u8,
+        b: u8,
+        #[serde(default = "opaque")]
+        a: u8,
+    },
+}
+
+#[cfg(feature = "serde")]
+fn opaque() -> u8 {
+    255
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ColorRepr> for Color {
+    type Error = ParseColorError;
+
+    fn try_from(repr: ColorRepr) -> Result<Self, Self::Error> {
+        match repr {
+            ColorRepr::Hex(hex) => Self::from_hex(&hex),
+            ColorRepr::Channels { r, g, b, a } => Ok(Self { r, g, b, a }),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Color;
+    use super::*;
 
     #[test]
     fn into_accepted_wgsl_color_format() {
-        let color: [f32; 4] = Color(255, 255, 255).into();
+        let color: [f32; 4] = Color::rgb(255, 255, 255).into();
         assert_eq!(color, [1., 1., 1., 1.0]);
-        let color: [f32; 4] = Color(155, 155, 155).into();
-        assert_eq!(color, [0., 0., 0., 1.]);
+        let color: [f32; 4] = Color::rgba(155, 0, 51, 0).into();
+        assert_eq!(color, [155. / 255., 0., 0.2, 0.]);
+    }
+
+    #[test]
+    fn srgb_linear_round_trip() {
+        assert_eq!(BLACK.to_linear(), [0., 0., 0., 1.]);
+        assert_eq!(WHITE.to_linear(), [1., 1., 1., 1.]);
+        // Mid gray is much darker in linear space
+        assert!((GRAY.to_linear()[0] - 0.2158).abs() < 1e-3);
+
+        for value in 0..=255 {
+            let color = Color::rgba(value, value, value, value);
+            assert_eq!(Color::from_linear(color.to_linear()), color);
+        }
+    }
+
+    #[test]
+    fn hsv_and_hsl() {
+        assert_eq!(Color::from_hsv(0., 1., 1.), RED);
+        assert_eq!(Color::from_hsv(120., 1., 1.), GREEN);
+        assert_eq!(Color::from_hsv(-120., 1., 1.), BLUE);
+        assert_eq!(Color::from_hsv(60., 0., 1.), WHITE);
+        assert_eq!(Color::from_hsl(240., 1., 0.5), BLUE);
+        assert_eq!(Color::from_hsl(0., 0., 1.), WHITE);
+        assert_eq!(Color::from_hsl(39., 1., 0.5), Color::rgb(255, 166, 0));
+
+        let (h, s, v) = ORANGE.to_hsv();
+        assert_eq!(Color::from_hsv(h, s, v), ORANGE);
+        let (h, s, l) = SKY_BLUE.to_hsl();
+        assert_eq!(Color::from_hsl(h, s, l), SKY_BLUE);
+    }
+
+    #[test]
+    fn parse_hex() {
+        assert_eq!(
+            "#ff8800cc".parse::<Color>(),
+            Ok(Color::rgba(255, 136, 0, 204))
+        );
+        assert_eq!(Color::from_hex("ff8800"), Ok(Color::rgb(255, 136, 0)));
+        assert_eq!(Color::from_hex("#f80"), Ok(Color::rgb(255, 136, 0)));
+        assert_eq!(Color::from_hex("#f80c"), Ok(Color::rgba(255, 136, 0, 204)));
+        assert_eq!(
+            Color::from_hex("#ff88"),
+            Ok(Color::rgba(255, 255, 136, 136))
+        );
+        assert_eq!(
+            Color::from_hex("#ff880"),
+            Err(ParseColorError::InvalidLength(5))
+        );
+        assert_eq!(
+            Color::from_hex("#gg8800"),
+            Err(ParseColorError::InvalidDigit)
+        );
+        assert_eq!(Color::from_hex("#é80"), Err(ParseColorError::InvalidDigit));
+        assert_eq!(
+            Color::from_hex("#+f8800"),
+            Err(ParseColorError::InvalidDigit)
+        );
+        assert_eq!(Color::from_hex("+fff"), Err(ParseColorError::InvalidDigit));
+        assert_eq!(Color::rgba(255, 136, 0, 204).to_hex(), "#ff8800cc");
+    }
+
+    #[test]
+    fn lerp() {
+        assert_eq!(BLACK.lerp(WHITE, 0.), BLACK);
+        assert_eq!(BLACK.lerp(WHITE, 1.), WHITE);
+        assert_eq!(BLACK.lerp(WHITE, 0.5), Color::rgb(128, 128, 128));
+        assert_eq!(RED.lerp(TRANSPARENT, 2.), TRANSPARENT);
+    }
+
+    #[test]
+    fn premultiplied_alpha() {
+        let color = Color::rgba(255, 128, 0, 128);
+        assert_eq!(color.premultiply(), Color::rgba(128, 64, 0, 128));
+        assert_eq!(color.premultiply().unpremultiply(), color);
+        assert_eq!(WHITE.premultiply(), WHITE);
+        assert_eq!(TRANSPARENT.unpremultiply(), TRANSPARENT);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize() {
+        assert_eq!(
+            ron::from_str::<Color>("\"#ff8800\""),
+            Ok(Color::rgb(255, 136, 0))
+        );
+        assert_eq!(
+            ron::from_str::<Color>("(r: 255, g: 136, b: 0)"),
+            Ok(Color::rgb(255, 136, 0))
+        );
+
+        let color = Color::rgba(1, 2, 3, 4);
+        assert_eq!(ron::from_str(&ron::to_string(&color).unwrap()), Ok(color));
     }
 }
@@ -0,0 +1,510 @@
+//! Textures compressed ahead of time, read from KTX2 or DDS containers and
+//! uploaded to the GPU without decoding.
+
+#[cfg(feature = "dds")]
+use super::texture::DDS_MAGIC;
+use crate::{Error, Result};
+
+/// Mipmap levels of a texture stored in a GPU format.
+#[derive(Debug)]
+pub(super) struct CompressedImage<'a> {
+    pub format: wgpu::TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Levels from the biggest one, with tightly packed rows of blocks.
+    pub levels: Vec<&'a [u8]>,
+}
+
+impl CompressedImage<'_> {
+    /// Size of the level at `mip_level`, rounded up to whole blocks.
+    pub fn level_size(&self, mip_level: u32) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        }
+        .mip_level_size(mip_level, wgpu::TextureDimension::D2)
+        .physical_size(self.format)
+    }
+
+    /// Number of bytes of a row of blocks of the level at `mip_level`.
+    pub fn bytes_per_row(&self, mip_level: u32) -> u32 {
+        let (block_width, _) = self.format.block_dimensions();
+        let block_size = self.format.block_copy_size(None).unwrap_or(0);
+
+        self.level_size(mip_level).width / block_width * block_size
+    }
+
+    /// Number of rows of blocks of the level at `mip_level`.
+    pub fn rows(&self, mip_level: u32) -> u32 {
+        let (_, block_height) = self.format.block_dimensions();
+
+        self.level_size(mip_level).height / block_height
+    }
+
+    fn level_len(&self, mip_level: u32) -> usize {
+        self.bytes_per_row(mip_level) as usize * self.rows(mip_level) as usize
+    }
+
+    /// Check that the texture can be created, on a device whose textures are
+    /// at most `max_dimension` pixels wide and high, and that every level
+    /// holds enough data.
+    fn validate(self, max_dimension: u32) -> Result<Self> {
+        check_size(
+            self.width,
+            self.height,
+            self.levels.len() as u32,
+            max_dimension,
+        )?;
+        let (block_width, block_height) = self.format.block_dimensions();
+        if self.width == 0
+            || self.height == 0
+            || !self.width.is_multiple_of(block_width)
+            || !self.height.is_multiple_of(block_height)
+        {
+            return Err(Error::InvalidTexture(format!(
+                "{}x{} is not a multiple of the {block_width}x{block_height} blocks of {:?}",
+                self.width, self.height, self.format
+            )));
+        }
+        for (mip_level, level) in self.levels.iter().enumerate() {
+            let expected = self.level_len(mip_level as u32);
+            if level.len() < expected {
+                return Err(Error::InvalidTexture(format!(
+                    "mipmap level {mip_level} holds {} bytes instead of {expected}",
+                    level.len()
+                )));
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+/// Check the size and number of mipmap levels read from a container before
+/// anything is allocated from them.
+fn check_size(width: u32, height: u32, mip_levels: u32, max_dimension: u32) -> Result<()> {
+    if width > max_dimension || height > max_dimension {
+        return Err(Error::InvalidTexture(format!(
+            "{width}x{height} is larger than the {max_dimension}x{max_dimension} limit of the device"
+        )));
+    }
+    let max_mips = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    }
+    .max_mips(wgpu::TextureDimension::D2);
+    if mip_levels > max_mips {
+        return Err(Error::InvalidTexture(format!(
+            "{mip_levels} mipmap levels for a {width}x{height} texture, which has at most {max_mips}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Read a 2D texture from a KTX2 container, for a device whose textures are
+/// at most `max_dimension` pixels wide and high.
+///
+/// Supercompressed textures, such as Basis Universal ones, are not
+/// supported.
+#[cfg(feature = "ktx2")]
+pub(super) fn parse_ktx2(bytes: &[u8], max_dimension: u32) -> Result<CompressedImage<'_>> {
+    let reader =
+        ktx2::Reader::new(bytes).map_err(|err| Error::InvalidTexture(format!("KTX2: {err}")))?;
+    let header = reader.header();
+
+    if let Some(scheme) = header.supercompression_scheme {
+        return Err(Error::UnsupportedFormat(format!(
+            "KTX2 supercompressed with {scheme:?}"
+        )));
+    }
+    if header.pixel_depth > 1 || header.layer_count > 1 || header.face_count > 1 {
+        return Err(Error::UnsupportedFormat(
+            "KTX2 3D, array or cube texture".to_owned(),
+        ));
+    }
+    let format = header
+        .format
+        .and_then(ktx2_format)
+        .ok_or_else(|| Error::UnsupportedFormat(format!("KTX2 {:?}", header.format)))?;
+
+    let (width, height) = (header.pixel_width, header.pixel_height.max(1));
+    // The level count is 0 when the loader is asked to generate mipmaps
+    let level_count = header.level_count.max(1);
+    check_size(width, height, level_count, max_dimension)?;
+
+    // Levels are sliced from `bytes` rather than the reader so they outlive
+    // it, the reader checked that the index and the levels are in bounds
+    let levels = bytes[ktx2::Header::LENGTH..]
+        .chunks_exact(ktx2::LevelIndex::LENGTH)
+        .take(level_count as usize)
+        .map(|index| {
+            let index = ktx2::LevelIndex::from_bytes(index.try_into().unwrap());
+            let start = index.byte_offset as usize;
+
+            &bytes[start..start + index.byte_length as usize]
+        })
+        .collect();
+
+    CompressedImage {
+        format,
+        width,
+        height,
+        levels,
+    }
+    .validate(max_dimension)
+}
+
+#[cfg(feature = "ktx2")]
+fn ktx2_format(format: ktx2::Format) -> Option<wgpu::TextureFormat> {
+    use ktx2::Format as Vk;
+    use wgpu::{AstcBlock, AstcChannel, TextureFormat as Tf};
+
+    let format = match format {
+        Vk::R8G8B8A8_UNORM => Tf::Rgba8Unorm,
+        Vk::R8G8B8A8_SRGB => Tf::Rgba8UnormSrgb,
+        Vk::B8G8R8A8_UNORM => Tf::Bgra8Unorm,
+        Vk::B8G8R8A8_SRGB => Tf::Bgra8UnormSrgb,
+        Vk::BC1_RGB_UNORM_BLOCK | Vk::BC1_RGBA_UNORM_BLOCK => Tf::Bc1RgbaUnorm,
+        Vk::BC1_RGB_SRGB_BLOCK | Vk::BC1_RGBA_SRGB_BLOCK => Tf::Bc1RgbaUnormSrgb,
+        Vk::BC2_UNORM_BLOCK => Tf::Bc2RgbaUnorm,
+        Vk::BC2_SRGB_BLOCK => Tf::Bc2RgbaUnormSrgb,
+        Vk::BC3_UNORM_BLOCK => Tf::Bc3RgbaUnorm,
+        Vk::BC3_SRGB_BLOCK => Tf::Bc3RgbaUnormSrgb,
+        Vk::BC4_UNORM_BLOCK => Tf::Bc4RUnorm,
+        Vk::BC4_SNORM_BLOCK => Tf::Bc4RSnorm,
+        Vk::BC5_UNORM_BLOCK => Tf::Bc5RgUnorm,
+        Vk::BC5_SNORM_BLOCK => Tf::Bc5RgSnorm,
+        Vk::BC6H_UFLOAT_BLOCK => Tf::Bc6hRgbUfloat,
+        Vk::BC6H_SFLOAT_BLOCK => Tf::Bc6hRgbFloat,
+        Vk::BC7_UNORM_BLOCK => Tf::Bc7RgbaUnorm,
+        Vk::BC7_SRGB_BLOCK => Tf::Bc7RgbaUnormSrgb,
+        Vk::ETC2_R8G8B8_UNORM_BLOCK => Tf::Etc2Rgb8Unorm,
+        Vk::ETC2_R8G8B8_SRGB_BLOCK => Tf::Etc2Rgb8UnormSrgb,
+        Vk::ETC2_R8G8B8A1_UNORM_BLOCK => Tf::Etc2Rgb8A1Unorm,
+        Vk::ETC2_R8G8B8A1_SRGB_BLOCK => Tf::Etc2Rgb8A1UnormSrgb,
+        Vk::ETC2_R8G8B8A8_UNORM_BLOCK => Tf::Etc2Rgba8Unorm,
+        Vk::ETC2_R8G8B8A8_SRGB_BLOCK => Tf::Etc2Rgba8UnormSrgb,
+        Vk::EAC_R11_UNORM_BLOCK => Tf::EacR11Unorm,
+        Vk::EAC_R11_SNORM_BLOCK => Tf::EacR11Snorm,
+        Vk::EAC_R11G11_UNORM_BLOCK => Tf::EacRg11Unorm,
+        Vk::EAC_R11G11_SNORM_BLOCK => Tf::EacRg11Snorm,
+        _ => {
+            // ASTC formats come in pairs of UNORM and SRGB, ordered by block
+            // size
+            const BLOCKS: [AstcBlock; 14] = [
+                AstcBlock::B4x4,
+                AstcBlock::B5x4,
+                AstcBlock::B5x5,
+                AstcBlock::B6x5,
+                AstcBlock::B6x6,
+                AstcBlock::B8x5,
+                AstcBlock::B8x6,
+                AstcBlock::B8x8,
+                AstcBlock::B10x5,
+                AstcBlock::B10x6,
+                AstcBlock::B10x8,
+                AstcBlock::B10x10,
+                AstcBlock::B12x10,
+                AstcBlock::B12x12,
+            ];
+            let index = format
+                .value()
+                .checked_sub(Vk::ASTC_4x4_UNORM_BLOCK.value())?;
+            let block = *BLOCKS.get(index as usize / 2)?;
+            let channel = if index % 2 == 0 {
+                AstcChannel::Unorm
+            } else {
+                AstcChannel::UnormSrgb
+            };
+
+            Tf::Astc { block, channel }
+        }
+    };
+
+    Some(format)
+}
+
+/// Size of the magic number and the header of DDS files.
+#[cfg(feature = "dds")]
+const DDS_HEADER_LEN: usize = 128;
+/// Size of the header extension of DDS files using DXGI formats.
+#[cfg(feature = "dds")]
+const DX10_HEADER_LEN: usize = 20;
+
+/// Read a 2D texture from a DDS container, for a device whose textures are
+/// at most `max_dimension` pixels wide and high.
+///
+/// Formats identified by a FourCC code do not say whether they hold sRGB
+/// colors, they follow `srgb` instead.
+#[cfg(feature = "dds")]
+pub(super) fn parse_dds(
+    bytes: &[u8],
+    srgb: bool,
+    max_dimension: u32,
+) -> Result<CompressedImage<'_>> {
+    let u32_at = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or_else(|| Error::InvalidTexture("DDS: unexpected end of file".to_owned()))
+    };
+    if !bytes.starts_with(&DDS_MAGIC) || u32_at(4)? != 124 {
+        return Err(Error::InvalidTexture("DDS: bad header".to_owned()));
+    }
+
+    const CUBEMAP: u32 = 0x200;
+    const VOLUME: u32 = 0x200000;
+    if u32_at(112)? & (CUBEMAP | VOLUME) != 0 {
+        return Err(Error::UnsupportedFormat(
+            "DDS volume or cube texture".to_owned(),
+        ));
+    }
+
+    let (height, width, mip_levels) = (u32_at(12)?, u32_at(16)?, u32_at(28)?.max(1));
+    check_size(width, height, mip_levels, max_dimension)?;
+    let four_cc = u32_at(84)?.to_le_bytes();
+    let (format, data_offset) = if &four_cc == b"DX10" {
+        if u32_at(DDS_HEADER_LEN + 12)? > 1 {
+            return Err(Error::UnsupportedFormat("DDS texture array".to_owned()));
+        }
+        let dxgi_format = u32_at(DDS_HEADER_LEN)?;
+        let format = dxgi_format_to_wgpu(dxgi_format)
+            .ok_or_else(|| Error::UnsupportedFormat(format!("DDS DXGI format {dxgi_format}")))?;
+
+        (format, DDS_HEADER_LEN + DX10_HEADER_LEN)
+    } else {
+        let format = four_cc_to_wgpu(&four_cc).ok_or_else(|| {
+            Error::UnsupportedFormat(format!(
+                "DDS FourCC {:?}",
+                String::from_utf8_lossy(&four_cc)
+            ))
+        })?;
+        let format = if srgb {
+            format.add_srgb_suffix()
+        } else {
+            format
+        };
+
+        (format, DDS_HEADER_LEN)
+    };
+
+    let mut image = CompressedImage {
+        format,
+        width,
+        height,
+        levels: Vec::with_capacity(mip_levels as usize),
+    };
+    let mut data = bytes.get(data_offset..).unwrap_or_default();
+    for mip_level in 0..mip_levels {
+        let (level, rest) = data.split_at(image.level_len(mip_level).min(data.len()));
+        image.levels.push(level);
+        data = rest;
+    }
+
+    image.validate(max_dimension)
+}
+
+#[cfg(feature = "dds")]
+fn four_cc_to_wgpu(four_cc: &[u8; 4]) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat as Tf;
+
+    let format = match four_cc {
+        b"DXT1" => Tf::Bc1RgbaUnorm,
+        b"DXT2" | b"DXT3" => Tf::Bc2RgbaUnorm,
+        b"DXT4" | b"DXT5" => Tf::Bc3RgbaUnorm,
+        b"ATI1" | b"BC4U" => Tf::Bc4RUnorm,
+        b"BC4S" => Tf::Bc4RSnorm,
+        b"ATI2" | b"BC5U" => Tf::Bc5RgUnorm,
+        b"BC5S" => Tf::Bc5RgSnorm,
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+#[cfg(feature = "dds")]
+fn dxgi_format_to_wgpu(format: u32) -> Option<wgpu::TextureFormat> {
+    use wgpu::TextureFormat as Tf;
+
+    let format = match format {
+        28 => Tf::Rgba8Unorm,
+        29 => Tf::Rgba8UnormSrgb,
+        71 => Tf::Bc1RgbaUnorm,
+        72 => Tf::Bc1RgbaUnormSrgb,
+        74 => Tf::Bc2RgbaUnorm,
+        75 => Tf::Bc2RgbaUnormSrgb,
+        77 => Tf::Bc3RgbaUnorm,
+        78 => Tf::Bc3RgbaUnormSrgb,
+        80 => Tf::Bc4RUnorm,
+        81 => Tf::Bc4RSnorm,
+        83 => Tf::Bc5RgUnorm,
+        84 => Tf::Bc5RgSnorm,
+        87 => Tf::Bgra8Unorm,
+        91 => Tf::Bgra8UnormSrgb,
+        95 => Tf::Bc6hRgbUfloat,
+        96 => Tf::Bc6hRgbFloat,
+        98 => Tf::Bc7RgbaUnorm,
+        99 => Tf::Bc7RgbaUnormSrgb,
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+#[cfg(test)]
+mod tests {
+    /// Largest texture of the default limits of wgpu.
+    const MAX_DIMENSION: u32 = 8192;
+
+    /// DDS file of `width` x `height` pixels described by `four_cc`,
+    /// followed by `data`.
+    #[cfg(feature = "dds")]
+    fn dds(four_cc: &[u8; 4], width: u32, height: u32, mip_levels: u32, data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0; super::DDS_HEADER_LEN];
+        bytes[..4].copy_from_slice(&super::DDS_MAGIC);
+        for (offset, value) in [
+            (4, 124),
+            (12, height),
+            (16, width),
+            (28, mip_levels),
+            (76, 32),
+        ] {
+            bytes[offset..offset + 4].copy_from_slice(&u32::to_le_bytes(value));
+        }
+        bytes[84..88].copy_from_slice(four_cc);
+        bytes.extend_from_slice(data);
+
+        bytes
+    }
+
+    /// KTX2 file of `width` x `height` pixels in `vk_format`, with one
+    /// mipmap level per entry of `levels`.
+    #[cfg(feature = "ktx2")]
+    fn ktx2(vk_format: u32, width: u32, height: u32, levels: &[&[u8]]) -> Vec<u8> {
+        const HEADER_LEN: usize = 80;
+        const LEVEL_INDEX_LEN: usize = 24;
+        let dfd_offset = HEADER_LEN + LEVEL_INDEX_LEN * levels.len();
+
+        let mut bytes = crate::graphics::texture::KTX2_MAGIC.to_vec();
+        let level_count = levels.len() as u32;
+        for value in [vk_format, 1, width, height, 0, 0, 1, level_count, 0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        // Data format descriptor holding only its own size, no key/value
+        // data nor supercompression data
+        for value in [dfd_offset as u32, 4, 0, 0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0; 16]);
+
+        let mut offset = dfd_offset + 4;
+        for level in levels {
+            for value in [offset, level.len(), level.len()] {
+                bytes.extend_from_slice(&(value as u64).to_le_bytes());
+            }
+            offset += level.len();
+        }
+        bytes.extend_from_slice(&4u32.to_le_bytes());
+        for level in levels {
+            bytes.extend_from_slice(level);
+        }
+
+        bytes
+    }
+
+    #[cfg(feature = "dds")]
+    #[test]
+    fn dds_levels_are_split_by_block_size() {
+        // 8x8 BC1 image with its 4x4, 2x2 and 1x1 mipmaps, all blocks
+        // being 8 bytes
+        let bytes = dds(b"DXT1", 8, 8, 4, &[0; 4 * 8 + 3 * 8]);
+        let image = super::parse_dds(&bytes, true, MAX_DIMENSION).unwrap();
+
+        assert_eq!(image.format, wgpu::TextureFormat::Bc1RgbaUnormSrgb);
+        assert_eq!(
+            image
+                .levels
+                .iter()
+                .map(|level| level.len())
+                .collect::<Vec<_>>(),
+            [32, 8, 8, 8]
+        );
+        assert_eq!(image.bytes_per_row(0), 16);
+        assert_eq!(image.rows(3), 1);
+
+        let image = super::parse_dds(&bytes, false, MAX_DIMENSION).unwrap();
+        assert_eq!(image.format, wgpu::TextureFormat::Bc1RgbaUnorm);
+    }
+
+    #[cfg(feature = "dds")]
+    #[test]
+    fn invalid_dds_is_rejected() {
+        use crate::Error;
+
+        let truncated = dds(b"DXT5", 8, 8, 1, &[0; 16]);
+        assert!(matches!(
+            super::parse_dds(&truncated, true, MAX_DIMENSION),
+            Err(Error::InvalidTexture(_))
+        ));
+
+        let unaligned = dds(b"DXT1", 6, 4, 1, &[0; 16]);
+        assert!(matches!(
+            super::parse_dds(&unaligned, true, MAX_DIMENSION),
+            Err(Error::InvalidTexture(_))
+        ));
+
+        // Checked before allocating the levels
+        let endless = dds(b"DXT1", 8, 8, u32::MAX, &[0; 32]);
+        assert!(matches!(
+            super::parse_dds(&endless, true, MAX_DIMENSION),
+            Err(Error::InvalidTexture(_))
+        ));
+
+        let huge = dds(b"DXT1", 2 * MAX_DIMENSION, 4, 1, &[0; 16]);
+        assert!(matches!(
+            super::parse_dds(&huge, true, MAX_DIMENSION),
+            Err(Error::InvalidTexture(_))
+        ));
+
+        let unknown = dds(b"RXGB", 4, 4, 1, &[0; 16]);
+        assert!(matches!(
+            super::parse_dds(&unknown, true, MAX_DIMENSION),
+            Err(Error::UnsupportedFormat(_))
+        ));
+    }
+
+    #[cfg(feature = "ktx2")]
+    #[test]
+    fn ktx2_formats_map_to_wgpu() {
+        // VK_FORMAT_BC7_SRGB_BLOCK
+        let bytes = ktx2(146, 4, 4, &[&[0; 16]]);
+        let image = super::parse_ktx2(&bytes, MAX_DIMENSION).unwrap();
+        assert_eq!(image.format, wgpu::TextureFormat::Bc7RgbaUnormSrgb);
+        assert_eq!(image.levels, [&[0; 16]]);
+
+        // VK_FORMAT_ASTC_6x6_SRGB_BLOCK
+        let bytes = ktx2(166, 6, 6, &[&[0; 16]]);
+        assert_eq!(
+            super::parse_ktx2(&bytes, MAX_DIMENSION).unwrap().format,
+            wgpu::TextureFormat::Astc {
+                block: wgpu::AstcBlock::B6x6,
+                channel: wgpu::AstcChannel::UnormSrgb,
+            }
+        );
+
+        // VK_FORMAT_R16G16_SFLOAT
+        let bytes = ktx2(83, 1, 1, &[&[0; 4]]);
+        assert!(super::parse_ktx2(&bytes, MAX_DIMENSION).is_err());
+
+        // A 4x4 texture has 3 levels at most
+        let level: &[u8] = &[0; 16];
+        let bytes = ktx2(146, 4, 4, &[level; 4]);
+        assert!(matches!(
+            super::parse_ktx2(&bytes, MAX_DIMENSION),
+            Err(crate::Error::InvalidTexture(_))
+        ));
+    }
+}
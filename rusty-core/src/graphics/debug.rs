@@ -0,0 +1,298 @@
+use std::f32::consts::TAU;
+
+use glam::{Mat4, Vec2};
+
+use super::{
+    batch::{BatchStats, SpriteBatch},
+    color::{self, Color},
+    shape::{Geometry, LineCap, LineJoin, Path, ShapeVertex},
+    Drawable, QUAD_INDICES,
+};
+use crate::{math::Rect, Ctx};
+
+/// Size of a glyph of the built-in font, in font pixels.
+const GLYPH_SIZE: (u32, u32) = (5, 7);
+
+/// Horizontal and vertical distance between glyphs, in font pixels.
+const GLYPH_ADVANCE: Vec2 = Vec2::new(6., 9.);
+
+/// Immediate-mode drawing of lines, shapes and text for debugging.
+///
+/// Primitives are recorded from anywhere during the frame, then drawn in a
+/// single batch by [`DebugDraw::draw`], after which the next recorded
+/// primitive starts a new frame. Draw it last, after the world, so it is
+/// shown on top through the current camera; it can be drawn again in the
+/// same frame, e.g. once per viewport.
+///
+/// ```ignore
+/// debug.rect(player.bounds(), color::GREEN);
+/// debug.line(player.position(), target, color::RED);
+/// debug.text((8., 8.).into(), &format!("{fps:.0} fps"));
+///
+/// renderer.set_camera(&mut render_pass, &camera, renderer.size());
+/// render_pass.draw_mesh(player.mesh());
+/// debug.draw(&mut render_pass);
+/// ```
+pub struct DebugDraw {
+    batch: SpriteBatch,
+    thickness: f32,
+    text_color: Color,
+    text_scale: f32,
+    enabled: bool,
+    drawn: bool,
+}
+
+impl DebugDraw {
+    pub fn new(ctx: &Ctx) -> Self {
+        Self {
+            batch: SpriteBatch::new(ctx),
+            thickness: 1.,
+            text_color: color::WHITE,
+            text_scale: 2.,
+            enabled: true,
+            drawn: false,
+        }
+    }
+
+    /// Ignore every primitive recorded while disabled, e.g. to toggle debug
+    /// drawing with a key.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Set the thickness of lines and outlines, in world units.
+    pub fn set_thickness(&mut self, thickness: f32) {
+        self.thickness = thickness;
+    }
+
+    pub fn thickness(&self) -> f32 {
+        self.thickness
+    }
+
+    pub fn set_text_color(&mut self, color: Color) {
+        self.text_color = color;
+    }
+
+    pub fn text_color(&self) -> Color {
+        self.text_color
+    }
+
+    /// Set the size of a pixel of the built-in font, in world units.
+    pub fn set_text_scale(&mut self, scale: f32) {
+        self.text_scale = scale;
+    }
+
+    pub fn text_scale(&self) -> f32 {
+        self.text_scale
+    }
+
+    pub fn line(&mut self, a: Vec2, b: Vec2, color: Color) {
+        self.stroke(&[a, b], false, color);
+    }
+
+    /// Outline of `rect`.
+    pub fn rect(&mut self, rect: Rect, color: Color) {
+        self.stroke(&corners(rect.position(), size(&rect)), true, color);
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+        if !self.begin() {
+            return;
+        }
+
+        self.quad(rect.position(), size(&rect), color.to_linear());
+    }
+
+    /// Outline of the circle of `radius` around `center`.
+    pub fn circle(&mut self, center: Vec2, radius: f32, color: Color) {
+        if !self.begin() {
+            return;
+        }
+
+        let mut path = Path::new();
+        path.arc(center, radius, 0., TAU);
+        let (points, _) = path.contours().next().unwrap();
+        // The last point closes the arc, the stroke already does
+        self.stroke(&points[..points.len() - 1], true, color);
+    }
+
+    /// Write `text` with its top-left corner at `position`, in the text color.
+    ///
+    /// The built-in font only has printable ASCII characters, others are
+    /// drawn as `?`. Lines are separated by `\n`.
+    pub fn text(&mut self, position: Vec2, text: &str) {
+        if !self.begin() {
+            return;
+        }
+
+        let color = self.text_color.to_linear();
+        let scale = self.text_scale;
+        let mut pen = position;
+        for character in text.chars() {
+            if character == '\n' {
+                pen = Vec2::new(position.x, pen.y + GLYPH_ADVANCE.y * scale);
+                continue;
+            }
+
+            let glyph = glyph(character);
+            for (x, column) in glyph.iter().enumerate() {
+                for y in 0..GLYPH_SIZE.1 {
+                    if column & (1 << y) == 0 {
+                        continue;
+                    }
+
+                    let pixel = pen + Vec2::new(x as f32, y as f32) * scale;
+                    self.quad(pixel, Vec2::splat(scale), color);
+                }
+            }
+            pen.x += GLYPH_ADVANCE.x * scale;
+        }
+    }
+
+    /// Upload what was recorded since the last frame and draw it, in one
+    /// draw call. The next recorded primitive clears it.
+    ///
+    /// Drawing again without recording anything draws the same primitives.
+    pub fn draw<'a, D: Drawable<'a>>(&'a mut self, drawable: &mut D) {
+        if !self.drawn {
+            self.batch.end();
+            self.drawn = true;
+        }
+
+        drawable.draw_batch(&self.batch);
+    }
+
+    /// Forget the recorded primitives, e.g. at the start of a frame which
+    /// may not record any.
+    pub fn clear(&mut self) {
+        self.batch.begin();
+        self.batch.end();
+        self.drawn = true;
+    }
+
+    /// Statistics of the last draw.
+    pub fn stats(&self) -> BatchStats {
+        self.batch.stats()
+    }
+
+    fn stroke(&mut self, points: &[Vec2], closed: bool, color: Color) {
+        if !self.begin() {
+            return;
+        }
+
+        let stroke = Geometry::stroke(
+            points,
+            closed,
+            self.thickness,
+            LineCap::Square,
+            LineJoin::Miter,
+            color.to_linear(),
+        );
+        self.batch
+            .push(None, Mat4::IDENTITY, &stroke.vertices, &stroke.indices);
+    }
+
+    fn quad(&mut self, position: Vec2, size: Vec2, color: [f32; 4]) {
+        let vertices = corners(position, size).map(|corner| ShapeVertex {
+            position: [corner.x, corner.y, 0.],
+            color,
+            tex_coords: [0., 0.],
+        });
+
+        self.batch
+            .push(None, Mat4::IDENTITY, &vertices, &QUAD_INDICES);
+    }
+
+    /// Clear the primitives of the previous frame if they were drawn, and
+    /// tell whether new ones should be recorded.
+    fn begin(&mut self) -> bool {
+        if self.drawn {
+            self.batch.begin();
+            self.drawn = false;
+        }
+
+        self.enabled
+    }
+}
+
+fn size(rect: &Rect) -> Vec2 {
+    Vec2::new(rect.width, rect.height)
+}
+
+/// Corners of a rectangle, clockwise from the top-left one as expected by
+/// [`QUAD_INDICES`].
+fn corners(position: Vec2, size: Vec2) -> [Vec2; 4] {
+    [
+        position,
+        position + Vec2::new(0., size.y),
+        position + size,
+        position + Vec2::new(size.x, 0.),
+    ]
+}
+
+/// Columns of the glyph of `character`, the lowest bit being the top row.
+fn glyph(character: char) -> &'static [u8; 5] {
+    let index = match character {
+        ' '..='~' => character as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+
+    &FONT[index]
+}
+
+/// 5x7 font covering printable ASCII, from space to `~`.
+#[rustfmt::skip]
+const FONT: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5f, 0x00, 0x00], // ' ' !
+    [0x00, 0x07, 0x00, 0x07, 0x00], [0x14, 0x7f, 0x14, 0x7f, 0x14], // " #
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62], // $ %
+    [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00], // & '
+    [0x00, 0x1c, 0x22, 0x41, 0x00], [0x00, 0x41, 0x22, 0x1c, 0x00], // ( )
+    [0x08, 0x2a, 0x1c, 0x2a, 0x08], [0x08, 0x08, 0x3e, 0x08, 0x08], // * +
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], // , -
+    [0x00, 0x60, 0x60, 0x00, 0x00], [0x20, 0x10, 0x08, 0x04, 0x02], // . /
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], [0x00, 0x42, 0x7f, 0x40, 0x00], // 0 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4b, 0x31], // 2 3
+    [0x18, 0x14, 0x12, 0x7f, 0x10], [0x27, 0x45, 0x45, 0x45, 0x39], // 4 5
+    [0x3c, 0x4a, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03], // 6 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1e], // 8 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], [0x00, 0x56, 0x36, 0x00, 0x00], // : ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14], // < =
+    [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], // > ?
+    [0x32, 0x49, 0x79, 0x41, 0x3e], [0x7e, 0x11, 0x11, 0x11, 0x7e], // @ A
+    [0x7f, 0x49, 0x49, 0x49, 0x36], [0x3e, 0x41, 0x41, 0x41, 0x22], // B C
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], [0x7f, 0x49, 0x49, 0x49, 0x41], // D E
+    [0x7f, 0x09, 0x09, 0x01, 0x01], [0x3e, 0x41, 0x41, 0x51, 0x32], // F G
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], [0x00, 0x41, 0x7f, 0x41, 0x00], // H I
+    [0x20, 0x40, 0x41, 0x3f, 0x01], [0x7f, 0x08, 0x14, 0x22, 0x41], // J K
+    [0x7f, 0x40, 0x40, 0x40, 0x40], [0x7f, 0x02, 0x04, 0x02, 0x7f], // L M
+    [0x7f, 0x04, 0x08, 0x10, 0x7f], [0x3e, 0x41, 0x41, 0x41, 0x3e], // N O
+    [0x7f, 0x09, 0x09, 0x09, 0x06], [0x3e, 0x41, 0x51, 0x21, 0x5e], // P Q
+    [0x7f, 0x09, 0x19, 0x29, 0x46], [0x46, 0x49, 0x49, 0x49, 0x31], // R S
+    [0x01, 0x01, 0x7f, 0x01, 0x01], [0x3f, 0x40, 0x40, 0x40, 0x3f], // T U
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], [0x7f, 0x20, 0x18, 0x20, 0x7f], // V W
+    [0x63, 0x14, 0x08, 0x14, 0x63], [0x03, 0x04, 0x78, 0x04, 0x03], // X Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7f, 0x41, 0x41, 0x00], // Z [
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7f, 0x00], // \ ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], [0x40, 0x40, 0x40, 0x40, 0x40], // ^ _
+    [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78], // ` a
+    [0x7f, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], // b c
+    [0x38, 0x44, 0x44, 0x48, 0x7f], [0x38, 0x54, 0x54, 0x54, 0x18], // d e
+    [0x08, 0x7e, 0x09, 0x01, 0x02], [0x08, 0x54, 0x54, 0x54, 0x3c], // f g
+    [0x7f, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7d, 0x40, 0x00], // h i
+    [0x20, 0x40, 0x44, 0x3d, 0x00], [0x7f, 0x10, 0x28, 0x44, 0x00], // j k
+    [0x00, 0x41, 0x7f, 0x40, 0x00], [0x7c, 0x04, 0x18, 0x04, 0x78], // l m
+    [0x7c, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], // n o
+    [0x7c, 0x14, 0x14, 0x14, 0x08], [0x08, 0x14, 0x14, 0x18, 0x7c], // p q
+    [0x7c, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20], // r s
+    [0x04, 0x3f, 0x44, 0x40, 0x20], [0x3c, 0x40, 0x40, 0x20, 0x7c], // t u
+    [0x1c, 0x20, 0x40, 0x20, 0x1c], [0x3c, 0x40, 0x30, 0x40, 0x3c], // v w
+    [0x44, 0x28, 0x10, 0x28, 0x44], [0x0c, 0x50, 0x50, 0x50, 0x3c], // x y
+    [0x44, 0x64, 0x54, 0x4c, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], // z {
+    [0x00, 0x00, 0x7f, 0x00, 0x00], [0x00, 0x41, 0x36, 0x08, 0x00], // | }
+    [0x08, 0x04, 0x08, 0x10, 0x08],                                 // ~
+];
@@ -0,0 +1,198 @@
+use std::rc::Rc;
+
+use glam::{Mat4, Vec2};
+use wgpu::{util::DeviceExt, BufferAddress, VertexAttribute, VertexBufferLayout, VertexFormat};
+
+use super::{color, shape::ShapeVertex, texture::Texture, Vertex, QUAD_INDICES};
+use crate::{math::Rect, Ctx};
+
+/// Initial number of instances the GPU buffer can hold.
+const INITIAL_CAPACITY: usize = 256;
+
+/// Per-instance data of an [`InstancedSprites`] draw.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SpriteInstance {
+    /// Position of the center of the quad.
+    pub position: [f32; 2],
+    /// Rotation around the center, in radians.
+    pub rotation: f32,
+    pub scale: [f32; 2],
+    /// Part of the texture displayed, in pixels: `[x, y, width, height]`.
+    pub uv_rect: [f32; 4],
+    /// Tint multiplied with the texture color, in linear space (see
+    /// [`color::Color::to_linear`]).
+    pub color: [f32; 4],
+}
+
+impl SpriteInstance {
+    /// Create an untransformed, untinted instance displaying `uv_rect` of the
+    /// texture centered on `position`.
+    pub fn new(position: Vec2, uv_rect: Rect) -> Self {
+        Self {
+            position: position.into(),
+            rotation: 0.,
+            scale: [1., 1.],
+            uv_rect: [uv_rect.x, uv_rect.y, uv_rect.width, uv_rect.height],
+            color: color::WHITE.to_linear(),
+        }
+    }
+}
+
+impl Vertex for SpriteInstance {
+    fn desc() -> VertexBufferLayout<'static> {
+        use std::mem;
+
+        VertexBufferLayout {
+            array_stride: mem::size_of::<SpriteInstance>() as BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            // Locations 0 to 2 are used by `ShapeVertex`
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 3,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Float32,
+                },
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 3]>() as BufferAddress,
+                    shader_location: 5,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 5]>() as BufferAddress,
+                    shader_location: 6,
+                    format: VertexFormat::Float32x4,
+                },
+                VertexAttribute {
+                    offset: mem::size_of::<[f32; 9]>() as BufferAddress,
+                    shader_location: 7,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Many copies of the same textured quad drawn in a single draw call, for
+/// bullets, particles or tile layers.
+///
+/// Instances are pushed every frame then sent to the GPU at once with
+/// [`InstancedSprites::upload`] before being drawn with
+/// [`super::Renderer::draw_instanced`].
+pub struct InstancedSprites {
+    ctx: Ctx,
+    texture: Rc<Texture>,
+    instances: Vec<SpriteInstance>,
+    pub(crate) uploaded: u32,
+    pub(crate) vertex_buffer: wgpu::Buffer,
+    pub(crate) index_buffer: wgpu::Buffer,
+    pub(crate) instance_buffer: wgpu::Buffer,
+    pub(crate) transform_bind_group: wgpu::BindGroup,
+}
+
+impl InstancedSprites {
+    pub fn new(ctx: &Ctx, texture: Rc<Texture>) -> Self {
+        // Unit quad, scaled to the UV rectangle of each instance by the shader
+        let vertices: Vec<ShapeVertex> = [[0., 0.], [0., 1.], [1., 1.], [1., 0.]]
+            .into_iter()
+            .map(|[x, y]| ShapeVertex {
+                position: [x, y, 0.],
+                color: [1., 1., 1., 1.],
+                tex_coords: [x, y],
+            })
+            .collect();
+        let vertex_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Vertex buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+        let index_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Index buffer"),
+                contents: bytemuck::cast_slice(&QUAD_INDICES),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+        let transform_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("instances transform buffer"),
+                contents: bytemuck::cast_slice(&[Mat4::IDENTITY]),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let transform_bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &ctx.bind_group_layouts.transform,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: transform_buffer.as_entire_binding(),
+            }],
+            label: Some("instances transform bind group"),
+        });
+
+        Self {
+            ctx: ctx.clone(),
+            texture,
+            instances: Vec::new(),
+            uploaded: 0,
+            vertex_buffer,
+            index_buffer,
+            instance_buffer: create_instance_buffer(ctx, INITIAL_CAPACITY),
+            transform_bind_group,
+        }
+    }
+
+    pub fn texture(&self) -> &Rc<Texture> {
+        &self.texture
+    }
+
+    pub fn push(&mut self, instance: SpriteInstance) {
+        self.instances.push(instance);
+    }
+
+    /// Remove every instance, usually at the beginning of a frame.
+    pub fn clear(&mut self) {
+        self.instances.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// Send the instances to the GPU, growing the instance buffer if needed.
+    pub fn upload(&mut self) {
+        let size = std::mem::size_of_val(self.instances.as_slice());
+        if size > self.instance_buffer.size() as usize {
+            self.instance_buffer =
+                create_instance_buffer(&self.ctx, self.instances.len().next_power_of_two());
+        }
+
+        if !self.instances.is_empty() {
+            self.ctx.queue.write_buffer(
+                &self.instance_buffer,
+                0,
+                bytemuck::cast_slice(&self.instances),
+            );
+        }
+        self.uploaded = self.instances.len() as u32;
+    }
+}
+
+fn create_instance_buffer(ctx: &Ctx, capacity: usize) -> wgpu::Buffer {
+    ctx.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Instance buffer"),
+        size: (capacity * std::mem::size_of::<SpriteInstance>()) as BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
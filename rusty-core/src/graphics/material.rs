@@ -0,0 +1,340 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    rc::Rc,
+    sync::Arc,
+};
+
+use wgpu::util::DeviceExt;
+
+use super::{
+    pipeline::{BlendMode, PipelineKey, VertexLayout},
+    texture::Texture,
+};
+use crate::{Context, Ctx, Error, Result};
+
+const VERTEX: &str = include_str!("shader.wgsl");
+
+/// Group of the material bind group, after the ones of the standard pipeline.
+pub(crate) const MATERIAL_GROUP: u32 = 5;
+
+/// Fragment shader of a [`Material`].
+///
+/// The source is appended to the bindings and vertex stage of the standard
+/// pipeline, see `shader.wgsl`, and must define `fs_main`. The material
+/// bind group is `@group(5)`: its parameters are bound to `@binding(0)`,
+/// then each texture and its sampler to the next two bindings.
+///
+/// ```wgsl
+/// @group(5) @binding(0)
+/// var<uniform> tint: vec4<f32>;
+/// @group(5) @binding(1)
+/// var mask: texture_2d<f32>;
+/// @group(5) @binding(2)
+/// var mask_sampler: sampler;
+///
+/// @fragment
+/// fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+///     let uv = in.tex_coords;
+///     let color = textureSample(t_diffuse, s_diffuse, uv) * in.color;
+///     return color * tint * textureSample(mask, mask_sampler, uv).a;
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Shader {
+    id: u64,
+    name: String,
+    module: wgpu::ShaderModule,
+    layout: wgpu::PipelineLayout,
+    /// Layout of the material bind group, absent for the standard shader.
+    material_layout: Option<wgpu::BindGroupLayout>,
+    textures: usize,
+}
+
+impl Shader {
+    /// Compile the fragment shader `source`, which reads `textures` extra
+    /// textures.
+    ///
+    /// Fails if the shader is invalid.
+    pub fn new(ctx: &Context, name: &str, source: &str, textures: usize) -> Result<Self> {
+        ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let shader = Self::create(ctx, name, source, Some(textures));
+        // Pipelines are created lazily, build one to validate the shader
+        // against the layout
+        shader.pipeline(ctx, BlendMode::Alpha, VertexLayout::Shape);
+        match pollster::block_on(ctx.device.pop_error_scope()) {
+            Some(err) => {
+                // Do not let the invalid pipeline be found by a shader with
+                // the same source
+                ctx.render_pipelines
+                    .lock()
+                    .unwrap()
+                    .retain(|key, _| key.shader != shader.id);
+                Err(Error::InvalidShader {
+                    name: name.to_owned(),
+                    message: err.to_string(),
+                })
+            }
+            None => Ok(shader),
+        }
+    }
+
+    /// Shader of the standard pipeline, see [`Context::standard_shader`].
+    pub(crate) fn standard(ctx: &Context) -> Self {
+        Self::create(ctx, "standard", include_str!("fragment.wgsl"), None)
+    }
+
+    fn create(ctx: &Context, name: &str, source: &str, textures: Option<usize>) -> Self {
+        let device = &ctx.device;
+        let source = format!("{VERTEX}\n{source}");
+
+        let mut hasher = DefaultHasher::new();
+        (&source, textures).hash(&mut hasher);
+        let id = hasher.finish();
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(name),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let material_layout = textures.map(|textures| material_layout(device, textures));
+        let mut bind_group_layouts = ctx.bind_group_layouts.as_slice().to_vec();
+        bind_group_layouts.extend(material_layout.as_ref());
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render pipeline layout"),
+            bind_group_layouts: &bind_group_layouts,
+            push_constant_ranges: &[],
+        });
+
+        Self {
+            id,
+            name: name.to_owned(),
+            module,
+            layout,
+            material_layout,
+            textures: textures.unwrap_or(0),
+        }
+    }
+
+    /// Hash of the source and layout of the shader, shared by identical
+    /// shaders so they use the same pipelines.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of extra textures read by the shader.
+    pub fn textures(&self) -> usize {
+        self.textures
+    }
+
+    /// Pipeline drawing `vertex_layout` with this shader and `blend_mode`,
+    /// created the first time it is needed.
+    pub fn pipeline(
+        &self,
+        ctx: &Context,
+        blend_mode: BlendMode,
+        vertex_layout: VertexLayout,
+    ) -> Arc<wgpu::RenderPipeline> {
+        let key = PipelineKey {
+            shader: self.id,
+            blend_mode,
+            vertex_layout,
+        };
+
+        ctx.render_pipeline(key, &self.layout, &self.module)
+    }
+}
+
+/// Custom shader and its parameters, drawn instead of the standard pipeline
+/// by the meshes it is attached to with [`Mesh::set_material`].
+///
+/// [`Mesh::set_material`]: super::Mesh::set_material
+///
+/// ```ignore
+/// let shader = Rc::new(Shader::new(ctx, "tint", include_str!("tint.wgsl"), 1)?);
+/// let mut material = Material::new(ctx, shader, [1., 0.5, 0.5, 1.]);
+/// material.set_texture(0, mask);
+///
+/// sprite.mesh_mut().set_material(Some(Rc::new(material)));
+/// ```
+pub struct Material {
+    ctx: Ctx,
+    shader: Rc<Shader>,
+    blend_mode: BlendMode,
+    pipeline: Arc<wgpu::RenderPipeline>,
+    params_buffer: wgpu::Buffer,
+    /// Extra textures, the white texture of the context being bound in place
+    /// of missing ones.
+    textures: Vec<Option<Rc<Texture>>>,
+    bind_group: wgpu::BindGroup,
+}
+
+impl Material {
+    /// Create a material drawing with `shader`, whose textures are white
+    /// until set.
+    ///
+    /// `params` must match the layout of the WGSL type, which is padded to
+    /// a multiple of 16 bytes.
+    pub fn new<T: bytemuck::Pod>(ctx: &Ctx, shader: Rc<Shader>, params: T) -> Self {
+        let pipeline = shader.pipeline(ctx, BlendMode::Alpha, VertexLayout::Shape);
+        let params_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("material params"),
+                contents: &padded(params),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+        let textures = vec![None; shader.textures];
+        let bind_group = bind_group(ctx, &shader, &params_buffer, &textures);
+
+        Self {
+            ctx: ctx.clone(),
+            shader,
+            blend_mode: BlendMode::Alpha,
+            pipeline,
+            params_buffer,
+            textures,
+            bind_group,
+        }
+    }
+
+    pub fn shader(&self) -> &Rc<Shader> {
+        &self.shader
+    }
+
+    /// Set how the meshes drawn with the material are blended with the
+    /// target, which replaces the blend mode of the meshes.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+        self.pipeline = self
+            .shader
+            .pipeline(&self.ctx, blend_mode, VertexLayout::Shape);
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Update the parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `params` are not the same size as the ones the material was
+    /// created with.
+    pub fn set_params<T: bytemuck::Pod>(&self, params: T) {
+        let bytes = padded(params);
+        assert_eq!(
+            bytes.len() as u64,
+            self.params_buffer.size(),
+            "material params changed size"
+        );
+
+        self.ctx.queue.write_buffer(&self.params_buffer, 0, &bytes);
+    }
+
+    /// Set the extra texture at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shader reads less than `index + 1` textures.
+    pub fn set_texture(&mut self, index: usize, texture: Rc<Texture>) {
+        self.textures[index] = Some(texture);
+        self.bind_group = bind_group(&self.ctx, &self.shader, &self.params_buffer, &self.textures);
+    }
+
+    pub fn texture(&self, index: usize) -> Option<&Rc<Texture>> {
+        self.textures.get(index)?.as_ref()
+    }
+
+    pub(super) fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    pub(super) fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
+
+/// Bytes of `params`, padded to a multiple of 16 as uniform buffers.
+fn padded<T: bytemuck::Pod>(params: T) -> Vec<u8> {
+    let mut bytes = bytemuck::bytes_of(&params).to_vec();
+    bytes.resize(bytes.len().div_ceil(16).max(1) * 16, 0);
+
+    bytes
+}
+
+fn material_layout(device: &wgpu::Device, textures: usize) -> wgpu::BindGroupLayout {
+    let mut entries = vec![wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }];
+    for i in 0..textures as u32 {
+        entries.push(wgpu::BindGroupLayoutEntry {
+            binding: 1 + 2 * i,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension: wgpu::TextureViewDimension::D2,
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            },
+            count: None,
+        });
+        entries.push(wgpu::BindGroupLayoutEntry {
+            binding: 2 + 2 * i,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        });
+    }
+
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &entries,
+        label: Some("material bind group layout"),
+    })
+}
+
+fn bind_group(
+    ctx: &Context,
+    shader: &Shader,
+    params: &wgpu::Buffer,
+    textures: &[Option<Rc<Texture>>],
+) -> wgpu::BindGroup {
+    let textures: Vec<&Texture> = textures
+        .iter()
+        .map(|texture| texture.as_deref().unwrap_or(ctx.white_texture()))
+        .collect();
+    let samplers: Vec<_> = textures.iter().map(|texture| texture.sampler()).collect();
+    let mut entries = vec![wgpu::BindGroupEntry {
+        binding: 0,
+        resource: params.as_entire_binding(),
+    }];
+    for (i, (texture, sampler)) in textures.iter().zip(&samplers).enumerate() {
+        let i = i as u32;
+        entries.push(wgpu::BindGroupEntry {
+            binding: 1 + 2 * i,
+            resource: wgpu::BindingResource::TextureView(&texture.view),
+        });
+        entries.push(wgpu::BindGroupEntry {
+            binding: 2 + 2 * i,
+            resource: wgpu::BindingResource::Sampler(sampler),
+        });
+    }
+
+    ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: shader
+            .material_layout
+            .as_ref()
+            .expect("materials are created from custom shaders"),
+        entries: &entries,
+        label: Some("material bind group"),
+    })
+}
@@ -1,63 +1,168 @@
+use std::{rc::Rc, sync::Arc};
+
 use glam::{Mat4, Vec2};
 use wgpu::{util::DeviceExt, VertexBufferLayout};
 
-use crate::Context;
+use crate::Ctx;
+use material::MATERIAL_GROUP;
+use texture::Texture;
 
+pub mod atlas;
+pub mod batch;
+pub mod camera;
 pub mod color;
+#[cfg(any(feature = "ktx2", feature = "dds"))]
+mod compressed;
+pub mod debug;
+pub mod instancing;
+pub mod material;
+pub mod pipeline;
+pub mod post_process;
+pub mod render_texture;
+pub mod renderer;
 pub mod shape;
 pub mod sprite;
+pub mod text;
 pub mod texture;
+mod triangulation;
+
+pub use atlas::{AtlasRegion, TextureAtlas, TextureAtlasBuilder};
+pub use batch::SpriteBatch;
+pub use camera::Camera2D;
+pub use debug::DebugDraw;
+pub use instancing::{InstancedSprites, SpriteInstance};
+pub use material::{Material, Shader};
+pub use pipeline::{BlendMode, PipelineKey, VertexLayout};
+pub use post_process::{Effect, PostProcessStack};
+pub use render_texture::RenderTexture;
+pub use renderer::{Frame, Renderer};
+pub use text::{Font, Text, TextAlignment};
+
+/// Indices of the two triangles of a quad whose vertices are given clockwise
+/// from the top-left corner.
+pub(crate) const QUAD_INDICES: [u32; 6] = [0, 1, 3, 1, 2, 3];
 
-#[derive(Default)]
+/// Position, rotation, scale and skew of an object, applied around its origin.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Transform {
     pub position: Vec2,
+    /// Rotation in radians.
     pub rotation: f32,
-    pub scale: f32,
+    pub scale: Vec2,
+    /// Shear angles in radians along the x and y axes.
+    pub skew: Vec2,
+    /// Point of the object, in local coordinates, placed at `position` and
+    /// around which it is rotated, scaled and skewed.
     pub origin: Vec2,
 }
 
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            rotation: 0.,
+            scale: Vec2::ONE,
+            skew: Vec2::ZERO,
+            origin: Vec2::ZERO,
+        }
+    }
+}
+
 impl Transform {
+    /// Matrix converting local coordinates to world coordinates.
     pub fn to_model_matrix(&self) -> Mat4 {
-        let rotation = Mat4::from_rotation_z(self.rotation);
         let translation = Mat4::from_translation((self.position.x, self.position.y, 0.0).into());
+        let rotation = Mat4::from_rotation_z(self.rotation);
+        let skew = Mat4::from_cols_array(&[
+            1.,
+            self.skew.y.tan(),
+            0.,
+            0.,
+            self.skew.x.tan(),
+            1.,
+            0.,
+            0.,
+            0.,
+            0.,
+            1.,
+            0.,
+            0.,
+            0.,
+            0.,
+            1.,
+        ]);
+        let scale = Mat4::from_scale((self.scale.x, self.scale.y, 1.).into());
         let origin = Mat4::from_translation((-self.origin.x, -self.origin.y, 0.).into());
 
-        translation * rotation * origin
+        translation * rotation * skew * scale * origin
+    }
+
+    /// Matrix converting world coordinates to local coordinates.
+    ///
+    /// A null scale makes the transform non invertible, the result is then
+    /// undefined.
+    pub fn inverse(&self) -> Mat4 {
+        self.to_model_matrix().inverse()
+    }
+
+    /// Convert a point from local to world coordinates.
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        self.to_model_matrix()
+            .transform_point3(point.extend(0.))
+            .truncate()
+    }
+
+    /// Convert a point from world to local coordinates.
+    pub fn inverse_transform_point(&self, point: Vec2) -> Vec2 {
+        self.inverse().transform_point3(point.extend(0.)).truncate()
     }
 }
 
 pub struct Mesh {
+    ctx: Ctx,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     transform: Transform,
+    parent_transform: Mat4,
     buffer: wgpu::Buffer,
     bind_group: wgpu::BindGroup,
-    // texture_bind_group: wgpu::BindGroup,
+    texture: Option<Rc<Texture>>,
+    material: Option<Rc<Material>>,
+    blend_mode: BlendMode,
+    /// Standard pipeline with `blend_mode`, absent for alpha blending which
+    /// is bound by default.
+    pipeline: Option<Arc<wgpu::RenderPipeline>>,
     num_elements: u32,
+    /// Number of leading indices drawn with the texture, the remaining ones
+    /// (e.g. an outline) being drawn untextured.
+    textured_elements: u32,
 }
 
 impl Mesh {
-    pub fn new(vertex_buffer: wgpu::Buffer, index_buffer: wgpu::Buffer, num_elements: u32) -> Self {
-        let gl_context = Context::get();
+    /// Mesh drawing the first `num_elements` indices of `index_buffer`, a
+    /// triangle list of `u32` indices into `vertex_buffer`.
+    pub fn new(
+        ctx: &Ctx,
+        vertex_buffer: wgpu::Buffer,
+        index_buffer: wgpu::Buffer,
+        num_elements: u32,
+    ) -> Self {
         let transform = Transform::default();
-        let buffer = gl_context
+        let buffer = ctx
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("vertex buffer"),
                 contents: bytemuck::cast_slice(&[transform.to_model_matrix()]),
                 usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             });
-        let bind_group_layout = gl_context.bind_group_layouts.get("transform").unwrap();
-        let bind_group = gl_context
-            .device
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: bind_group_layout,
-                entries: &[wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: buffer.as_entire_binding(),
-                }],
-                label: Some("transform bind group"),
-            });
+        let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &ctx.bind_group_layouts.transform,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("transform bind group"),
+        });
         // let bind_group_layout = ctx.bind_group_layouts.get("texture").unwrap();
         // let bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
         //     layout: bind_group_layout,
@@ -69,14 +174,135 @@ impl Mesh {
         // });
 
         Self {
+            ctx: ctx.clone(),
             vertex_buffer,
             index_buffer,
             num_elements,
+            textured_elements: num_elements,
             buffer,
             bind_group,
+            texture: None,
+            material: None,
+            blend_mode: BlendMode::Alpha,
+            pipeline: None,
             transform: Default::default(),
+            parent_transform: Mat4::IDENTITY,
         }
     }
+
+    /// Set the matrix the transform of the mesh is relative to, e.g. the
+    /// world matrix of the node it is attached to.
+    pub fn set_parent_transform(&mut self, parent_transform: Mat4) {
+        self.parent_transform = parent_transform;
+        self.update_transform();
+    }
+
+    /// Matrix converting the vertices of the mesh to world coordinates.
+    pub fn model_matrix(&self) -> Mat4 {
+        self.parent_transform * self.transform.to_model_matrix()
+    }
+
+    /// Set the texture bound when drawing the mesh.
+    ///
+    /// Without texture, the mesh is drawn with the white texture of the
+    /// context so only vertex colors show up.
+    pub fn set_texture(&mut self, texture: Option<Rc<Texture>>) {
+        self.texture = texture;
+    }
+
+    pub fn texture(&self) -> Option<&Rc<Texture>> {
+        self.texture.as_ref()
+    }
+
+    /// Draw the mesh with a custom shader instead of the standard pipeline.
+    ///
+    /// The texture of the mesh is still bound, as `t_diffuse`.
+    pub fn set_material(&mut self, material: Option<Rc<Material>>) {
+        self.material = material;
+    }
+
+    pub fn material(&self) -> Option<&Rc<Material>> {
+        self.material.as_ref()
+    }
+
+    /// Set how the mesh is blended with the target, unless it has a material
+    /// whose blend mode is used instead.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+        self.pipeline = (blend_mode != BlendMode::Alpha).then(|| {
+            self.ctx
+                .standard_shader()
+                .pipeline(&self.ctx, blend_mode, VertexLayout::Shape)
+        });
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Upload the current model matrix to the transform uniform.
+    fn update_transform(&self) {
+        self.ctx.queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::cast_slice(&[self.model_matrix()]),
+        );
+    }
+
+    /// Replace the content of the vertex buffer.
+    fn write_vertices<T: bytemuck::Pod>(&self, vertices: &[T]) {
+        self.ctx
+            .queue
+            .write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(vertices));
+    }
+
+    /// Replace the vertices and indices of the mesh, growing the buffers if
+    /// needed.
+    ///
+    /// Only the first `textured_elements` indices are drawn with the texture
+    /// of the mesh, the following ones are drawn untextured.
+    fn write_geometry<T: bytemuck::Pod>(
+        &mut self,
+        vertices: &[T],
+        indices: &[u32],
+        textured_elements: u32,
+    ) {
+        self.num_elements = indices.len() as u32;
+        self.textured_elements = textured_elements;
+
+        let vertices: &[u8] = bytemuck::cast_slice(vertices);
+        if vertices.len() as u64 > self.vertex_buffer.size() {
+            self.vertex_buffer = create_buffer(
+                &self.ctx,
+                "Vertex buffer",
+                vertices.len(),
+                wgpu::BufferUsages::VERTEX,
+            );
+        }
+        let indices: &[u8] = bytemuck::cast_slice(indices);
+        if indices.len() as u64 > self.index_buffer.size() {
+            self.index_buffer = create_buffer(
+                &self.ctx,
+                "Index buffer",
+                indices.len(),
+                wgpu::BufferUsages::INDEX,
+            );
+        }
+
+        self.ctx
+            .queue
+            .write_buffer(&self.vertex_buffer, 0, vertices);
+        self.ctx.queue.write_buffer(&self.index_buffer, 0, indices);
+    }
+}
+
+fn create_buffer(ctx: &Ctx, label: &str, size: usize, usage: wgpu::BufferUsages) -> wgpu::Buffer {
+    ctx.device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: size.next_power_of_two() as wgpu::BufferAddress,
+        usage: usage | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
 }
 
 // impl Transformable for Mesh {
@@ -139,8 +365,12 @@ pub trait Transformable {
     fn rotation(&self) -> f32;
     fn rotate(&mut self, angle: f32);
 
+    /// Scale both axes by the same factor.
     fn set_scale(&mut self, scale: f32);
+    /// Horizontal scale factor, see [`Transformable::scale_xy`] for both axes.
     fn scale(&self) -> f32;
+    fn set_scale_xy(&mut self, scale: Vec2);
+    fn scale_xy(&self) -> Vec2;
 
     fn set_origin(&mut self, origin: Vec2);
     fn origin(&self) -> &Vec2;
@@ -152,6 +382,7 @@ pub trait Vertex {
 
 pub trait Drawable<'a> {
     fn draw_mesh(&mut self, vertex_buffer: &'a Mesh);
+    fn draw_batch(&mut self, batch: &'a SpriteBatch);
 }
 
 impl<'a, 'b> Drawable<'b> for wgpu::RenderPass<'a>
@@ -159,10 +390,143 @@ where
     'b: 'a,
 {
     fn draw_mesh(&mut self, mesh: &'b Mesh) {
-        self.set_bind_group(3, &mesh.bind_group, &[]);
+        let white = mesh.ctx.white_texture().bind_group();
+
+        if let Some(material) = &mesh.material {
+            self.set_pipeline(material.pipeline());
+            self.set_bind_group(MATERIAL_GROUP, material.bind_group(), &[]);
+        } else if let Some(pipeline) = &mesh.pipeline {
+            self.set_pipeline(pipeline);
+        }
         self.set_bind_group(3, &mesh.bind_group, &[]);
         self.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-        self.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        self.draw_indexed(0..mesh.num_elements, 0, 0..1);
+        self.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        match &mesh.texture {
+            Some(texture) => {
+                self.set_bind_group(4, &*texture.bind_group(), &[]);
+                self.draw_indexed(0..mesh.textured_elements, 0, 0..1);
+                if mesh.textured_elements < mesh.num_elements {
+                    self.set_bind_group(4, &*white, &[]);
+                    self.draw_indexed(mesh.textured_elements..mesh.num_elements, 0, 0..1);
+                }
+            }
+            None => {
+                self.set_bind_group(4, &*white, &[]);
+                self.draw_indexed(0..mesh.num_elements, 0, 0..1);
+            }
+        }
+        if mesh.material.is_some() || mesh.pipeline.is_some() {
+            let standard = mesh.ctx.standard_shader().pipeline(
+                &mesh.ctx,
+                BlendMode::Alpha,
+                VertexLayout::Shape,
+            );
+            self.set_pipeline(&standard);
+        }
+    }
+
+    fn draw_batch(&mut self, batch: &'b SpriteBatch) {
+        if batch.batches.is_empty() {
+            return;
+        }
+
+        self.set_bind_group(3, &batch.transform_bind_group, &[]);
+        self.set_vertex_buffer(0, batch.vertex_buffer.slice(..));
+        self.set_index_buffer(batch.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        for run in &batch.batches {
+            let texture = match &run.texture {
+                Some(texture) => texture,
+                None => batch.ctx.white_texture(),
+            };
+            self.set_bind_group(4, &*texture.bind_group(), &[]);
+            self.draw_indexed(run.indices.clone(), 0, 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transform;
+    use glam::{Mat4, Vec2};
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_vec2_eq(a: Vec2, b: Vec2) {
+        assert!(a.abs_diff_eq(b, 1e-4), "{a} != {b}");
+    }
+
+    #[test]
+    fn default_transform_is_identity() {
+        assert_eq!(Transform::default().to_model_matrix(), Mat4::IDENTITY);
+    }
+
+    #[test]
+    fn transform_point_applies_scale_then_rotation_then_translation() {
+        let transform = Transform {
+            position: Vec2 { x: 10., y: 20. },
+            rotation: FRAC_PI_2,
+            scale: Vec2 { x: 2., y: 3. },
+            ..Default::default()
+        };
+
+        assert_vec2_eq(
+            transform.transform_point(Vec2 { x: 1., y: 1. }),
+            Vec2 { x: 7., y: 22. },
+        );
+    }
+
+    #[test]
+    fn transform_point_around_origin() {
+        let transform = Transform {
+            position: Vec2 { x: 50., y: 50. },
+            scale: Vec2 { x: 2., y: 2. },
+            origin: Vec2 { x: 5., y: 5. },
+            ..Default::default()
+        };
+
+        assert_vec2_eq(
+            transform.transform_point(Vec2 { x: 5., y: 5. }),
+            Vec2 { x: 50., y: 50. },
+        );
+        assert_vec2_eq(
+            transform.transform_point(Vec2 { x: 10., y: 5. }),
+            Vec2 { x: 60., y: 50. },
+        );
+    }
+
+    #[test]
+    fn skew_shears_along_axis() {
+        let transform = Transform {
+            skew: Vec2 {
+                x: std::f32::consts::FRAC_PI_4,
+                y: 0.,
+            },
+            ..Default::default()
+        };
+
+        assert_vec2_eq(
+            transform.transform_point(Vec2 { x: 0., y: 10. }),
+            Vec2 { x: 10., y: 10. },
+        );
+    }
+
+    #[test]
+    fn inverse_transform_point_roundtrip() {
+        let transform = Transform {
+            position: Vec2 { x: -3., y: 8. },
+            rotation: 0.7,
+            scale: Vec2 { x: 0.5, y: 4. },
+            skew: Vec2 { x: 0.2, y: -0.1 },
+            origin: Vec2 { x: 16., y: 16. },
+        };
+        let point = Vec2 { x: 12., y: -7. };
+
+        assert_vec2_eq(
+            transform.inverse_transform_point(transform.transform_point(point)),
+            point,
+        );
+        assert!(
+            (transform.inverse() * transform.to_model_matrix()).abs_diff_eq(Mat4::IDENTITY, 1e-5)
+        );
     }
 }
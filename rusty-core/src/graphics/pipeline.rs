@@ -0,0 +1,194 @@
+//! Render pipelines, created on first use and cached in
+//! [`Context::render_pipelines`].
+
+use std::sync::Arc;
+
+use super::{instancing::SpriteInstance, shape::ShapeVertex, Vertex};
+use crate::Context;
+
+/// How the colors drawn are combined with the ones of the target.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Draw over the target according to the alpha of the source.
+    #[default]
+    Alpha,
+    /// Same as [`BlendMode::Alpha`] for colors already multiplied by their
+    /// alpha, e.g. render textures drawn with transparency.
+    PremultipliedAlpha,
+    /// Add the source to the target, brightening it: glows, lights, fire.
+    Additive,
+    /// Multiply the target by the source, darkening it: shadows, tinting.
+    Multiply,
+    /// Inverse of multiplying the inverses, brightening the target without
+    /// saturating as fast as [`BlendMode::Additive`].
+    Screen,
+    /// Replace the target, ignoring the alpha of the source.
+    Opaque,
+}
+
+impl BlendMode {
+    pub fn to_blend_state(self) -> wgpu::BlendState {
+        use wgpu::{BlendComponent, BlendFactor, BlendOperation};
+
+        let component = |src_factor, dst_factor| BlendComponent {
+            src_factor,
+            dst_factor,
+            operation: BlendOperation::Add,
+        };
+        // Alpha of the target as with alpha blending, so the result can be
+        // drawn again over something else
+        let alpha = component(BlendFactor::One, BlendFactor::OneMinusSrcAlpha);
+
+        match self {
+            Self::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            Self::PremultipliedAlpha => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            Self::Additive => wgpu::BlendState {
+                color: component(BlendFactor::SrcAlpha, BlendFactor::One),
+                alpha: component(BlendFactor::Zero, BlendFactor::One),
+            },
+            Self::Multiply => wgpu::BlendState {
+                color: component(BlendFactor::Dst, BlendFactor::Zero),
+                alpha: component(BlendFactor::Zero, BlendFactor::One),
+            },
+            Self::Screen => wgpu::BlendState {
+                color: component(BlendFactor::One, BlendFactor::OneMinusSrc),
+                alpha,
+            },
+            Self::Opaque => wgpu::BlendState::REPLACE,
+        }
+    }
+}
+
+/// Vertex buffers read by a pipeline.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum VertexLayout {
+    /// [`ShapeVertex`], used by meshes and sprite batches.
+    Shape,
+    /// [`ShapeVertex`] of a unit quad, then a [`SpriteInstance`] per
+    /// instance.
+    Instanced,
+}
+
+impl VertexLayout {
+    fn buffers(self) -> Vec<wgpu::VertexBufferLayout<'static>> {
+        match self {
+            Self::Shape => vec![ShapeVertex::desc()],
+            Self::Instanced => vec![ShapeVertex::desc(), SpriteInstance::desc()],
+        }
+    }
+
+    fn entry_point(self) -> &'static str {
+        match self {
+            Self::Shape => "vs_main",
+            Self::Instanced => "vs_instanced",
+        }
+    }
+
+    fn cull_mode(self) -> Option<wgpu::Face> {
+        match self {
+            Self::Shape => Some(wgpu::Face::Back),
+            // Instances may be flipped with a negative scale, which reverses
+            // the winding order, so nothing is culled.
+            Self::Instanced => None,
+        }
+    }
+}
+
+/// Variant of a pipeline in the cache of the context.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    /// Identifier of the shader, see [`Shader::id`](super::material::Shader::id).
+    pub shader: u64,
+    pub blend_mode: BlendMode,
+    pub vertex_layout: VertexLayout,
+}
+
+impl Context {
+    /// Pipeline drawing `vertex_layout` with `module`, created with `layout`
+    /// the first time `key` is requested.
+    pub(crate) fn render_pipeline(
+        &self,
+        key: PipelineKey,
+        layout: &wgpu::PipelineLayout,
+        module: &wgpu::ShaderModule,
+    ) -> Arc<wgpu::RenderPipeline> {
+        let mut pipelines = self.render_pipelines.lock().unwrap();
+
+        pipelines
+            .entry(key)
+            .or_insert_with(|| {
+                let buffers = key.vertex_layout.buffers();
+                Arc::new(create_pipeline(
+                    &self.device,
+                    layout,
+                    module,
+                    self.format,
+                    PipelineDescriptor {
+                        label: "Render pipeline",
+                        vertex_entry_point: key.vertex_layout.entry_point(),
+                        buffers: &buffers,
+                        cull_mode: key.vertex_layout.cull_mode(),
+                        blend: key.blend_mode.to_blend_state(),
+                    },
+                ))
+            })
+            .clone()
+    }
+}
+
+pub(crate) struct PipelineDescriptor<'a> {
+    pub label: &'a str,
+    pub vertex_entry_point: &'a str,
+    pub buffers: &'a [wgpu::VertexBufferLayout<'a>],
+    pub cull_mode: Option<wgpu::Face>,
+    pub blend: wgpu::BlendState,
+}
+
+pub(crate) fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
+    desc: PipelineDescriptor,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(desc.label),
+        layout: Some(layout),
+        cache: None,
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some(desc.vertex_entry_point),
+            buffers: desc.buffers,
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(desc.blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: desc.cull_mode,
+            // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+            polygon_mode: wgpu::PolygonMode::Fill,
+            // Requires Features::DEPTH_CLIP_CONTROL
+            unclipped_depth: false,
+            // Requires Features::CONSERVATIVE_RASTERIZATION
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
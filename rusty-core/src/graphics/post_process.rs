@@ -0,0 +1,434 @@
+use std::{borrow::Cow, rc::Rc};
+
+use wgpu::util::DeviceExt;
+
+use super::{
+    pipeline::{create_pipeline, PipelineDescriptor},
+    render_texture::RenderTexture,
+    texture::Texture,
+    Frame, Renderer,
+};
+use crate::{Context, Ctx, Error, Result};
+
+const PRELUDE: &str = include_str!("post_process/prelude.wgsl");
+
+/// Full-screen pass of a [`PostProcessStack`], running a WGSL fragment shader
+/// over the result of the previous pass.
+///
+/// The source of an effect is appended to a prelude declaring:
+/// - `VertexOutput`, whose `uv` goes from (0, 0) at the top-left of the
+///   target to (1, 1) at its bottom-right,
+/// - `input_texture` and `input_sampler`, the result of the previous pass,
+/// - `post`, with the `resolution` of the target and the `time` in seconds,
+/// - `effect_texture` and `effect_sampler`, see [`Effect::set_texture`].
+///
+/// It must define `fs_main`, and declares its own parameters, if any, at
+/// `@group(1) @binding(1)`:
+///
+/// ```wgsl
+/// @group(1) @binding(1)
+/// var<uniform> tint: vec4<f32>;
+///
+/// @fragment
+/// fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+///     return textureSample(input_texture, input_sampler, in.uv) * tint;
+/// }
+/// ```
+pub struct Effect {
+    ctx: Ctx,
+    name: String,
+    enabled: bool,
+    pipeline: wgpu::RenderPipeline,
+    globals_buffer: wgpu::Buffer,
+    params_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    texture: Option<Rc<Texture>>,
+}
+
+impl Effect {
+    /// Compile an effect from the WGSL `source` of its fragment shader.
+    ///
+    /// Fails if the shader is invalid. Blocks the thread until the device
+    /// has validated the shader.
+    pub fn new(ctx: &Ctx, name: &str, source: &str) -> Result<Self> {
+        ctx.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let effect = Self::compile(ctx, name, source);
+        match pollster::block_on(ctx.device.pop_error_scope()) {
+            Some(err) => Err(Error::InvalidShader {
+                name: name.to_owned(),
+                message: err.to_string(),
+            }),
+            None => Ok(effect),
+        }
+    }
+
+    /// Copy the input as is.
+    pub fn passthrough(ctx: &Ctx) -> Self {
+        Self::compile(
+            ctx,
+            "passthrough",
+            include_str!("post_process/passthrough.wgsl"),
+        )
+    }
+
+    /// Darken the edges of the screen by `intensity`, from `radius` (0 at the
+    /// center, 1 at the corners) over `softness`.
+    pub fn vignette(ctx: &Ctx, intensity: f32, radius: f32, softness: f32) -> Self {
+        let mut effect = Self::compile(ctx, "vignette", include_str!("post_process/vignette.wgsl"));
+        effect.set_params([intensity, radius, softness, 0.]);
+
+        effect
+    }
+
+    /// Show the screen with pixels of `size` x `size`.
+    pub fn pixelate(ctx: &Ctx, size: f32) -> Self {
+        let mut effect = Self::compile(ctx, "pixelate", include_str!("post_process/pixelate.wgsl"));
+        effect.set_params([size, 0., 0., 0.]);
+
+        effect
+    }
+
+    /// Gaussian blur over `radius` pixels.
+    pub fn blur(ctx: &Ctx, radius: f32) -> Self {
+        let mut effect = Self::compile(ctx, "blur", include_str!("post_process/blur.wgsl"));
+        effect.set_params([radius, 0., 0., 0.]);
+
+        effect
+    }
+
+    /// Make the parts brighter than `threshold` glow over `radius` pixels.
+    pub fn bloom(ctx: &Ctx, threshold: f32, intensity: f32, radius: f32) -> Self {
+        let mut effect = Self::compile(ctx, "bloom", include_str!("post_process/bloom.wgsl"));
+        effect.set_params([threshold, intensity, radius, 0.]);
+
+        effect
+    }
+
+    /// Old screen look: darken every other line of `spacing` pixels by
+    /// `intensity` and bend the image by `curvature`.
+    pub fn crt(ctx: &Ctx, intensity: f32, spacing: f32, curvature: f32) -> Self {
+        let mut effect = Self::compile(ctx, "crt", include_str!("post_process/crt.wgsl"));
+        effect.set_params([intensity, spacing, curvature, 0.]);
+
+        effect
+    }
+
+    /// Remap colors through `lut`, a strip of N slices of N x N pixels (e.g.
+    /// 256 x 16) where red grows to the right of a slice, green to its bottom
+    /// and blue from a slice to the next one.
+    ///
+    /// The LUT maps sRGB colors to sRGB colors, as exported by image editors.
+    /// Load it with `srgb: false` in its [`TextureOptions`] so that its
+    /// entries are blended in sRGB space; LUTs loaded as sRGB textures are
+    /// re-encoded when sampled, which is slightly less accurate.
+    ///
+    /// `intensity` blends between the original colors (0) and the graded ones
+    /// (1).
+    ///
+    /// [`TextureOptions`]: super::texture::TextureOptions
+    pub fn color_grading(ctx: &Ctx, lut: Rc<Texture>, intensity: f32) -> Self {
+        let mut effect = Self::compile(
+            ctx,
+            "color grading",
+            include_str!("post_process/color_grading.wgsl"),
+        );
+        let decoded = if lut.texture.format().is_srgb() {
+            1.
+        } else {
+            0.
+        };
+        effect.set_params([intensity, decoded, 0., 0.]);
+        effect.set_texture(Some(lut));
+
+        effect
+    }
+
+    fn compile(ctx: &Ctx, name: &str, source: &str) -> Self {
+        let device = &ctx.device;
+        let layouts = &ctx.bind_group_layouts;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(name),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(format!("{PRELUDE}\n{source}"))),
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Effect pipeline layout"),
+            bind_group_layouts: &[&layouts.texture, &layouts.effect, &layouts.texture],
+            push_constant_ranges: &[],
+        });
+        let pipeline = create_pipeline(
+            device,
+            &layout,
+            &shader,
+            ctx.format,
+            PipelineDescriptor {
+                label: name,
+                vertex_entry_point: "vs_main",
+                buffers: &[],
+                cull_mode: None,
+                blend: wgpu::BlendState::REPLACE,
+            },
+        );
+
+        let globals_buffer = uniform_buffer(ctx, "effect globals", &[0; 16]);
+        let params_buffer = uniform_buffer(ctx, "effect params", &[0; 16]);
+        let bind_group = bind_group(ctx, &globals_buffer, &params_buffer);
+
+        Self {
+            ctx: ctx.clone(),
+            name: name.to_owned(),
+            enabled: true,
+            pipeline,
+            globals_buffer,
+            params_buffer,
+            bind_group,
+            texture: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Skip the effect without removing it from its stack.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Set the value bound to `@group(1) @binding(1)`.
+    ///
+    /// `params` must match the layout of the WGSL type, which is padded to
+    /// a multiple of 16 bytes.
+    pub fn set_params<T: bytemuck::Pod>(&mut self, params: T) {
+        let mut bytes = bytemuck::bytes_of(&params).to_vec();
+        bytes.resize(bytes.len().div_ceil(16).max(1) * 16, 0);
+
+        if bytes.len() as u64 == self.params_buffer.size() {
+            self.ctx.queue.write_buffer(&self.params_buffer, 0, &bytes);
+        } else {
+            self.params_buffer = uniform_buffer(&self.ctx, "effect params", &bytes);
+            self.bind_group = bind_group(&self.ctx, &self.globals_buffer, &self.params_buffer);
+        }
+    }
+
+    /// Set the texture bound to `effect_texture`, the white texture of the
+    /// context being bound without one.
+    pub fn set_texture(&mut self, texture: Option<Rc<Texture>>) {
+        self.texture = texture;
+    }
+
+    pub fn texture(&self) -> Option<&Rc<Texture>> {
+        self.texture.as_ref()
+    }
+
+    /// Run the effect over `input`, writing into `output`.
+    fn apply(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        input: &Texture,
+        output: &wgpu::TextureView,
+        globals: [f32; 4],
+    ) {
+        self.ctx
+            .queue
+            .write_buffer(&self.globals_buffer, 0, bytemuck::cast_slice(&globals));
+        let texture = match &self.texture {
+            Some(texture) => texture,
+            None => self.ctx.white_texture(),
+        };
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(&self.name),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &*input.bind_group(), &[]);
+        render_pass.set_bind_group(1, &self.bind_group, &[]);
+        render_pass.set_bind_group(2, &*texture.bind_group(), &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Ordered list of full-screen [`Effect`]s applied to a scene.
+///
+/// The scene is drawn into an intermediate target, then every enabled effect
+/// reads the result of the previous one, the last one writing into the frame.
+///
+/// ```ignore
+/// let mut post = PostProcessStack::new(ctx, width, height);
+/// post.push(Effect::bloom(ctx, 0.8, 1., 8.));
+/// post.push(Effect::vignette(ctx, 0.5, 0.6, 0.4));
+///
+/// let mut frame = renderer.begin_frame()?;
+/// {
+///     let mut render_pass = post.begin_scene_pass(&renderer, &mut frame, Some(wgpu::Color::BLACK));
+///     render_pass.draw_mesh(sprite.mesh());
+/// }
+/// post.apply(&mut frame);
+/// renderer.end_frame(frame);
+/// ```
+pub struct PostProcessStack {
+    ctx: Ctx,
+    scene: RenderTexture,
+    /// Intermediate targets, written to in turn by the effects.
+    targets: [RenderTexture; 2],
+    effects: Vec<Effect>,
+    /// Copies the scene to the frame when no effect is enabled.
+    passthrough: Effect,
+    time: f32,
+}
+
+impl PostProcessStack {
+    /// Create a stack for a frame of `width` x `height` pixels.
+    pub fn new(ctx: &Ctx, width: u32, height: u32) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            scene: RenderTexture::new(ctx, width, height),
+            targets: [
+                RenderTexture::new(ctx, width, height),
+                RenderTexture::new(ctx, width, height),
+            ],
+            effects: Vec::new(),
+            passthrough: Effect::passthrough(ctx),
+            time: 0.,
+        }
+    }
+
+    /// Resize the intermediate targets, to follow the size of the frame.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if (width, height) == self.size() || width == 0 || height == 0 {
+            return;
+        }
+
+        self.scene = RenderTexture::new(&self.ctx, width, height);
+        self.targets = [
+            RenderTexture::new(&self.ctx, width, height),
+            RenderTexture::new(&self.ctx, width, height),
+        ];
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.scene.size()
+    }
+
+    /// Target the scene is drawn into, e.g. to set a camera on the scene
+    /// pass with [`RenderTexture::set_camera`].
+    pub fn scene(&self) -> &RenderTexture {
+        &self.scene
+    }
+
+    /// Add an effect after the existing ones.
+    pub fn push(&mut self, effect: Effect) {
+        self.effects.push(effect);
+    }
+
+    /// Add an effect at `index`, shifting the following ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of effects.
+    pub fn insert(&mut self, index: usize, effect: Effect) {
+        self.effects.insert(index, effect);
+    }
+
+    /// Remove the first effect named `name`.
+    pub fn remove(&mut self, name: &str) -> Option<Effect> {
+        let index = self.effects.iter().position(|effect| effect.name == name)?;
+
+        Some(self.effects.remove(index))
+    }
+
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    /// First effect named `name`.
+    pub fn effect_mut(&mut self, name: &str) -> Option<&mut Effect> {
+        self.effects.iter_mut().find(|effect| effect.name == name)
+    }
+
+    /// Effects, in the order they are applied.
+    pub fn effects(&self) -> &[Effect] {
+        &self.effects
+    }
+
+    pub fn effects_mut(&mut self) -> &mut Vec<Effect> {
+        &mut self.effects
+    }
+
+    /// Advance the time given to the effects by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+    }
+
+    /// Start a render pass drawing the scene into the intermediate target.
+    pub fn begin_scene_pass<'a>(
+        &'a self,
+        renderer: &'a Renderer,
+        frame: &'a mut Frame,
+        clear: Option<wgpu::Color>,
+    ) -> wgpu::RenderPass<'a> {
+        renderer.begin_texture_pass(frame, &self.scene, clear)
+    }
+
+    /// Run the enabled effects over the scene, the last one writing into
+    /// `frame`.
+    pub fn apply(&self, frame: &mut Frame) {
+        let (encoder, view) = frame.target();
+        let mut effects: Vec<&Effect> = self.effects.iter().filter(|e| e.enabled).collect();
+        if effects.is_empty() {
+            effects.push(&self.passthrough);
+        }
+
+        let (width, height) = self.size();
+        let globals = [width as f32, height as f32, self.time, 0.];
+        let mut input = self.scene.texture();
+        for (i, effect) in effects.iter().enumerate() {
+            if i + 1 == effects.len() {
+                effect.apply(encoder, input, view, globals);
+            } else {
+                let output = self.targets[i % 2].texture();
+                effect.apply(encoder, input, &output.view, globals);
+                input = output;
+            }
+        }
+    }
+}
+
+fn uniform_buffer(ctx: &Context, label: &str, contents: &[u8]) -> wgpu::Buffer {
+    ctx.device
+        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        })
+}
+
+fn bind_group(ctx: &Context, globals: &wgpu::Buffer, params: &wgpu::Buffer) -> wgpu::BindGroup {
+    ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &ctx.bind_group_layouts.effect,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: globals.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: params.as_entire_binding(),
+            },
+        ],
+        label: Some("effect bind group"),
+    })
+}
@@ -0,0 +1,24 @@
+// x: brightness threshold, y: intensity, z: radius in pixels
+@group(1) @binding(1)
+var<uniform> params: vec4<f32>;
+
+// Blurred bright parts of the image added back to it
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let weights = array<f32, 5>(1.0, 4.0, 6.0, 4.0, 1.0);
+    let spread = params.z / 2.0 / post.resolution;
+
+    var glow = vec3<f32>(0.0);
+    for (var y = 0; y < 5; y++) {
+        for (var x = 0; x < 5; x++) {
+            let offset = vec2<f32>(f32(x - 2), f32(y - 2)) * spread;
+            let texel_color = textureSample(input_texture, input_sampler, in.uv + offset).rgb;
+            let brightness = max(texel_color.r, max(texel_color.g, texel_color.b));
+            glow += texel_color * step(params.x, brightness) * weights[x] * weights[y] / 256.0;
+        }
+    }
+
+    let color = textureSample(input_texture, input_sampler, in.uv);
+
+    return vec4<f32>(color.rgb + glow * params.y, color.a);
+}
@@ -0,0 +1,21 @@
+// x: radius, in pixels
+@group(1) @binding(1)
+var<uniform> params: vec4<f32>;
+
+// 5x5 gaussian kernel, whose taps are spread over the radius
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let weights = array<f32, 5>(1.0, 4.0, 6.0, 4.0, 1.0);
+    let spread = params.x / 2.0 / post.resolution;
+
+    var color = vec4<f32>(0.0);
+    for (var y = 0; y < 5; y++) {
+        for (var x = 0; x < 5; x++) {
+            let offset = vec2<f32>(f32(x - 2), f32(y - 2)) * spread;
+            let weight = weights[x] * weights[y] / 256.0;
+            color += textureSample(input_texture, input_sampler, in.uv + offset) * weight;
+        }
+    }
+
+    return color;
+}
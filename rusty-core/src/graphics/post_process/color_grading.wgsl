@@ -0,0 +1,41 @@
+// x: intensity, y: 1 when the LUT is decoded to linear when sampled
+@group(1) @binding(1)
+var<uniform> params: vec4<f32>;
+
+fn linear_to_srgb(linear: vec3<f32>) -> vec3<f32> {
+    let low = linear * 12.92;
+    let high = 1.055 * pow(linear, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, linear <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(srgb: vec3<f32>) -> vec3<f32> {
+    let low = srgb / 12.92;
+    let high = pow((srgb + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, srgb <= vec3<f32>(0.04045));
+}
+
+// The LUT is a horizontal strip of N slices of N x N pixels: red grows along
+// the x axis of a slice, green along its y axis and blue from one slice to
+// the next. Like the LUTs of image editors, it maps sRGB colors to sRGB
+// colors, while the scene is read and written in linear space.
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, input_sampler, in.uv);
+    let size = vec2<f32>(textureDimensions(effect_texture));
+    let slices = size.y;
+
+    let rgb = linear_to_srgb(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0)));
+    let blue = rgb.b * (slices - 1.0);
+    let slice = floor(blue);
+    let next = min(slice + 1.0, slices - 1.0);
+    let texel = (rgb.rg * (slices - 1.0) + 0.5) / vec2<f32>(size.x, slices);
+    var low = textureSample(effect_texture, effect_sampler, texel + vec2<f32>(slice / slices, 0.0)).rgb;
+    var high = textureSample(effect_texture, effect_sampler, texel + vec2<f32>(next / slices, 0.0)).rgb;
+    if params.y > 0.5 {
+        low = linear_to_srgb(low);
+        high = linear_to_srgb(high);
+    }
+    let graded = srgb_to_linear(mix(low, high, blue - slice));
+
+    return vec4<f32>(mix(color.rgb, graded, params.x), color.a);
+}
@@ -0,0 +1,19 @@
+// x: scanline intensity, y: scanline spacing in pixels, z: curvature
+@group(1) @binding(1)
+var<uniform> params: vec4<f32>;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Barrel distortion, pixels bent outside of the screen are black
+    let centered = in.uv * 2.0 - 1.0;
+    let uv = (centered * (1.0 + params.z * dot(centered, centered)) + 1.0) * 0.5;
+    let color = textureSample(input_texture, input_sampler, uv);
+    if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    let line = uv.y * post.resolution.y / max(params.y, 1.0) * 3.14159265;
+    let scanline = 1.0 - params.x * (0.5 - 0.5 * cos(2.0 * line));
+
+    return vec4<f32>(color.rgb * scanline, color.a);
+}
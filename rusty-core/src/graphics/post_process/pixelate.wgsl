@@ -0,0 +1,11 @@
+// x: size of the pixels, in pixels of the target
+@group(1) @binding(1)
+var<uniform> params: vec4<f32>;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = max(params.x, 1.0);
+    let uv = (floor(in.uv * post.resolution / size) + 0.5) * size / post.resolution;
+
+    return textureSample(input_texture, input_sampler, uv);
+}
@@ -0,0 +1,42 @@
+// Declarations shared by every post-processing effect, prepended to their
+// source.
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // Position on the target, from (0, 0) top-left to (1, 1) bottom-right
+    @location(0) uv: vec2<f32>
+};
+
+struct PostProcess {
+    // Size of the target in pixels
+    resolution: vec2<f32>,
+    // Seconds elapsed, see `PostProcessStack::update`
+    time: f32
+};
+
+// Result of the previous pass, or the scene for the first one
+@group(0) @binding(0)
+var input_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var input_sampler: sampler;
+
+@group(1) @binding(0)
+var<uniform> post: PostProcess;
+
+// Extra texture of the effect, e.g. a color grading LUT
+@group(2) @binding(0)
+var effect_texture: texture_2d<f32>;
+@group(2) @binding(1)
+var effect_sampler: sampler;
+
+// Triangle covering the whole target
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let position = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = vec2<f32>(position.x + 1.0, 1.0 - position.y) * 0.5;
+
+    return out;
+}
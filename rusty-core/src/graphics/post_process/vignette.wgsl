@@ -0,0 +1,12 @@
+// x: intensity, y: radius, z: softness
+@group(1) @binding(1)
+var<uniform> params: vec4<f32>;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(input_texture, input_sampler, in.uv);
+    let distance = length(in.uv - vec2<f32>(0.5, 0.5)) * sqrt(2.0);
+    let shade = smoothstep(params.y, params.y + params.z, distance) * params.x;
+
+    return vec4<f32>(color.rgb * (1.0 - shade), color.a);
+}
@@ -0,0 +1,118 @@
+use std::rc::Rc;
+
+use glam::{Mat4, Vec2};
+
+use super::{
+    camera::Camera2D,
+    renderer::create_uniform,
+    texture::{Texture, TextureOptions},
+};
+use crate::Ctx;
+
+/// Texture that can be drawn into, e.g. to cache a part of the UI, render a
+/// minimap or chain post-processing effects.
+///
+/// Draw into it with a render pass started by
+/// [`Renderer::begin_texture_pass`](super::Renderer::begin_texture_pass),
+/// then use [`RenderTexture::texture`] like any other texture, for instance
+/// in a [`Sprite`](super::sprite::Sprite).
+///
+/// ```ignore
+/// let minimap = RenderTexture::new(ctx, 128, 128);
+/// let mut sprite = Sprite::new(ctx, minimap.texture().clone());
+///
+/// let mut frame = renderer.begin_frame()?;
+/// {
+///     let mut render_pass = renderer.begin_texture_pass(&mut frame, &minimap, Some(wgpu::Color::BLACK));
+///     minimap.set_camera(&mut render_pass, &minimap_camera);
+///     render_pass.draw_mesh(world.mesh());
+/// }
+/// {
+///     let mut render_pass = renderer.begin_pass(&mut frame, Some(wgpu::Color::BLACK));
+///     render_pass.draw_mesh(sprite.mesh());
+/// }
+/// renderer.end_frame(frame);
+/// ```
+pub struct RenderTexture {
+    texture: Rc<Texture>,
+    pub(super) resolution_bind_group: wgpu::BindGroup,
+    pub(super) projection_bind_group: wgpu::BindGroup,
+}
+
+impl RenderTexture {
+    /// Create a `width` x `height` texture, in the color format of the
+    /// context so it can be drawn with the standard pipeline.
+    ///
+    /// Null sizes are raised to 1.
+    pub fn new(ctx: &Ctx, width: u32, height: u32) -> Self {
+        let (width, height) = (width.max(1), height.max(1));
+        let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("render texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ctx.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let texture = Rc::new(Texture::from_wgpu(
+            ctx,
+            texture,
+            Some("render texture"),
+            TextureOptions::default(),
+        ));
+
+        let size = Vec2::new(width as f32, height as f32);
+        let (_, resolution_bind_group) = create_uniform(
+            &ctx.device,
+            &ctx.bind_group_layouts.resolution,
+            "render texture resolution",
+            size,
+        );
+        let (_, projection_bind_group) = create_uniform(
+            &ctx.device,
+            &ctx.bind_group_layouts.projection,
+            "render texture projection",
+            Mat4::orthographic_rh(0., size.x, size.y, 0., -1., 0.),
+        );
+
+        Self {
+            texture,
+            resolution_bind_group,
+            projection_bind_group,
+        }
+    }
+
+    /// Texture holding what was drawn, shared with the sprites showing it.
+    pub fn texture(&self) -> &Rc<Texture> {
+        &self.texture
+    }
+
+    /// Size of the texture in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.texture.texture.width(), self.texture.texture.height())
+    }
+
+    /// Draw the next objects of a pass into this texture through `camera`,
+    /// into its viewport.
+    pub fn set_camera<'a>(&self, render_pass: &mut wgpu::RenderPass<'a>, camera: &'a Camera2D) {
+        camera.bind(render_pass, self.size());
+    }
+
+    /// Go back to the default projection, mapping world coordinates to the
+    /// pixels of the whole texture.
+    pub fn reset_camera<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        let (width, height) = self.size();
+
+        render_pass.set_viewport(0., 0., width as f32, height as f32, 0., 1.);
+        render_pass.set_bind_group(2, &self.projection_bind_group, &[]);
+    }
+}
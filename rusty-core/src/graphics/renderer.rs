@@ -0,0 +1,463 @@
+use std::sync::Arc;
+
+use glam::{Mat4, Vec2};
+use wgpu::util::DeviceExt;
+use winit::window::Window;
+
+use super::{
+    camera::Camera2D,
+    instancing::InstancedSprites,
+    pipeline::{BlendMode, VertexLayout},
+    render_texture::RenderTexture,
+    texture::read_texture_blocking,
+    QUAD_INDICES,
+};
+use crate::{Context, Ctx, Error, Result};
+
+/// Format of the offscreen target used by headless renderers.
+const HEADLESS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Owns the GPU device, the standard 2D pipeline and the global uniforms
+/// (mouse position, resolution, projection) shared by every draw.
+///
+/// A renderer either presents to a window surface ([`Renderer::new`]) or draws
+/// into an offscreen texture ([`Renderer::headless`]).
+pub struct Renderer {
+    ctx: Ctx,
+    surface: Option<wgpu::Surface<'static>>,
+    target: Option<wgpu::Texture>,
+    config: wgpu::SurfaceConfiguration,
+    pipeline: Arc<wgpu::RenderPipeline>,
+    instanced_pipeline: Arc<wgpu::RenderPipeline>,
+    mouse_buffer: wgpu::Buffer,
+    mouse_bind_group: wgpu::BindGroup,
+    resolution_buffer: wgpu::Buffer,
+    resolution_bind_group: wgpu::BindGroup,
+    projection_buffer: wgpu::Buffer,
+    projection_bind_group: wgpu::BindGroup,
+}
+
+/// A frame being recorded, returned by [`Renderer::begin_frame`].
+pub struct Frame {
+    surface_texture: Option<wgpu::SurfaceTexture>,
+    view: wgpu::TextureView,
+    encoder: wgpu::CommandEncoder,
+}
+
+impl Frame {
+    /// Encoder recording the commands of the frame and view of its target.
+    pub(super) fn target(&mut self) -> (&mut wgpu::CommandEncoder, &wgpu::TextureView) {
+        (&mut self.encoder, &self.view)
+    }
+}
+
+impl Renderer {
+    /// Create a renderer presenting to `window`.
+    pub async fn new(window: Arc<Window>) -> Result<Self> {
+        let size = window.inner_size();
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let surface = instance.create_surface(window)?;
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                compatible_surface: Some(&surface),
+                ..Default::default()
+            })
+            .await
+            .ok_or(Error::NoAdapter)?;
+        let (device, queue) = request_device(&adapter).await?;
+
+        let surface_caps = surface.get_capabilities(&adapter);
+        let surface_format = surface_caps
+            .formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb())
+            .unwrap_or(surface_caps.formats[0]);
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: surface_caps.present_modes[0],
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 1,
+        };
+        surface.configure(&device, &config);
+
+        let ctx = Arc::new(Context::new(device, queue, config.format));
+
+        Ok(Self::with_target(ctx, Some(surface), None, config))
+    }
+
+    /// Create a renderer drawing into an offscreen texture of the given size.
+    ///
+    /// The adapter can be picked with the `WGPU_BACKEND` and
+    /// `WGPU_ADAPTER_NAME` environment variables. When no hardware adapter is
+    /// available, a software one is used so it also works on machines without
+    /// GPU. Use [`Renderer::capture`] to read back what was drawn.
+    pub async fn headless(width: u32, height: u32) -> Result<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::util::backend_bits_from_env().unwrap_or_default(),
+            ..Default::default()
+        });
+        let adapter =
+            match wgpu::util::initialize_adapter_from_env_or_default(&instance, None).await {
+                Some(adapter) => Some(adapter),
+                None => {
+                    instance
+                        .request_adapter(&wgpu::RequestAdapterOptions {
+                            force_fallback_adapter: true,
+                            ..Default::default()
+                        })
+                        .await
+                }
+            }
+            .ok_or(Error::NoAdapter)?;
+        let (device, queue) = request_device(&adapter).await?;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: HEADLESS_FORMAT,
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 1,
+        };
+        let target = create_target(&device, &config);
+
+        let ctx = Arc::new(Context::new(device, queue, config.format));
+
+        Ok(Self::with_target(ctx, None, Some(target), config))
+    }
+
+    fn with_target(
+        ctx: Ctx,
+        surface: Option<wgpu::Surface<'static>>,
+        target: Option<wgpu::Texture>,
+        config: wgpu::SurfaceConfiguration,
+    ) -> Self {
+        let device = &ctx.device;
+        let layouts = &ctx.bind_group_layouts;
+
+        let (mouse_buffer, mouse_bind_group) =
+            create_uniform(device, &layouts.mouse, "mouse", Vec2::ZERO);
+        let (resolution_buffer, resolution_bind_group) = create_uniform(
+            device,
+            &layouts.resolution,
+            "resolution",
+            resolution(&config),
+        );
+        let (projection_buffer, projection_bind_group) = create_uniform(
+            device,
+            &layouts.projection,
+            "projection",
+            projection(&config),
+        );
+
+        let shader = ctx.standard_shader();
+        let pipeline = shader.pipeline(&ctx, BlendMode::Alpha, VertexLayout::Shape);
+        let instanced_pipeline = shader.pipeline(&ctx, BlendMode::Alpha, VertexLayout::Instanced);
+
+        Self {
+            ctx,
+            surface,
+            target,
+            config,
+            pipeline,
+            instanced_pipeline,
+            mouse_buffer,
+            mouse_bind_group,
+            resolution_buffer,
+            resolution_bind_group,
+            projection_buffer,
+            projection_bind_group,
+        }
+    }
+
+    /// Shared handle on the device, needed to create meshes and textures.
+    pub fn context(&self) -> &Ctx {
+        &self.ctx
+    }
+
+    /// Size of the render target in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.config.width, self.config.height)
+    }
+
+    /// Resize the render target and update the projection accordingly.
+    ///
+    /// Null sizes, sent by some platforms when the window is minimized, are
+    /// ignored.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.config.width = width;
+        self.config.height = height;
+
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.ctx.device, &self.config);
+        }
+        if self.target.is_some() {
+            self.target = Some(create_target(&self.ctx.device, &self.config));
+        }
+
+        self.ctx.queue.write_buffer(
+            &self.resolution_buffer,
+            0,
+            bytemuck::cast_slice(&[resolution(&self.config)]),
+        );
+        self.ctx.queue.write_buffer(
+            &self.projection_buffer,
+            0,
+            bytemuck::cast_slice(&[projection(&self.config)]),
+        );
+    }
+
+    pub fn set_mouse_position(&self, position: Vec2) {
+        self.ctx
+            .queue
+            .write_buffer(&self.mouse_buffer, 0, bytemuck::cast_slice(&[position]));
+    }
+
+    /// Acquire the next target texture and start recording commands.
+    pub fn begin_frame(&self) -> Result<Frame, wgpu::SurfaceError> {
+        let (surface_texture, view) = match (&self.surface, &self.target) {
+            (Some(surface), _) => {
+                let output = surface.get_current_texture()?;
+                let view = output
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor::default());
+
+                (Some(output), view)
+            }
+            (None, Some(target)) => (
+                None,
+                target.create_view(&wgpu::TextureViewDescriptor::default()),
+            ),
+            (None, None) => unreachable!("a renderer always has a surface or a target"),
+        };
+        let encoder = self
+            .ctx
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        Ok(Frame {
+            surface_texture,
+            view,
+            encoder,
+        })
+    }
+
+    /// Start a render pass on `frame` with the standard pipeline and global
+    /// uniforms bound. The target is cleared with `clear` when given.
+    pub fn begin_pass<'a>(
+        &'a self,
+        frame: &'a mut Frame,
+        clear: Option<wgpu::Color>,
+    ) -> wgpu::RenderPass<'a> {
+        self.pass(
+            &mut frame.encoder,
+            &frame.view,
+            clear,
+            &self.resolution_bind_group,
+            &self.projection_bind_group,
+        )
+    }
+
+    /// Start a render pass drawing into `target` instead of the frame, with
+    /// the same pipeline and a projection mapping world coordinates to the
+    /// pixels of the texture.
+    ///
+    /// The pass is recorded with the commands of `frame`, before the ones of
+    /// the passes started after it, so the texture can be drawn in the same
+    /// frame once the pass is dropped.
+    pub fn begin_texture_pass<'a>(
+        &'a self,
+        frame: &'a mut Frame,
+        target: &'a RenderTexture,
+        clear: Option<wgpu::Color>,
+    ) -> wgpu::RenderPass<'a> {
+        self.pass(
+            &mut frame.encoder,
+            &target.texture().view,
+            clear,
+            &target.resolution_bind_group,
+            &target.projection_bind_group,
+        )
+    }
+
+    fn pass<'a>(
+        &'a self,
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &'a wgpu::TextureView,
+        clear: Option<wgpu::Color>,
+        resolution_bind_group: &'a wgpu::BindGroup,
+        projection_bind_group: &'a wgpu::BindGroup,
+    ) -> wgpu::RenderPass<'a> {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: clear.map_or(wgpu::LoadOp::Load, wgpu::LoadOp::Clear),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            ..Default::default()
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.mouse_bind_group, &[]);
+        render_pass.set_bind_group(1, resolution_bind_group, &[]);
+        render_pass.set_bind_group(2, projection_bind_group, &[]);
+
+        render_pass
+    }
+
+    /// Draw the next objects through `camera`, into its viewport within a
+    /// target of `target_size` pixels: [`Renderer::size`] for a pass started
+    /// by [`Renderer::begin_pass`], or the size of the texture of a texture
+    /// pass.
+    pub fn set_camera<'a>(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera: &'a Camera2D,
+        target_size: (u32, u32),
+    ) {
+        camera.bind(render_pass, target_size);
+    }
+
+    /// Go back to the default projection, mapping world coordinates to pixels
+    /// over the whole target.
+    pub fn reset_camera<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        let (width, height) = self.size();
+
+        render_pass.set_viewport(0., 0., width as f32, height as f32, 0., 1.);
+        render_pass.set_bind_group(2, &self.projection_bind_group, &[]);
+    }
+
+    /// Draw every instance of `sprites` in a single draw call.
+    ///
+    /// The standard pipeline is bound again afterwards, so meshes can be
+    /// drawn with the same render pass.
+    pub fn draw_instanced<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        sprites: &'a InstancedSprites,
+    ) {
+        if sprites.uploaded == 0 {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.instanced_pipeline);
+        render_pass.set_bind_group(3, &sprites.transform_bind_group, &[]);
+        render_pass.set_bind_group(4, &*sprites.texture().bind_group(), &[]);
+        render_pass.set_vertex_buffer(0, sprites.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, sprites.instance_buffer.slice(..));
+        render_pass.set_index_buffer(sprites.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..QUAD_INDICES.len() as u32, 0, 0..sprites.uploaded);
+        render_pass.set_pipeline(&self.pipeline);
+    }
+
+    /// Submit the recorded commands and present the frame.
+    pub fn end_frame(&self, frame: Frame) {
+        self.ctx
+            .queue
+            .submit(std::iter::once(frame.encoder.finish()));
+
+        if let Some(output) = frame.surface_texture {
+            output.present();
+        }
+    }
+
+    /// Copy the offscreen target of a headless renderer back to the CPU.
+    ///
+    /// Blocks until every submitted frame has been rendered. Fails for
+    /// renderers presenting to a window.
+    pub fn capture(&self) -> Result<image::RgbaImage> {
+        let target = self.target.as_ref().ok_or_else(|| {
+            Error::InvalidArgument("only headless renderers can be captured".to_owned())
+        })?;
+
+        read_texture_blocking(&self.ctx, target)
+    }
+}
+
+async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue)> {
+    // Compressed textures are uploaded as is in whichever formats the
+    // adapter supports
+    let compression = wgpu::Features::TEXTURE_COMPRESSION_BC
+        | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+        | wgpu::Features::TEXTURE_COMPRESSION_ASTC;
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                required_features: adapter.features() & compression,
+                required_limits: adapter.limits(),
+                ..Default::default()
+            },
+            None,
+        )
+        .await?;
+
+    Ok((device, queue))
+}
+
+fn create_target(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("offscreen target"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: config.usage,
+        view_formats: &[],
+    })
+}
+
+pub(super) fn create_uniform<T: bytemuck::Pod>(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    name: &str,
+    value: T,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(&format!("{name} buffer")),
+        contents: bytemuck::cast_slice(&[value]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        }],
+        label: Some(&format!("{name} bind group")),
+    });
+
+    (buffer, bind_group)
+}
+
+fn resolution(config: &wgpu::SurfaceConfiguration) -> Vec2 {
+    Vec2 {
+        x: config.width as f32,
+        y: config.height as f32,
+    }
+}
+
+fn projection(config: &wgpu::SurfaceConfiguration) -> Mat4 {
+    Mat4::orthographic_rh(0., config.width as f32, config.height as f32, 0., -1., 0.)
+}
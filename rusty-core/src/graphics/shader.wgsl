@@ -0,0 +1,71 @@
+// Bindings and vertex stage of the standard pipeline, shared with the
+// fragment shaders of materials.
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) tex_coords: vec2<f32>
+};
+
+struct InstanceInput {
+    @location(3) position: vec2<f32>,
+    @location(4) rotation: f32,
+    @location(5) scale: vec2<f32>,
+    @location(6) uv_rect: vec4<f32>,
+    @location(7) color: vec4<f32>
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) tex_coords: vec2<f32>
+};
+
+@group(0) @binding(0)
+var<uniform> mouse_position: vec2<f32>;
+@group(1) @binding(0)
+var<uniform> resolution: vec2<f32>;
+
+@group(2) @binding(0)
+var<uniform> projection: mat4x4<f32>;
+
+@group(3) @binding(0)
+var<uniform> transforms: mat4x4<f32>;
+
+@group(4) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(4) @binding(1)
+var s_diffuse: sampler;
+@group(4) @binding(2)
+var<uniform> t_size: vec2<f32>;
+
+@vertex
+fn vs_main(
+    model: VertexInput
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = projection * transforms * vec4<f32>(model.position, 1.0);
+    out.color = model.color;
+    out.tex_coords = model.tex_coords / t_size;
+
+    return out;
+}
+
+// Unit quad scaled to the instance UV rectangle, rotated around its center
+@vertex
+fn vs_instanced(
+    model: VertexInput,
+    instance: InstanceInput
+) -> VertexOutput {
+    var out: VertexOutput;
+    let local = (model.position.xy - vec2<f32>(0.5, 0.5)) * instance.uv_rect.zw * instance.scale;
+    let c = cos(instance.rotation);
+    let s = sin(instance.rotation);
+    let rotated = vec2<f32>(local.x * c - local.y * s, local.x * s + local.y * c);
+
+    out.clip_position = projection * transforms * vec4<f32>(rotated + instance.position, model.position.z, 1.0);
+    out.color = model.color * instance.color;
+    out.tex_coords = (instance.uv_rect.xy + model.tex_coords * instance.uv_rect.zw) / t_size;
+
+    return out;
+}
@@ -1,7 +1,7 @@
 use std::{f32::consts::PI, fmt::Debug};
 
-use super::{color, Mesh, Vertex};
-use crate::{math::Rect, Context};
+use super::{color, triangulation::triangulate, Mesh, Vertex, QUAD_INDICES};
+use crate::{math::Rect, Ctx};
 use glam::Vec2;
 use wgpu::{util::DeviceExt, BufferAddress, VertexAttribute, VertexBufferLayout, VertexFormat};
 
@@ -44,6 +44,20 @@ impl Vertex for ShapeVertex {
 pub trait Shape: super::Transformable {
     fn set_fill_color(&mut self, color: color::Color);
 
+    fn fill_color(&self) -> color::Color;
+
+    /// Set the color of the outline, drawn untextured.
+    fn set_outline_color(&mut self, color: color::Color);
+
+    fn outline_color(&self) -> color::Color;
+
+    /// Set the thickness of the outline drawn around the points of the
+    /// shape. Positive values extrude it outwards, negative ones inwards and
+    /// 0 disables it.
+    fn set_outline_thickness(&mut self, thickness: f32);
+
+    fn outline_thickness(&self) -> f32;
+
     fn point(&self, index: usize) -> Vec2;
 
     fn set_texture_rect(&mut self, rect: Rect);
@@ -51,118 +65,487 @@ pub trait Shape: super::Transformable {
     fn point_count(&self) -> usize;
 }
 
+/// Maximum length of a miter join, relative to the outline thickness, before
+/// it is clamped. Same default as SVG.
+const MITER_LIMIT: f32 = 4.;
+
+/// Default maximum distance between curves and the segments approximating
+/// them, in local units.
+const PATH_TOLERANCE: f32 = 0.25;
+
+/// Maximum number of segments approximating a single curve or arc.
+const MAX_CURVE_SEGMENTS: usize = 256;
+
+/// Number of segments approximating an arc of `radius` over `angle` radians
+/// within `tolerance`.
+fn arc_segments(radius: f32, angle: f32, tolerance: f32) -> usize {
+    let step = 2. * (1. - (tolerance / radius).min(1.)).acos();
+
+    ((angle.abs() / step).ceil() as usize).clamp(1, MAX_CURVE_SEGMENTS)
+}
+
+/// Number of segments approximating a Bezier curve within `tolerance`, from
+/// the maximum length of its second derivative.
+fn curve_segments(second_derivative: f32, tolerance: f32) -> usize {
+    ((second_derivative / (8. * tolerance)).sqrt().ceil() as usize).clamp(1, MAX_CURVE_SEGMENTS)
+}
+
+/// How the ends of an open line are drawn.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LineCap {
+    /// Stop at the end points.
+    #[default]
+    Butt,
+    /// Extend past the end points by half the thickness.
+    Square,
+    /// Half disc centered on the end points.
+    Round,
+}
+
+/// How consecutive segments of a line are connected.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LineJoin {
+    /// Sharp corner, beveled when longer than 4 times the thickness.
+    #[default]
+    Miter,
+    /// Corner cut straight.
+    Bevel,
+    /// Rounded corner.
+    Round,
+}
+
+/// Triangles of a shape, in local coordinates.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct Geometry {
+    pub(crate) vertices: Vec<ShapeVertex>,
+    pub(crate) indices: Vec<u32>,
+}
+
+impl Geometry {
+    /// Add a triangle, reordering its vertices if needed so it is not culled.
+    pub(crate) fn push_triangle(&mut self, [a, b, c]: [u32; 3]) {
+        let position = |i: u32| {
+            let [x, y, _] = self.vertices[i as usize].position;
+            Vec2::new(x, y)
+        };
+        let (pa, pb, pc) = (position(a), position(b), position(c));
+
+        // With y pointing down, front faces are clockwise in local coordinates
+        if (pb - pa).perp_dot(pc - pa) <= 0. {
+            self.indices.extend([a, b, c]);
+        } else {
+            self.indices.extend([a, c, b]);
+        }
+    }
+
+    /// Add a vertex, returning its index.
+    fn push_vertex(&mut self, position: Vec2, color: [f32; 4], tex_coords: [f32; 2]) -> u32 {
+        self.vertices.push(ShapeVertex {
+            position: [position.x, position.y, 0.],
+            color,
+            tex_coords,
+        });
+
+        (self.vertices.len() - 1) as u32
+    }
+
+    /// Fan of triangles from `center` to the arc starting at `center + from`
+    /// and turning by `angle` radians.
+    fn push_arc(
+        &mut self,
+        center: (Vec2, [f32; 2]),
+        from: Vec2,
+        angle: f32,
+        color: [f32; 4],
+        tex_coords: impl Fn(Vec2) -> [f32; 2],
+    ) {
+        let segments = arc_segments(from.length(), angle, PATH_TOLERANCE);
+        let center_index = self.push_vertex(center.0, color, center.1);
+        let mut previous = self.push_vertex(center.0 + from, color, tex_coords(from));
+
+        for i in 1..=segments {
+            let offset = Vec2::from_angle(angle * i as f32 / segments as f32).rotate(from);
+            let index = self.push_vertex(center.0 + offset, color, tex_coords(offset));
+            self.push_triangle([center_index, previous, index]);
+            previous = index;
+        }
+    }
+
+    /// Stroke of width `thickness` along `points`, closed or not.
+    ///
+    /// Texture coordinates follow the stroke: x is the distance along it and
+    /// y goes from 0 to `thickness` across it. Segments and joins overlap on
+    /// the inner side of corners, which shows with translucent colors.
+    pub(crate) fn stroke(
+        points: &[Vec2],
+        closed: bool,
+        thickness: f32,
+        cap: LineCap,
+        join: LineJoin,
+        color: [f32; 4],
+    ) -> Self {
+        let mut stroke = Self::default();
+        let mut points = points.to_vec();
+        points.dedup();
+        if closed && points.len() > 2 && points.first() == points.last() {
+            points.pop();
+        }
+        let half = thickness.abs() / 2.;
+        if points.len() < 2 || half == 0. {
+            return stroke;
+        }
+
+        let count = points.len();
+        let segment_count = if closed { count } else { count - 1 };
+        // Left of the direction with y pointing up, so the right on screen
+        let normal = |direction: Vec2| direction.perp() * half;
+        // Distance along the stroke at each point
+        let mut distances = vec![0.; segment_count + 1];
+        for i in 0..segment_count {
+            distances[i + 1] = distances[i] + points[i].distance(points[(i + 1) % count]);
+        }
+        // Texture coordinates of `offset` relative to a point of the stroke
+        let along = |distance: f32, direction: Vec2| {
+            move |offset: Vec2| {
+                [
+                    distance + offset.dot(direction),
+                    half - offset.dot(direction.perp()),
+                ]
+            }
+        };
+
+        for i in 0..segment_count {
+            let (mut a, mut b) = (points[i], points[(i + 1) % count]);
+            let direction = (b - a).normalize();
+            let n = normal(direction);
+            let (mut start, mut end) = (distances[i], distances[i + 1]);
+
+            if !closed && cap == LineCap::Square {
+                if i == 0 {
+                    a -= direction * half;
+                    start -= half;
+                }
+                if i == segment_count - 1 {
+                    b += direction * half;
+                    end += half;
+                }
+            }
+
+            let quad = [
+                stroke.push_vertex(a + n, color, [start, 0.]),
+                stroke.push_vertex(a - n, color, [start, thickness.abs()]),
+                stroke.push_vertex(b + n, color, [end, 0.]),
+                stroke.push_vertex(b - n, color, [end, thickness.abs()]),
+            ];
+            stroke.push_triangle([quad[0], quad[1], quad[2]]);
+            stroke.push_triangle([quad[1], quad[3], quad[2]]);
+        }
+
+        let joins = if closed { 0..count } else { 1..count - 1 };
+        for i in joins {
+            let point = points[i];
+            let d1 = (point - points[(i + count - 1) % count]).normalize();
+            let d2 = (points[(i + 1) % count] - point).normalize();
+            let turn = d1.perp_dot(d2);
+            if turn.abs() <= f32::EPSILON && d1.dot(d2) > 0. {
+                continue;
+            }
+
+            // The gap between segments is on the outer side of the turn
+            let side = if turn > 0. { -1. } else { 1. };
+            let (n1, n2) = (normal(d1) * side, normal(d2) * side);
+            let tex_coords = along(distances[i], d1);
+            let center = (point, tex_coords(Vec2::ZERO));
+
+            match join {
+                LineJoin::Round => stroke.push_arc(center, n1, n1.angle_to(n2), color, tex_coords),
+                LineJoin::Miter | LineJoin::Bevel => {
+                    let p = stroke.push_vertex(point, color, center.1);
+                    let o1 = stroke.push_vertex(point + n1, color, tex_coords(n1));
+                    let o2 = stroke.push_vertex(point + n2, color, tex_coords(n2));
+
+                    // Miter length relative to the thickness, as in outlines
+                    let cos = 1. + (n1 / half).dot(n2 / half);
+                    let miter = (n1 + n2) / cos;
+                    if join == LineJoin::Miter
+                        && cos > f32::EPSILON
+                        && miter.length() <= MITER_LIMIT * half
+                    {
+                        let m = stroke.push_vertex(point + miter, color, tex_coords(miter));
+                        stroke.push_triangle([p, o1, m]);
+                        stroke.push_triangle([p, m, o2]);
+                    } else {
+                        stroke.push_triangle([p, o1, o2]);
+                    }
+                }
+            }
+        }
+
+        if !closed && cap == LineCap::Round {
+            let (first, last) = (points[0], points[count - 1]);
+            let d1 = (points[1] - first).normalize();
+            let d2 = (last - points[count - 2]).normalize();
+            let end = distances[segment_count];
+
+            let start_coords = along(0., d1);
+            let end_coords = along(end, d2);
+            stroke.push_arc(
+                (first, start_coords(Vec2::ZERO)),
+                normal(d1),
+                PI,
+                color,
+                start_coords,
+            );
+            stroke.push_arc(
+                (last, end_coords(Vec2::ZERO)),
+                -normal(d2),
+                PI,
+                color,
+                end_coords,
+            );
+        }
+
+        stroke
+    }
+
+    /// Fill of a polygon from its triangles, `bounds` being mapped to
+    /// `texture_rect`.
+    pub(crate) fn polygon(
+        points: &[Vec2],
+        triangles: &[[usize; 3]],
+        color: [f32; 4],
+        bounds: &Rect,
+        texture_rect: &Rect,
+    ) -> Self {
+        let mut polygon = Self {
+            vertices: points
+                .iter()
+                .map(|point| ShapeVertex {
+                    position: [point.x, point.y, 0.],
+                    color,
+                    tex_coords: tex_coords(*point, bounds, texture_rect),
+                })
+                .collect(),
+            indices: Vec::with_capacity(triangles.len() * 3),
+        };
+        for triangle in triangles {
+            polygon.push_triangle(triangle.map(|i| i as u32));
+        }
+
+        polygon
+    }
+
+    /// Add the triangles of `other`.
+    pub(crate) fn append(&mut self, other: &Geometry) {
+        let base = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&other.vertices);
+        self.indices
+            .extend(other.indices.iter().map(|index| base + index));
+    }
+
+    /// Outline ring extruded from the closed polygon `points`, with miter
+    /// joins.
+    pub(crate) fn outline(points: &[Vec2], thickness: f32, color: [f32; 4]) -> Self {
+        let mut outline = Self::default();
+        let count = points.len();
+        if count < 2 || thickness == 0. {
+            return outline;
+        }
+
+        // Normals point outwards whatever the winding order of the points
+        let area: f32 = (0..count)
+            .map(|i| points[i].perp_dot(points[(i + 1) % count]))
+            .sum();
+        let sign = if area >= 0. { 1. } else { -1. };
+        let normal = |a: Vec2, b: Vec2| {
+            let direction = (b - a).normalize_or_zero();
+            Vec2::new(direction.y, -direction.x) * sign
+        };
+
+        for i in 0..count {
+            let previous = points[(i + count - 1) % count];
+            let point = points[i];
+            let next = points[(i + 1) % count];
+            let (n1, n2) = (normal(previous, point), normal(point, next));
+
+            // Offset whose projection on both normals is 1
+            let cos = 1. + n1.dot(n2);
+            let miter = if cos > f32::EPSILON {
+                ((n1 + n2) / cos).clamp_length_max(MITER_LIMIT)
+            } else {
+                n2
+            };
+
+            for position in [point, point + miter * thickness] {
+                outline.vertices.push(ShapeVertex {
+                    position: [position.x, position.y, 0.],
+                    color,
+                    tex_coords: [0., 0.],
+                });
+            }
+        }
+
+        for i in 0..count {
+            let (inner, outer) = (2 * i as u32, 2 * i as u32 + 1);
+            let j = (i + 1) % count;
+            let (next_inner, next_outer) = (2 * j as u32, 2 * j as u32 + 1);
+
+            outline.push_triangle([inner, outer, next_inner]);
+            outline.push_triangle([outer, next_outer, next_inner]);
+        }
+
+        outline
+    }
+}
+
+/// Send the fill and outline of a shape to its mesh, the outline being drawn
+/// untextured after the fill.
+fn write_shape(mesh: &mut Mesh, fill: &Geometry, outline: &Geometry) {
+    let mut geometry = fill.clone();
+    geometry.append(outline);
+
+    mesh.write_geometry(
+        &geometry.vertices,
+        &geometry.indices,
+        fill.indices.len() as u32,
+    );
+}
+
+/// Texture coordinates, in pixels, of `point` of a shape whose bounds are
+/// mapped to `texture_rect`.
+fn tex_coords(point: Vec2, bounds: &Rect, texture_rect: &Rect) -> [f32; 2] {
+    let ratio = |value: f32, size: f32| if size == 0. { 0. } else { value / size };
+
+    [
+        texture_rect.x + ratio(point.x - bounds.x, bounds.width) * texture_rect.width,
+        texture_rect.y + ratio(point.y - bounds.y, bounds.height) * texture_rect.height,
+    ]
+}
+
+/// Implement [`super::Transformable`] for a shape whose transform is the one
+/// of its `mesh` field.
+macro_rules! impl_transformable {
+    ($shape:ty) => {
+        impl super::Transformable for $shape {
+            fn set_position(&mut self, position: Vec2) {
+                self.mesh.transform.position = position;
+                self.mesh.update_transform();
+            }
+
+            fn position(&self) -> &Vec2 {
+                &self.mesh.transform.position
+            }
+
+            fn r#move(&mut self, offset: Vec2) {
+                self.mesh.transform.position += offset;
+                self.mesh.update_transform();
+            }
+
+            fn set_rotation(&mut self, rotation: f32) {
+                self.mesh.transform.rotation = rotation;
+                self.mesh.update_transform();
+            }
+
+            fn rotation(&self) -> f32 {
+                self.mesh.transform.rotation
+            }
+
+            fn rotate(&mut self, angle: f32) {
+                self.mesh.transform.rotation = (self.mesh.transform.rotation + angle) % (2. * PI);
+                self.mesh.update_transform();
+            }
+
+            fn set_scale(&mut self, scale: f32) {
+                self.set_scale_xy(Vec2::splat(scale));
+            }
+
+            fn scale(&self) -> f32 {
+                self.mesh.transform.scale.x
+            }
+
+            fn set_scale_xy(&mut self, scale: Vec2) {
+                self.mesh.transform.scale = scale;
+                self.mesh.update_transform();
+            }
+
+            fn scale_xy(&self) -> Vec2 {
+                self.mesh.transform.scale
+            }
+
+            fn set_origin(&mut self, origin: Vec2) {
+                self.mesh.transform.origin = origin;
+                self.mesh.update_transform();
+            }
+
+            fn origin(&self) -> &Vec2 {
+                &self.mesh.transform.origin
+            }
+        }
+    };
+}
+
 pub struct RectangleShape {
-    // transform: super::Transform,
     size: Vec2,
     mesh: Mesh,
-    vertices: Vec<ShapeVertex>,
+    fill: Geometry,
+    outline: Geometry,
     color: color::Color,
-    texture_rect: Rect, // pub texture: texture::Texture
+    outline_color: color::Color,
+    outline_thickness: f32,
+    texture_rect: Rect,
 }
 
 impl RectangleShape {
-    pub fn mesh(&mut self) -> &mut Mesh {
-        &mut self.mesh
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
     }
 
-    pub fn new(size: Vec2) -> Self {
-        let gl_context = Context::get();
-        // Generate buffer
-        let vertices = vec![
-            ShapeVertex {
-                position: [0., 0., 0.],
-                color: [1., 1., 1., 1.0],
-                tex_coords: [0., 0.],
-            },
-            ShapeVertex {
-                position: [0., size.y, 0.],
-                color: [1., 1., 1., 1.0],
-                tex_coords: [0., 36.],
-            },
-            ShapeVertex {
-                position: [size.x, size.y, 0.],
-                color: [1., 1., 1., 1.0],
-                tex_coords: [33., 36.],
-            },
-            ShapeVertex {
-                position: [size.x, 0., 0.],
-                color: [1., 1., 1., 1.0],
-                tex_coords: [33., 0.],
-            },
-        ];
-
-        let indices: Vec<u16> = vec![0, 1, 3, 1, 2, 3];
-
-        let vertex_buffer =
-            gl_context
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Vertex buffer"),
-                    contents: bytemuck::cast_slice(&vertices),
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                });
-        let index_buffer =
-            gl_context
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Index buffer"),
-                    contents: bytemuck::cast_slice(&indices),
-                    usage: wgpu::BufferUsages::INDEX,
-                });
+    pub fn mesh_mut(&mut self) -> &mut Mesh {
+        &mut self.mesh
+    }
 
-        // let transform = Transform::default();
-        // let transform_buffer = ctx.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        //     label: Some("Transform buffer"),
-        //     contents: bytemuck::cast_slice(&[transform.to_model_matrix()]),
-        //     usage: wgpu::BufferUsages::UNIFORM
-        // });
-        // let transform_bind_group_layout = ctx.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        //     entries: &[wgpu::BindGroupLayoutEntry {
-        //         binding: 0,
-        //         visibility: wgpu::ShaderStages::VERTEX,
-        //         ty: wgpu::BindingType::Buffer {
-        //             ty: wgpu::BufferBindingType::Uniform,
-        //             has_dynamic_offset: false,
-        //             min_binding_size: None
-        //         },
-        //         count: None
-        //     }],
-        //     label: Some("transform bind group layout")
-        // });
-        // let transform_bind_group = ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
-        //     layout: &transform_bind_group_layout,
-        //     entries: &[wgpu::BindGroupEntry {
-        //         binding: 0,
-        //         resource: transform_buffer.as_entire_binding()
-        //     }],
-        //     label: Some("transform bind group")
-        // });
-
-        // let mesh = Mesh {
-        //     vertex_buffer,
-        //     index_buffer,
-        //     num_elements: indices.len() as u32,
-        // };
-        let mesh = Mesh::new(
-            vertex_buffer,
-            index_buffer,
-            indices.len() as u32,
-            // transform_buffer,
-            // transform_bind_group
-        );
+    /// Create a white rectangle, its texture rectangle covering `size` pixels
+    /// from the top-left corner of the texture.
+    pub fn new(ctx: &Ctx, size: Vec2) -> Self {
+        let vertices = [ShapeVertex {
+            position: [0., 0., 0.],
+            color: [1., 1., 1., 1.],
+            tex_coords: [0., 0.],
+        }; 4];
+        let indices = QUAD_INDICES;
+
+        let vertex_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Vertex buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            });
+        let index_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Index buffer"),
+                contents: bytemuck::cast_slice(&indices),
+                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            });
+        let mesh = Mesh::new(ctx, vertex_buffer, index_buffer, indices.len() as u32);
 
-        let rect = Self {
-            // transform,
+        let mut rect = Self {
             size,
             mesh,
-            // vertex_buffer,
-            vertices,
-            // texture: Texture::empty(context.clone()).unwrap(),
+            fill: Geometry::default(),
+            outline: Geometry::default(),
             color: color::WHITE,
-            texture_rect: Default::default(),
+            outline_color: color::WHITE,
+            outline_thickness: 0.,
+            texture_rect: Rect {
+                x: 0.,
+                y: 0.,
+                width: size.x,
+                height: size.y,
+            },
         };
-
-        // rect.update();
+        rect.update();
 
         rect
     }
@@ -177,42 +560,46 @@ impl RectangleShape {
     }
 
     fn update(&mut self) {
-        for i in 0..self.point_count() {
-            let point = self.point(i);
-
-            if let Some(vertex) = self.vertices.get_mut(i) {
-                vertex.position = [point.x, point.y, 0.0];
-                vertex.color = self.color.into();
-                vertex.tex_coords = match i {
-                    0 => [self.texture_rect.x, self.texture_rect.y],
-                    1 => [
-                        self.texture_rect.x,
-                        self.texture_rect.y + self.texture_rect.height,
-                    ],
-                    2 => [
-                        self.texture_rect.x + self.texture_rect.width,
-                        self.texture_rect.y + self.texture_rect.height,
-                    ],
-                    _ => [
-                        self.texture_rect.x + self.texture_rect.width,
-                        self.texture_rect.y,
-                    ],
-                };
-            }
+        let points: Vec<Vec2> = (0..self.point_count()).map(|i| self.point(i)).collect();
+        let bounds = Rect {
+            x: 0.,
+            y: 0.,
+            width: self.size.x,
+            height: self.size.y,
+        };
+        let color = self.color.to_linear();
+
+        self.fill = Geometry {
+            vertices: points
+                .iter()
+                .map(|point| ShapeVertex {
+                    position: [point.x, point.y, 0.],
+                    color,
+                    tex_coords: tex_coords(*point, &bounds, &self.texture_rect),
+                })
+                .collect(),
+            indices: QUAD_INDICES.to_vec(),
+        };
+        self.outline = Geometry::outline(
+            &points,
+            self.outline_thickness,
+            self.outline_color.to_linear(),
+        );
 
-            // TODO : writter buffer
-            let gl_context = Context::get();
-            gl_context.queue.write_buffer(
-                &self.mesh.vertex_buffer,
-                0,
-                bytemuck::cast_slice(&self.vertices),
-            );
-        }
+        write_shape(&mut self.mesh, &self.fill, &self.outline);
     }
 
     pub fn size(&self) -> &Vec2 {
         &self.size
     }
+
+    pub(crate) fn fill(&self) -> &Geometry {
+        &self.fill
+    }
+
+    pub(crate) fn outline(&self) -> &Geometry {
+        &self.outline
+    }
 }
 
 impl Shape for RectangleShape {
@@ -231,272 +618,1089 @@ impl Shape for RectangleShape {
     }
 
     fn point_count(&self) -> usize {
-        self.vertices.len()
+        4
     }
 
     fn set_fill_color(&mut self, color: color::Color) {
         self.color = color;
         self.update();
     }
+
+    fn fill_color(&self) -> color::Color {
+        self.color
+    }
+
+    fn set_outline_color(&mut self, color: color::Color) {
+        self.outline_color = color;
+        self.update();
+    }
+
+    fn outline_color(&self) -> color::Color {
+        self.outline_color
+    }
+
+    fn set_outline_thickness(&mut self, thickness: f32) {
+        self.outline_thickness = thickness;
+        self.update();
+    }
+
+    fn outline_thickness(&self) -> f32 {
+        self.outline_thickness
+    }
 }
 
-impl super::Transformable for RectangleShape {
-    fn set_position(&mut self, position: Vec2) {
-        self.mesh.transform.position = position;
+impl_transformable!(RectangleShape);
+
+pub struct CircleShape {
+    radius: f32,
+    point_count: u8,
+    pub mesh: Mesh,
+    fill: Geometry,
+    outline: Geometry,
+    color: color::Color,
+    outline_color: color::Color,
+    outline_thickness: f32,
+    texture_rect: Rect,
+}
+
+impl CircleShape {
+    /// Create a white circle centered on its position, approximated by
+    /// `point_count` points.
+    pub fn new(ctx: &Ctx, radius: f32, point_count: u8) -> Self {
+        let vertex_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Vertex buffer"),
+                contents: &[0; std::mem::size_of::<ShapeVertex>()],
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            });
+        let index_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Index buffer"),
+                contents: &[0; 4],
+                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+            });
+        let mesh = Mesh::new(ctx, vertex_buffer, index_buffer, 0);
+
+        let mut circle = Self {
+            radius,
+            point_count,
+            mesh,
+            fill: Geometry::default(),
+            outline: Geometry::default(),
+            color: color::WHITE,
+            outline_color: color::WHITE,
+            outline_thickness: 0.,
+            texture_rect: Rect::default(),
+        };
+        circle.update();
+
+        circle
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    fn update(&mut self) {
+        let points: Vec<Vec2> = (0..self.point_count()).map(|i| self.point(i)).collect();
+        let bounds = Rect {
+            x: -self.radius,
+            y: -self.radius,
+            width: 2. * self.radius,
+            height: 2. * self.radius,
+        };
+        let color = self.color.to_linear();
+
+        // Triangle fan around the center
+        let mut fill = Geometry::default();
+        for point in std::iter::once(Vec2::ZERO).chain(points.iter().copied()) {
+            fill.vertices.push(ShapeVertex {
+                position: [point.x, point.y, 0.],
+                color,
+                tex_coords: tex_coords(point, &bounds, &self.texture_rect),
+            });
+        }
+        let count = points.len() as u32;
+        for i in 0..count {
+            fill.push_triangle([0, i + 1, (i + 1) % count + 1]);
+        }
 
-        let gl_context = Context::get();
-        gl_context.queue.write_buffer(
-            &self.mesh.buffer,
-            0,
-            bytemuck::cast_slice(&[self.mesh.transform.to_model_matrix()]),
+        self.fill = fill;
+        self.outline = Geometry::outline(
+            &points,
+            self.outline_thickness,
+            self.outline_color.to_linear(),
         );
 
+        write_shape(&mut self.mesh, &self.fill, &self.outline);
+    }
+}
+
+impl Shape for CircleShape {
+    fn set_texture_rect(&mut self, rect: Rect) {
+        self.texture_rect = rect;
         self.update();
     }
 
-    fn position(&self) -> &Vec2 {
-        &self.mesh.transform.position
+    /// Point on the circle, relative to its center.
+    fn point(&self, index: usize) -> Vec2 {
+        let angle = -2. * PI * index as f32 / self.point_count as f32;
+
+        Vec2::from_angle(angle) * self.radius
     }
 
-    fn r#move(&mut self, offset: Vec2) {
-        self.mesh.transform.position += offset;
+    fn point_count(&self) -> usize {
+        self.point_count as usize
+    }
 
-        let gl_context = Context::get();
-        gl_context.queue.write_buffer(
-            &self.mesh.buffer,
-            0,
-            bytemuck::cast_slice(&[self.mesh.transform.to_model_matrix()]),
-        );
+    fn set_fill_color(&mut self, color: color::Color) {
+        self.color = color;
+        self.update();
+    }
 
-        // self.update();
+    fn fill_color(&self) -> color::Color {
+        self.color
     }
 
-    fn set_rotation(&mut self, rotation: f32) {
-        self.mesh.transform.rotation = rotation;
+    fn set_outline_color(&mut self, color: color::Color) {
+        self.outline_color = color;
+        self.update();
+    }
 
+    fn outline_color(&self) -> color::Color {
+        self.outline_color
+    }
+
+    fn set_outline_thickness(&mut self, thickness: f32) {
+        self.outline_thickness = thickness;
         self.update();
+    }
 
-        let gl_context = Context::get();
-        gl_context.queue.write_buffer(
-            &self.mesh.buffer,
-            0,
-            bytemuck::cast_slice(&[self.mesh.transform.to_model_matrix()]),
-        );
+    fn outline_thickness(&self) -> f32 {
+        self.outline_thickness
     }
+}
+
+impl_transformable!(CircleShape);
+
+/// Implement the color and outline accessors of [`Shape`] for a shape with
+/// `color`, `outline_color` and `outline_thickness` fields and an `update`
+/// method.
+macro_rules! impl_shape_style {
+    () => {
+        fn set_fill_color(&mut self, color: color::Color) {
+            self.color = color;
+            self.update();
+        }
+
+        fn fill_color(&self) -> color::Color {
+            self.color
+        }
+
+        fn set_outline_color(&mut self, color: color::Color) {
+            self.outline_color = color;
+            self.update();
+        }
+
+        fn outline_color(&self) -> color::Color {
+            self.outline_color
+        }
+
+        fn set_outline_thickness(&mut self, thickness: f32) {
+            self.outline_thickness = thickness;
+            self.update();
+        }
+
+        fn outline_thickness(&self) -> f32 {
+            self.outline_thickness
+        }
+    };
+}
+
+/// Mesh with empty buffers, filled by the first `update` of a shape.
+pub(super) fn empty_mesh(ctx: &Ctx) -> Mesh {
+    let vertex_buffer = ctx
+        .device
+        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex buffer"),
+            contents: &[0; std::mem::size_of::<ShapeVertex>()],
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+    let index_buffer = ctx
+        .device
+        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index buffer"),
+            contents: &[0; 4],
+            usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+    Mesh::new(ctx, vertex_buffer, index_buffer, 0)
+}
 
-    fn rotation(&self) -> f32 {
-        self.mesh.transform.rotation
+/// Smallest rectangle containing `points`.
+fn bounding_rect(points: &[Vec2]) -> Rect {
+    if points.is_empty() {
+        return Rect::default();
     }
+    let min = points.iter().copied().reduce(Vec2::min).unwrap();
+    let max = points.iter().copied().reduce(Vec2::max).unwrap();
+
+    Rect {
+        x: min.x,
+        y: min.y,
+        width: max.x - min.x,
+        height: max.y - min.y,
+    }
+}
 
-    fn rotate(&mut self, angle: f32) {
-        // let deg =
-        // let radian = deg * PI / 180;
+/// Convex polygon whose points are set one by one, like a triangle or a
+/// hexagon.
+///
+/// Points must be given in order, clockwise or not. Use [`PolygonShape`] for
+/// concave polygons.
+pub struct ConvexShape {
+    points: Vec<Vec2>,
+    pub mesh: Mesh,
+    fill: Geometry,
+    outline: Geometry,
+    color: color::Color,
+    outline_color: color::Color,
+    outline_thickness: f32,
+    texture_rect: Option<Rect>,
+}
 
-        if self.mesh.transform.rotation + angle > 360. {
-            self.mesh.transform.rotation = 0.;
-        } else {
-            self.mesh.transform.rotation += angle;
+impl ConvexShape {
+    /// Create a white shape with `point_count` points at the origin.
+    pub fn new(ctx: &Ctx, point_count: usize) -> Self {
+        let mut shape = Self {
+            points: vec![Vec2::ZERO; point_count],
+            mesh: empty_mesh(ctx),
+            fill: Geometry::default(),
+            outline: Geometry::default(),
+            color: color::WHITE,
+            outline_color: color::WHITE,
+            outline_thickness: 0.,
+            texture_rect: None,
         };
+        shape.update();
 
-        // println!("Rotation deg: {}", self.mesh.transform.rotation * 180. / PI);
-        // self.update();
+        shape
+    }
 
-        let gl_context = Context::get();
-        gl_context.queue.write_buffer(
-            &self.mesh.buffer,
-            0,
-            bytemuck::cast_slice(&[self.mesh.transform.to_model_matrix()]),
-        );
+    /// Change the number of points, new ones being placed at the origin.
+    pub fn set_point_count(&mut self, count: usize) {
+        self.points.resize(count, Vec2::ZERO);
+        self.update();
     }
 
-    fn set_scale(&mut self, scale: f32) {
-        self.mesh.transform.scale = scale;
+    /// Move the point at `index`, in local coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not lower than the point count.
+    pub fn set_point(&mut self, index: usize, point: Vec2) {
+        self.points[index] = point;
         self.update();
     }
 
-    fn scale(&self) -> f32 {
-        self.mesh.transform.scale
+    /// Replace every point at once.
+    pub fn set_points(&mut self, points: &[Vec2]) {
+        self.points = points.to_vec();
+        self.update();
+    }
+
+    /// Smallest rectangle containing the points, in local coordinates.
+    pub fn local_bounds(&self) -> Rect {
+        bounding_rect(&self.points)
+    }
+
+    fn update(&mut self) {
+        let bounds = self.local_bounds();
+        // Fan from the first point, which covers any convex polygon
+        let triangles: Vec<[usize; 3]> = (1..self.points.len().saturating_sub(1))
+            .map(|i| [0, i, i + 1])
+            .collect();
+
+        self.fill = Geometry::polygon(
+            &self.points,
+            &triangles,
+            self.color.to_linear(),
+            &bounds,
+            &self.texture_rect.unwrap_or(bounds),
+        );
+        self.outline = Geometry::outline(
+            &self.points,
+            self.outline_thickness,
+            self.outline_color.to_linear(),
+        );
+
+        write_shape(&mut self.mesh, &self.fill, &self.outline);
     }
+}
+
+impl Shape for ConvexShape {
+    impl_shape_style!();
 
-    fn set_origin(&mut self, origin: Vec2) {
-        self.mesh.transform.origin = origin;
+    /// Set the part of the texture mapped on the bounds of the shape, in
+    /// pixels. By default, texture pixels match local coordinates.
+    fn set_texture_rect(&mut self, rect: Rect) {
+        self.texture_rect = Some(rect);
         self.update();
     }
 
-    fn origin(&self) -> &Vec2 {
-        &self.mesh.transform.origin
+    fn point(&self, index: usize) -> Vec2 {
+        self.points[index]
+    }
+
+    fn point_count(&self) -> usize {
+        self.points.len()
     }
 }
 
-pub struct CircleShape {
-    radius: f32,
-    point_count: u8,
+impl_transformable!(ConvexShape);
+
+/// Polygon of any shape, concave or with holes, triangulated by ear
+/// clipping.
+///
+/// Contours must be simple: their edges must not cross each other, and holes
+/// must lie inside the outer contour without overlapping.
+pub struct PolygonShape {
+    points: Vec<Vec2>,
+    holes: Vec<Vec<Vec2>>,
     pub mesh: Mesh,
-    vertices: Vec<ShapeVertex>,
+    fill: Geometry,
+    outline: Geometry,
     color: color::Color,
+    outline_color: color::Color,
+    outline_thickness: f32,
+    texture_rect: Option<Rect>,
 }
 
-impl CircleShape {
-    pub fn new(radius: f32, point_count: u8) -> Self {
-        let mut vertices = Vec::with_capacity(point_count as usize + 1);
-        let mut indices: Vec<u16> = Vec::with_capacity(point_count as usize * 3);
+impl PolygonShape {
+    /// Create a white polygon from its outer contour, in local coordinates.
+    pub fn new(ctx: &Ctx, points: &[Vec2]) -> Self {
+        let mut shape = Self {
+            points: points.to_vec(),
+            holes: Vec::new(),
+            mesh: empty_mesh(ctx),
+            fill: Geometry::default(),
+            outline: Geometry::default(),
+            color: color::WHITE,
+            outline_color: color::WHITE,
+            outline_thickness: 0.,
+            texture_rect: None,
+        };
+        shape.update();
 
-        let center = [0.0, 0.0, 0.0];
-        let color = [1.0, 1.0, 1.0, 1.0];
+        shape
+    }
 
-        vertices.push(ShapeVertex {
-            position: center,
-            color,
-            tex_coords: [0., 0.],
-        });
+    /// Replace the outer contour, keeping the holes.
+    pub fn set_points(&mut self, points: &[Vec2]) {
+        self.points = points.to_vec();
+        self.update();
+    }
 
-        for i in 0..point_count {
-            let theta = -2.0 * std::f32::consts::PI * i as f32 / point_count as f32;
-            let x = center[0] + radius * theta.cos();
-            let y = center[1] + radius * theta.sin();
+    /// Cut a hole in the polygon, its outline being drawn inside the hole.
+    pub fn add_hole(&mut self, hole: &[Vec2]) {
+        self.holes.push(hole.to_vec());
+        self.update();
+    }
 
-            vertices.push(ShapeVertex {
-                position: [x, y, 0.0],
-                color,
-                tex_coords: [0., 0.],
-            });
+    pub fn holes(&self) -> &[Vec<Vec2>] {
+        &self.holes
+    }
+
+    pub fn clear_holes(&mut self) {
+        self.holes.clear();
+        self.update();
+    }
+
+    /// Smallest rectangle containing the outer contour, in local coordinates.
+    pub fn local_bounds(&self) -> Rect {
+        bounding_rect(&self.points)
+    }
 
-            indices.push(0);
-            indices.push(i as u16 + 1);
-            indices.push((i + 1) as u16 % point_count as u16 + 1);
+    fn update(&mut self) {
+        let bounds = self.local_bounds();
+        let points: Vec<Vec2> = self
+            .points
+            .iter()
+            .chain(self.holes.iter().flatten())
+            .copied()
+            .collect();
+        let triangles = triangulate(&self.points, &self.holes);
+
+        self.fill = Geometry::polygon(
+            &points,
+            &triangles,
+            self.color.to_linear(),
+            &bounds,
+            &self.texture_rect.unwrap_or(bounds),
+        );
+
+        let color = self.outline_color.to_linear();
+        self.outline = Geometry::outline(&self.points, self.outline_thickness, color);
+        for hole in &self.holes {
+            // Holes are outlined away from the filled area, so inwards
+            self.outline
+                .append(&Geometry::outline(hole, -self.outline_thickness, color));
         }
 
-        let gl_context = Context::get();
-        let vertex_buffer =
-            gl_context
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Vertex buffer"),
-                    contents: bytemuck::cast_slice(&vertices),
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                });
-        let index_buffer =
-            gl_context
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Index buffer"),
-                    contents: bytemuck::cast_slice(&indices),
-                    usage: wgpu::BufferUsages::INDEX,
-                });
+        write_shape(&mut self.mesh, &self.fill, &self.outline);
+    }
+}
+
+impl Shape for PolygonShape {
+    impl_shape_style!();
+
+    /// Set the part of the texture mapped on the bounds of the shape, in
+    /// pixels. By default, texture pixels match local coordinates.
+    fn set_texture_rect(&mut self, rect: Rect) {
+        self.texture_rect = Some(rect);
+        self.update();
+    }
+
+    /// Point of the outer contour.
+    fn point(&self, index: usize) -> Vec2 {
+        self.points[index]
+    }
+
+    fn point_count(&self) -> usize {
+        self.points.len()
+    }
+}
+
+impl_transformable!(PolygonShape);
+
+/// Sequence of lines, Bezier curves and arcs, flattened into segments as it
+/// is built.
+///
+/// A path is made of contours, each one starting with [`Path::move_to`] and
+/// optionally closed with [`Path::close`]. Angles are in radians, positive
+/// ones turning clockwise on screen.
+///
+/// ```ignore
+/// let mut path = Path::new();
+/// path.move_to((0., 0.).into())
+///     .quad_to((50., -40.).into(), (100., 0.).into())
+///     .arc((100., 20.).into(), 20., -FRAC_PI_2, PI);
+/// let polyline = Polyline::from_path(ctx, &path, 3.);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    contours: Vec<Contour>,
+    tolerance: f32,
+}
 
-        let mesh = Mesh::new(vertex_buffer, index_buffer, indices.len() as u32);
+#[derive(Clone, Debug, PartialEq)]
+struct Contour {
+    points: Vec<Vec2>,
+    closed: bool,
+}
 
+impl Default for Path {
+    fn default() -> Self {
         Self {
-            radius,
-            point_count,
-            mesh,
-            vertices,
-            color: color::WHITE,
+            contours: Vec::new(),
+            tolerance: PATH_TOLERANCE,
         }
     }
-
-    fn update(&mut self) {}
 }
 
-impl Shape for CircleShape {
-    fn set_texture_rect(&mut self, _rect: Rect) {}
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    fn point(&self, index: usize) -> Vec2 {
-        let angle = (index as f32 / self.point_count as f32) * 2.0 * PI - (PI / 2.0);
+    /// Open path going through `points`.
+    pub fn from_points(points: &[Vec2]) -> Self {
+        let mut path = Self::new();
+        if !points.is_empty() {
+            path.contours.push(Contour {
+                points: points.to_vec(),
+                closed: false,
+            });
+        }
+
+        path
+    }
 
-        Vec2 {
-            x: self.radius,
-            y: self.radius,
-        } + Vec2 {
-            x: self.radius * angle.cos(),
-            y: self.radius * angle.sin(),
+    /// Set the maximum distance between the curves added next and the
+    /// segments approximating them. Defaults to a quarter of a unit.
+    pub fn set_tolerance(&mut self, tolerance: f32) -> &mut Self {
+        self.tolerance = tolerance.max(f32::EPSILON);
+        self
+    }
+
+    pub fn tolerance(&self) -> f32 {
+        self.tolerance
+    }
+
+    /// Start a new contour at `to`.
+    pub fn move_to(&mut self, to: Vec2) -> &mut Self {
+        self.contours.push(Contour {
+            points: vec![to],
+            closed: false,
+        });
+        self
+    }
+
+    /// Add a straight line to `to`.
+    pub fn line_to(&mut self, to: Vec2) -> &mut Self {
+        self.contour(to).points.push(to);
+        self
+    }
+
+    /// Add a quadratic Bezier curve to `to`.
+    pub fn quad_to(&mut self, control: Vec2, to: Vec2) -> &mut Self {
+        let tolerance = self.tolerance;
+        let contour = self.contour(control);
+        let from = *contour.points.last().unwrap();
+        let segments = curve_segments(2. * (from - 2. * control + to).length(), tolerance);
+
+        contour.points.extend((1..=segments).map(|i| {
+            let t = i as f32 / segments as f32;
+            let u = 1. - t;
+            from * u * u + control * 2. * u * t + to * t * t
+        }));
+        self
+    }
+
+    /// Add a cubic Bezier curve to `to`.
+    pub fn cubic_to(&mut self, control1: Vec2, control2: Vec2, to: Vec2) -> &mut Self {
+        let tolerance = self.tolerance;
+        let contour = self.contour(control1);
+        let from = *contour.points.last().unwrap();
+        let second_derivative = 6.
+            * (from - 2. * control1 + control2)
+                .length()
+                .max((control1 - 2. * control2 + to).length());
+        let segments = curve_segments(second_derivative, tolerance);
+
+        contour.points.extend((1..=segments).map(|i| {
+            let t = i as f32 / segments as f32;
+            let u = 1. - t;
+            from * u * u * u
+                + control1 * 3. * u * u * t
+                + control2 * 3. * u * t * t
+                + to * t * t * t
+        }));
+        self
+    }
+
+    /// Add an arc of circle starting at `start_angle` and turning by `sweep`.
+    ///
+    /// A line joins the current point to the start of the arc, unless the
+    /// path is empty or its last contour is closed.
+    pub fn arc(&mut self, center: Vec2, radius: f32, start_angle: f32, sweep: f32) -> &mut Self {
+        let point = |angle: f32| center + Vec2::from_angle(angle) * radius;
+        let segments = arc_segments(radius, sweep, self.tolerance);
+        let contour = self.contour(point(start_angle));
+
+        contour.points.extend(
+            (0..=segments).map(|i| point(start_angle + sweep * i as f32 / segments as f32)),
+        );
+        self
+    }
+
+    /// Close the current contour with a line back to its first point.
+    pub fn close(&mut self) -> &mut Self {
+        if let Some(contour) = self.contours.last_mut() {
+            contour.closed = true;
         }
+        self
     }
 
-    fn point_count(&self) -> usize {
-        self.vertices.len()
+    pub fn is_empty(&self) -> bool {
+        self.contours.is_empty()
     }
 
-    fn set_fill_color(&mut self, _color: color::Color) {}
+    /// Points of each contour, and whether it is closed.
+    pub fn contours(&self) -> impl Iterator<Item = (&[Vec2], bool)> {
+        self.contours
+            .iter()
+            .map(|contour| (contour.points.as_slice(), contour.closed))
+    }
+
+    /// Smallest rectangle containing the points of the path.
+    pub fn bounds(&self) -> Rect {
+        let points: Vec<Vec2> = self
+            .contours
+            .iter()
+            .flat_map(|contour| contour.points.iter().copied())
+            .collect();
+
+        bounding_rect(&points)
+    }
+
+    /// Contour to extend, a new one starting at `start` when the path is
+    /// empty or its last contour is closed. A closed contour is continued
+    /// from its first point, like in SVG.
+    fn contour(&mut self, start: Vec2) -> &mut Contour {
+        match self.contours.last() {
+            None => self.move_to(start),
+            Some(contour) if contour.closed => self.move_to(contour.points[0]),
+            Some(_) => self,
+        };
+
+        self.contours.last_mut().unwrap()
+    }
 }
 
-impl super::Transformable for CircleShape {
-    fn set_position(&mut self, position: Vec2) {
-        self.mesh.transform.position = position;
+/// Straight line between two points, with a thickness and caps.
+///
+/// The mesh can be textured, texture pixels running along the line and
+/// across its thickness, e.g. for laser beams.
+pub struct LineShape {
+    start: Vec2,
+    end: Vec2,
+    pub mesh: Mesh,
+    thickness: f32,
+    cap: LineCap,
+    color: color::Color,
+}
 
-        let gl_context = Context::get();
-        gl_context.queue.write_buffer(
-            &self.mesh.buffer,
-            0,
-            bytemuck::cast_slice(&[self.mesh.transform.to_model_matrix()]),
-        );
+impl LineShape {
+    /// Create a white line with butt caps, in local coordinates.
+    pub fn new(ctx: &Ctx, start: Vec2, end: Vec2, thickness: f32) -> Self {
+        let mut line = Self {
+            start,
+            end,
+            mesh: empty_mesh(ctx),
+            thickness,
+            cap: LineCap::default(),
+            color: color::WHITE,
+        };
+        line.update();
+
+        line
+    }
 
+    pub fn set_points(&mut self, start: Vec2, end: Vec2) {
+        self.start = start;
+        self.end = end;
         self.update();
     }
 
-    fn position(&self) -> &Vec2 {
-        &self.mesh.transform.position
+    pub fn start(&self) -> Vec2 {
+        self.start
     }
 
-    fn r#move(&mut self, offset: Vec2) {
-        self.mesh.transform.position += offset;
+    pub fn end(&self) -> Vec2 {
+        self.end
+    }
+
+    pub fn length(&self) -> f32 {
+        self.start.distance(self.end)
+    }
 
+    pub fn set_thickness(&mut self, thickness: f32) {
+        self.thickness = thickness;
         self.update();
     }
 
-    fn set_rotation(&mut self, rotation: f32) {
-        self.mesh.transform.rotation = rotation;
+    pub fn thickness(&self) -> f32 {
+        self.thickness
+    }
 
+    pub fn set_cap(&mut self, cap: LineCap) {
+        self.cap = cap;
         self.update();
+    }
 
-        let gl_context = Context::get();
-        gl_context.queue.write_buffer(
-            &self.mesh.buffer,
-            0,
-            bytemuck::cast_slice(&[self.mesh.transform.to_model_matrix()]),
-        );
+    pub fn cap(&self) -> LineCap {
+        self.cap
+    }
+
+    pub fn set_color(&mut self, color: color::Color) {
+        self.color = color;
+        self.update();
     }
 
-    fn rotation(&self) -> f32 {
-        self.mesh.transform.rotation
+    pub fn color(&self) -> color::Color {
+        self.color
     }
 
-    fn rotate(&mut self, angle: f32) {
-        // let deg =
-        // let radian = deg * PI / 180;
+    fn update(&mut self) {
+        let stroke = Geometry::stroke(
+            &[self.start, self.end],
+            false,
+            self.thickness,
+            self.cap,
+            LineJoin::default(),
+            self.color.to_linear(),
+        );
 
-        if self.mesh.transform.rotation + angle > 360. {
-            self.mesh.transform.rotation = 0.;
-        } else {
-            self.mesh.transform.rotation += angle;
+        write_shape(&mut self.mesh, &stroke, &Geometry::default());
+    }
+}
+
+impl_transformable!(LineShape);
+
+/// Line going through several points, or along the contours of a [`Path`].
+///
+/// Like [`LineShape`], it can be textured along its length.
+pub struct Polyline {
+    path: Path,
+    pub mesh: Mesh,
+    thickness: f32,
+    cap: LineCap,
+    join: LineJoin,
+    color: color::Color,
+}
+
+impl Polyline {
+    /// Create a white open line through `points`, in local coordinates.
+    pub fn new(ctx: &Ctx, points: &[Vec2], thickness: f32) -> Self {
+        Self::from_path(ctx, &Path::from_points(points), thickness)
+    }
+
+    /// Create a white line along the contours of `path`.
+    pub fn from_path(ctx: &Ctx, path: &Path, thickness: f32) -> Self {
+        let mut polyline = Self {
+            path: path.clone(),
+            mesh: empty_mesh(ctx),
+            thickness,
+            cap: LineCap::default(),
+            join: LineJoin::default(),
+            color: color::WHITE,
         };
+        polyline.update();
 
-        println!("Rotation deg: {}", self.mesh.transform.rotation * 180. / PI);
-        // self.update();
+        polyline
+    }
 
-        let gl_context = Context::get();
-        gl_context.queue.write_buffer(
-            &self.mesh.buffer,
-            0,
-            bytemuck::cast_slice(&[self.mesh.transform.to_model_matrix()]),
-        );
+    /// Replace the path with an open line through `points`.
+    pub fn set_points(&mut self, points: &[Vec2]) {
+        self.set_path(&Path::from_points(points));
+    }
+
+    pub fn set_path(&mut self, path: &Path) {
+        self.path = path.clone();
+        self.update();
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
     }
 
-    fn set_scale(&mut self, scale: f32) {
-        self.mesh.transform.scale = scale;
+    pub fn set_thickness(&mut self, thickness: f32) {
+        self.thickness = thickness;
         self.update();
     }
 
-    fn scale(&self) -> f32 {
-        self.mesh.transform.scale
+    pub fn thickness(&self) -> f32 {
+        self.thickness
     }
 
-    fn set_origin(&mut self, origin: Vec2) {
-        self.mesh.transform.origin = origin;
+    /// Set the caps of open contours.
+    pub fn set_cap(&mut self, cap: LineCap) {
+        self.cap = cap;
         self.update();
     }
 
-    fn origin(&self) -> &Vec2 {
-        &self.mesh.transform.origin
+    pub fn cap(&self) -> LineCap {
+        self.cap
+    }
+
+    pub fn set_join(&mut self, join: LineJoin) {
+        self.join = join;
+        self.update();
+    }
+
+    pub fn join(&self) -> LineJoin {
+        self.join
+    }
+
+    pub fn set_color(&mut self, color: color::Color) {
+        self.color = color;
+        self.update();
+    }
+
+    pub fn color(&self) -> color::Color {
+        self.color
+    }
+
+    fn update(&mut self) {
+        let color = self.color.to_linear();
+        let mut stroke = Geometry::default();
+        for (points, closed) in self.path.contours() {
+            stroke.append(&Geometry::stroke(
+                points,
+                closed,
+                self.thickness,
+                self.cap,
+                self.join,
+                color,
+            ));
+        }
+
+        write_shape(&mut self.mesh, &stroke, &Geometry::default());
+    }
+}
+
+impl_transformable!(Polyline);
+
+#[cfg(test)]
+mod tests {
+    use super::{Geometry, LineCap, LineJoin, Path};
+    use glam::Vec2;
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    fn square() -> Vec<Vec2> {
+        vec![
+            Vec2::new(0., 0.),
+            Vec2::new(0., 10.),
+            Vec2::new(10., 10.),
+            Vec2::new(10., 0.),
+        ]
+    }
+
+    fn positions(geometry: &Geometry) -> Vec<Vec2> {
+        geometry
+            .vertices
+            .iter()
+            .map(|vertex| Vec2::new(vertex.position[0], vertex.position[1]))
+            .collect()
+    }
+
+    fn assert_front_facing(geometry: &Geometry) {
+        let positions = positions(geometry);
+        for triangle in geometry.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+            assert!((b - a).perp_dot(c - a) <= 0., "{triangle:?} is culled");
+        }
+    }
+
+    #[test]
+    fn outline_extrudes_corners_with_miter_joins() {
+        let outline = Geometry::outline(&square(), 2., [1.; 4]);
+
+        assert_eq!(outline.vertices.len(), 8);
+        assert_eq!(outline.indices.len(), 24);
+        assert_eq!(
+            positions(&outline),
+            [
+                Vec2::new(0., 0.),
+                Vec2::new(-2., -2.),
+                Vec2::new(0., 10.),
+                Vec2::new(-2., 12.),
+                Vec2::new(10., 10.),
+                Vec2::new(12., 12.),
+                Vec2::new(10., 0.),
+                Vec2::new(12., -2.),
+            ]
+        );
+        assert_front_facing(&outline);
+    }
+
+    #[test]
+    fn outline_does_not_depend_on_winding_order() {
+        let mut points = square();
+        points.reverse();
+        let outline = Geometry::outline(&points, 2., [1.; 4]);
+
+        assert_eq!(positions(&outline)[1], Vec2::new(12., -2.));
+        assert_front_facing(&outline);
+    }
+
+    #[test]
+    fn negative_thickness_extrudes_inwards() {
+        let outline = Geometry::outline(&square(), -2., [1.; 4]);
+
+        assert_eq!(positions(&outline)[1], Vec2::new(2., 2.));
+        assert_front_facing(&outline);
+    }
+
+    #[test]
+    fn sharp_miters_are_clamped() {
+        let points = [Vec2::new(0., 0.), Vec2::new(100., 1.), Vec2::new(0., 2.)];
+        let outline = Geometry::outline(&points, 1., [1.; 4]);
+
+        for (inner, outer) in positions(&outline).chunks(2).map(|pair| (pair[0], pair[1])) {
+            assert!(inner.distance(outer) <= super::MITER_LIMIT + 1e-4);
+        }
+    }
+
+    #[test]
+    fn no_outline_without_thickness() {
+        assert!(Geometry::outline(&square(), 0., [1.; 4]).indices.is_empty());
+    }
+
+    fn stroke(points: &[Vec2], closed: bool, cap: LineCap, join: LineJoin) -> Geometry {
+        Geometry::stroke(points, closed, 2., cap, join, [1.; 4])
+    }
+
+    fn corner() -> [Vec2; 3] {
+        [Vec2::new(0., 0.), Vec2::new(10., 0.), Vec2::new(10., 10.)]
+    }
+
+    #[test]
+    fn butt_line_is_a_quad() {
+        let line = stroke(
+            &[Vec2::new(0., 0.), Vec2::new(10., 0.)],
+            false,
+            LineCap::Butt,
+            LineJoin::Miter,
+        );
+
+        assert_eq!(
+            positions(&line),
+            [
+                Vec2::new(0., 1.),
+                Vec2::new(0., -1.),
+                Vec2::new(10., 1.),
+                Vec2::new(10., -1.),
+            ]
+        );
+        assert_eq!(line.vertices[3].tex_coords, [10., 2.]);
+        assert_front_facing(&line);
+    }
+
+    #[test]
+    fn caps_extend_the_ends() {
+        let points = [Vec2::new(0., 0.), Vec2::new(10., 0.)];
+
+        let square = positions(&stroke(&points, false, LineCap::Square, LineJoin::Miter));
+        assert_eq!(square[0], Vec2::new(-1., 1.));
+        assert_eq!(square[3], Vec2::new(11., -1.));
+
+        let round = stroke(&points, false, LineCap::Round, LineJoin::Miter);
+        let (min, max) = positions(&round)
+            .into_iter()
+            .fold((Vec2::MAX, Vec2::MIN), |(min, max), p| {
+                (min.min(p), max.max(p))
+            });
+        // Half discs, within the flattening tolerance
+        assert!(min.x >= -1. - 1e-5 && min.x <= -0.75);
+        assert!(max.x <= 11. + 1e-5 && max.x >= 10.75);
+        assert!(Vec2::new(min.y, max.y).abs_diff_eq(Vec2::new(-1., 1.), 1e-5));
+        assert_front_facing(&round);
+    }
+
+    #[test]
+    fn joins_fill_the_outer_corner() {
+        let miter = positions(&stroke(&corner(), false, LineCap::Butt, LineJoin::Miter));
+        assert!(miter
+            .iter()
+            .any(|p| p.abs_diff_eq(Vec2::new(11., -1.), 1e-5)));
+
+        let bevel = stroke(&corner(), false, LineCap::Butt, LineJoin::Bevel);
+        assert!(!positions(&bevel)
+            .iter()
+            .any(|p| p.abs_diff_eq(Vec2::new(11., -1.), 1e-5)));
+        assert_eq!(bevel.indices.len(), (2 * 2 + 1) * 3);
+
+        let round = stroke(&corner(), false, LineCap::Butt, LineJoin::Round);
+        for p in &positions(&round)[8..] {
+            assert!(p.distance(Vec2::new(10., 0.)) <= 1. + 1e-5);
+        }
+        assert_front_facing(&round);
+    }
+
+    #[test]
+    fn closed_strokes_join_every_point_without_caps() {
+        let bevel = stroke(&square(), true, LineCap::Round, LineJoin::Bevel);
+
+        // 4 segments and 4 joins
+        assert_eq!(bevel.vertices.len(), 4 * 4 + 4 * 3);
+        assert_front_facing(&bevel);
+    }
+
+    #[test]
+    fn degenerate_strokes_are_empty() {
+        let point = [Vec2::ZERO, Vec2::ZERO];
+
+        assert!(stroke(&point, false, LineCap::Round, LineJoin::Round)
+            .vertices
+            .is_empty());
+        assert!(Geometry::stroke(
+            &corner(),
+            false,
+            0.,
+            LineCap::Butt,
+            LineJoin::Miter,
+            [1.; 4]
+        )
+        .vertices
+        .is_empty());
+    }
+
+    #[test]
+    fn curves_are_flattened_within_tolerance() {
+        let (from, control, to) = (Vec2::ZERO, Vec2::new(50., 100.), Vec2::new(100., 0.));
+        let mut path = Path::new();
+        path.move_to(from).quad_to(control, to);
+        let (points, closed) = path.contours().next().unwrap();
+
+        assert!(!closed);
+        assert_eq!(points[0], from);
+        assert_eq!(*points.last().unwrap(), to);
+        // The curve reaches half the height of its control point
+        let top = points.iter().map(|p| p.y).fold(0., f32::max);
+        assert!((top - 50.).abs() <= path.tolerance());
+
+        let mut precise = Path::new();
+        precise
+            .set_tolerance(0.01)
+            .move_to(from)
+            .cubic_to(control, control, to);
+        assert!(precise.contours().next().unwrap().0.len() > points.len());
+    }
+
+    #[test]
+    fn arcs_start_at_their_start_angle() {
+        let center = Vec2::new(10., 10.);
+        let mut path = Path::new();
+        path.move_to(Vec2::ZERO).arc(center, 5., 0., FRAC_PI_2);
+        let (points, _) = path.contours().next().unwrap();
+
+        // Line from the current point to the start of the arc
+        assert_eq!(points[0], Vec2::ZERO);
+        assert_eq!(points[1], Vec2::new(15., 10.));
+        assert!(points
+            .last()
+            .unwrap()
+            .abs_diff_eq(Vec2::new(10., 15.), 1e-5));
+        for point in &points[1..] {
+            assert!((point.distance(center) - 5.).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn closed_contours_continue_from_their_start() {
+        let mut path = Path::new();
+        path.move_to(Vec2::new(1., 1.))
+            .line_to(Vec2::new(5., 1.))
+            .line_to(Vec2::new(5., 5.))
+            .close()
+            .line_to(Vec2::new(0., 9.))
+            .arc(Vec2::ZERO, 2., 0., PI);
+        let contours: Vec<_> = path.contours().collect();
+
+        assert_eq!(contours.len(), 2);
+        assert!(contours[0].1 && !contours[1].1);
+        assert_eq!(contours[1].0[..2], [Vec2::new(1., 1.), Vec2::new(0., 9.)]);
+    }
+
+    #[test]
+    fn long_strokes_index_past_u16() {
+        // A zigzag with round joins needs more than 65536 vertices
+        let points: Vec<Vec2> = (0..4000)
+            .map(|i| Vec2::new(i as f32, (i % 2) as f32 * 10.))
+            .collect();
+        let mut geometry = stroke(&points, false, LineCap::Round, LineJoin::Round);
+        geometry.append(&stroke(&points, false, LineCap::Round, LineJoin::Round));
+
+        assert!(geometry.vertices.len() > u16::MAX as usize);
+        let max = geometry.indices.iter().copied().max().unwrap();
+        assert_eq!(max as usize, geometry.vertices.len() - 1);
+        assert!(geometry
+            .indices
+            .iter()
+            .all(|index| (*index as usize) < geometry.vertices.len()));
     }
 }
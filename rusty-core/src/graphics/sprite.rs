@@ -1,21 +1,22 @@
+use std::rc::Rc;
+
 use glam::Vec2;
 use wgpu::util::DeviceExt;
 
-use crate::{math::Rect, Context};
+use crate::{math::Rect, Ctx};
 
-use super::{color, shape::ShapeVertex, texture::Texture, Mesh, Transformable};
+use super::{color, shape::ShapeVertex, texture::Texture, Mesh, Transformable, QUAD_INDICES};
 
 pub struct Sprite {
     mesh: Mesh,
     vertices: Vec<ShapeVertex>,
     color: Option<color::Color>,
-    texture: Texture,
+    texture: Rc<Texture>,
     texture_rect: Rect,
 }
 
 impl Sprite {
-    pub fn new(texture: Texture) -> Self {
-        let gl_context = Context::get();
+    pub fn new(ctx: &Ctx, texture: Rc<Texture>) -> Self {
         // Generating the mesh
         let texture_rect = Rect {
             x: 0.,
@@ -24,47 +25,25 @@ impl Sprite {
             height: texture.texture.size().height as f32,
         };
 
-        let vertices = vec![
-            ShapeVertex {
-                position: [0., 0., 0.],
-                color: [1., 1., 1., 1.0],
-                tex_coords: [0., 0.],
-            },
-            ShapeVertex {
-                position: [0., texture_rect.height, 0.],
-                color: [1., 1., 1., 1.0],
-                tex_coords: [0., 0.],
-            },
-            ShapeVertex {
-                position: [texture_rect.width, texture_rect.height, 0.],
-                color: [1., 1., 1., 1.0],
-                tex_coords: [0., 0.],
-            },
-            ShapeVertex {
-                position: [texture_rect.width, 0., 0.],
-                color: [1., 1., 1., 1.0],
-                tex_coords: [0., 0.],
-            },
-        ];
-        let indices: Vec<u16> = vec![0, 1, 3, 1, 2, 3];
-        let vertex_buffer =
-            gl_context
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Vertex buffer"),
-                    contents: bytemuck::cast_slice(&vertices),
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                });
-        let index_buffer =
-            gl_context
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Index buffer"),
-                    contents: bytemuck::cast_slice(&indices),
-                    usage: wgpu::BufferUsages::INDEX,
-                });
-
-        let mesh = Mesh::new(vertex_buffer, index_buffer, indices.len() as u32);
+        let vertices = quad(&texture_rect, [1., 1., 1., 1.]);
+        let indices = QUAD_INDICES;
+        let vertex_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Vertex buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            });
+        let index_buffer = ctx
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Index buffer"),
+                contents: bytemuck::cast_slice(&indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+
+        let mut mesh = Mesh::new(ctx, vertex_buffer, index_buffer, indices.len() as u32);
+        mesh.set_texture(Some(texture.clone()));
 
         Self {
             color: None,
@@ -75,16 +54,85 @@ impl Sprite {
         }
     }
 
-    pub fn set_texture(&mut self, texture: Texture) {
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    pub fn mesh_mut(&mut self) -> &mut Mesh {
+        &mut self.mesh
+    }
+
+    pub fn set_texture(&mut self, texture: Rc<Texture>) {
+        self.mesh.set_texture(Some(texture.clone()));
         self.texture = texture;
     }
+
+    pub fn texture(&self) -> &Rc<Texture> {
+        &self.texture
+    }
+
+    /// Set the part of the texture displayed by the sprite, in pixels.
+    ///
+    /// The sprite is resized to the size of the rectangle.
+    pub fn set_texture_rect(&mut self, rect: Rect) {
+        self.texture_rect = rect;
+        self.update();
+    }
+
+    pub(crate) fn vertices(&self) -> &[ShapeVertex] {
+        &self.vertices
+    }
+
+    pub fn texture_rect(&self) -> &Rect {
+        &self.texture_rect
+    }
+
+    pub fn set_color(&mut self, color: color::Color) {
+        self.color = Some(color);
+        self.update();
+    }
+
+    pub fn color(&self) -> color::Color {
+        self.color.unwrap_or(color::WHITE)
+    }
+
+    fn update(&mut self) {
+        self.vertices = quad(&self.texture_rect, self.color().to_linear());
+        self.mesh.write_vertices(&self.vertices);
+    }
+}
+
+/// Vertices of a quad displaying `rect` of the texture, with its top-left
+/// corner at the origin.
+fn quad(rect: &Rect, color: [f32; 4]) -> Vec<ShapeVertex> {
+    vec![
+        ShapeVertex {
+            position: [0., 0., 0.],
+            color,
+            tex_coords: [rect.x, rect.y],
+        },
+        ShapeVertex {
+            position: [0., rect.height, 0.],
+            color,
+            tex_coords: [rect.x, rect.y + rect.height],
+        },
+        ShapeVertex {
+            position: [rect.width, rect.height, 0.],
+            color,
+            tex_coords: [rect.x + rect.width, rect.y + rect.height],
+        },
+        ShapeVertex {
+            position: [rect.width, 0., 0.],
+            color,
+            tex_coords: [rect.x + rect.width, rect.y],
+        },
+    ]
 }
 
 impl Transformable for Sprite {
     fn set_position(&mut self, position: glam::Vec2) {
         self.mesh.transform.position = position;
-
-        // update model matrix
+        self.mesh.update_transform();
     }
 
     fn position(&self) -> &Vec2 {
@@ -93,14 +141,12 @@ impl Transformable for Sprite {
 
     fn r#move(&mut self, offset: Vec2) {
         self.mesh.transform.position += offset;
-
-        // self.update();
+        self.mesh.update_transform();
     }
 
     fn set_rotation(&mut self, rotation: f32) {
         self.mesh.transform.rotation = rotation;
-
-        // update model matrix
+        self.mesh.update_transform();
     }
 
     fn rotation(&self) -> f32 {
@@ -113,21 +159,29 @@ impl Transformable for Sprite {
         } else {
             self.mesh.transform.rotation += angle;
         };
-
-        // update model matrix
+        self.mesh.update_transform();
     }
 
     fn set_scale(&mut self, scale: f32) {
-        self.mesh.transform.scale = scale;
+        self.set_scale_xy(Vec2::splat(scale));
     }
 
     fn scale(&self) -> f32 {
+        self.mesh.transform.scale.x
+    }
+
+    fn set_scale_xy(&mut self, scale: Vec2) {
+        self.mesh.transform.scale = scale;
+        self.mesh.update_transform();
+    }
+
+    fn scale_xy(&self) -> Vec2 {
         self.mesh.transform.scale
     }
 
     fn set_origin(&mut self, origin: Vec2) {
         self.mesh.transform.origin = origin;
-        // self.update();
+        self.mesh.update_transform();
     }
 
     fn origin(&self) -> &Vec2 {
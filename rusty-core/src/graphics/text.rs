@@ -0,0 +1,975 @@
+use std::{cell::RefCell, collections::HashMap, path::Path, rc::Rc};
+
+use ab_glyph::{Font as _, FontArc, GlyphId, PxScale, ScaleFont};
+use glam::Vec2;
+use image::RgbaImage;
+
+use crate::{math::Rect, Context, Ctx, Error, Result};
+
+use super::{
+    atlas::Skyline, color, shape::empty_mesh, shape::ShapeVertex, texture::Texture, Mesh,
+    Transformable, QUAD_INDICES,
+};
+
+/// Size of the glyph cache texture when the first glyph is rasterized.
+const INITIAL_CACHE_SIZE: u32 = 256;
+
+/// Transparent pixels kept around each glyph of the cache so that linear
+/// filtering does not bleed neighbouring glyphs in.
+const GLYPH_PADDING: u32 = 1;
+
+/// Side of the opaque block of the cache used to draw lines, such as
+/// underlines, with the same texture as the glyphs.
+const WHITE_BLOCK_SIZE: u32 = 3;
+
+/// Horizontal shear applied to the glyphs of italic texts.
+const ITALIC_SHEAR: f32 = 0.2;
+
+/// TrueType or OpenType font, with a cache of the glyphs rasterized so far.
+///
+/// Glyphs are rasterized on demand, for each character size they are drawn
+/// at, into a texture shared by every [`Text`] using the font. Share the font
+/// between texts with an [`Rc`] rather than loading it several times.
+///
+/// ```ignore
+/// let font = Rc::new(Font::from_path("assets/fonts/DejaVuSans.ttf")?);
+/// let mut text = Text::new(ctx, font, "Hello, world!", 24.);
+/// text.set_position(Vec2::new(10., 10.));
+/// render_pass.draw_mesh(text.mesh());
+/// ```
+pub struct Font {
+    font: FontArc,
+    cache: RefCell<GlyphCache>,
+}
+
+impl Font {
+    /// Font parsed from the content of a TTF or OTF file.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        let font =
+            FontArc::try_from_vec(bytes).map_err(|err| Error::InvalidFont(err.to_string()))?;
+
+        Ok(Self {
+            font,
+            cache: RefCell::new(GlyphCache::default()),
+        })
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|err| Error::io(path, err))?;
+
+        Self::from_bytes(bytes)
+    }
+
+    /// Distance between the baselines of two lines of text, in pixels, for
+    /// a character size of `size`.
+    pub fn line_height(&self, size: f32) -> f32 {
+        let font = self.font.as_scaled(px_scale(&self.font, size));
+
+        font.height() + font.line_gap()
+    }
+
+    /// Texture holding the glyphs rasterized so far, if any was.
+    pub fn texture(&self) -> Option<Rc<Texture>> {
+        self.cache.borrow().texture.clone()
+    }
+}
+
+/// Scale of `font` for which an em is `size` pixels high.
+fn px_scale(font: &FontArc, size: f32) -> PxScale {
+    let units_per_em = font.units_per_em().unwrap_or(1000.);
+
+    PxScale::from(size * font.height_unscaled() / units_per_em)
+}
+
+/// Area of the cache texture holding a glyph.
+#[derive(Copy, Clone, Debug)]
+struct CachedGlyph {
+    /// Area covered by the glyph, in pixels, without padding.
+    rect: Rect,
+    /// Position of the top-left corner of the glyph relative to its origin
+    /// on the baseline.
+    offset: Vec2,
+}
+
+/// Glyphs packed into a texture as they are first drawn.
+///
+/// When the texture is full, a new texture twice as large, or as large when
+/// it reached the limit of the device, replaces it and the glyphs are
+/// rasterized again. Texts built before keep the previous texture alive, so
+/// they are still drawn correctly until they are updated.
+#[derive(Default)]
+struct GlyphCache {
+    texture: Option<Rc<Texture>>,
+    skyline: Option<Skyline>,
+    /// Glyphs by id and character size, `None` for the glyphs without
+    /// outline such as spaces.
+    glyphs: HashMap<(GlyphId, u32), Option<CachedGlyph>>,
+    /// Area of the opaque block.
+    white: Rect,
+    /// Incremented each time the texture is replaced, invalidating the areas
+    /// returned before.
+    generation: u32,
+}
+
+impl GlyphCache {
+    /// Area of the glyph `id` at `size` in the texture, rasterized if it was
+    /// not yet.
+    ///
+    /// When the texture is full, it is replaced if `allow_reset` is set,
+    /// otherwise the glyph is not drawn.
+    fn glyph(
+        &mut self,
+        ctx: &Context,
+        font: &FontArc,
+        id: GlyphId,
+        size: f32,
+        allow_reset: bool,
+    ) -> Result<Option<CachedGlyph>> {
+        if let Some(glyph) = self.glyphs.get(&(id, size.to_bits())) {
+            return Ok(*glyph);
+        }
+
+        let scale = px_scale(font, size);
+        let Some(outline) = font.outline_glyph(id.with_scale(scale)) else {
+            self.glyphs.insert((id, size.to_bits()), None);
+            return Ok(None);
+        };
+        let bounds = outline.px_bounds();
+        let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+        let mut image = RgbaImage::new(width, height);
+        outline.draw(|x, y, coverage| {
+            if x < width && y < height {
+                let alpha = (coverage.clamp(0., 1.) * 255.).round() as u8;
+                image.put_pixel(x, y, image::Rgba([255, 255, 255, alpha]));
+            }
+        });
+
+        let padded = (width + 2 * GLYPH_PADDING, height + 2 * GLYPH_PADDING);
+        let position = match self.skyline.as_mut().and_then(|s| s.insert(padded)) {
+            Some(position) => position,
+            None if allow_reset => {
+                let size = self.texture.as_ref().map_or(0, |t| t.texture.width());
+                let max = ctx.device.limits().max_texture_dimension_2d;
+                self.reset(ctx, (size * 2).min(max))?;
+                match self.skyline.as_mut().and_then(|s| s.insert(padded)) {
+                    Some(position) => position,
+                    // Larger than the whole texture
+                    None => return Ok(None),
+                }
+            }
+            None => return Ok(None),
+        };
+
+        let origin = (position.0 + GLYPH_PADDING, position.1 + GLYPH_PADDING);
+        self.texture.as_ref().unwrap().update(ctx, &image, origin)?;
+        let glyph = CachedGlyph {
+            rect: Rect {
+                x: origin.0 as f32,
+                y: origin.1 as f32,
+                width: width as f32,
+                height: height as f32,
+            },
+            offset: Vec2::new(bounds.min.x, bounds.min.y),
+        };
+        self.glyphs.insert((id, size.to_bits()), Some(glyph));
+
+        Ok(Some(glyph))
+    }
+
+    /// Replace the texture by an empty one of `size` x `size` pixels.
+    fn reset(&mut self, ctx: &Context, size: u32) -> Result<()> {
+        let texture = Texture::create(ctx, size, size, wgpu::TextureFormat::Rgba8UnormSrgb)?;
+        let mut skyline = Skyline::new((size, size));
+        let block = WHITE_BLOCK_SIZE + 2 * GLYPH_PADDING;
+        let (x, y) = skyline.insert((block, block)).unwrap();
+        let white = RgbaImage::from_pixel(
+            WHITE_BLOCK_SIZE,
+            WHITE_BLOCK_SIZE,
+            image::Rgba([255, 255, 255, 255]),
+        );
+        texture.update(ctx, &white, (x + GLYPH_PADDING, y + GLYPH_PADDING))?;
+
+        self.white = Rect {
+            x: (x + GLYPH_PADDING) as f32,
+            y: (y + GLYPH_PADDING) as f32,
+            width: WHITE_BLOCK_SIZE as f32,
+            height: WHITE_BLOCK_SIZE as f32,
+        };
+        self.texture = Some(Rc::new(texture));
+        self.skyline = Some(skyline);
+        self.glyphs.clear();
+        self.generation += 1;
+
+        Ok(())
+    }
+}
+
+/// Horizontal alignment of the lines of a [`Text`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TextAlignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Parameters of the layout of a text.
+#[derive(Copy, Clone, Debug)]
+struct LayoutOptions {
+    size: f32,
+    line_spacing: f32,
+    letter_spacing: f32,
+    alignment: TextAlignment,
+    max_width: Option<f32>,
+}
+
+/// Glyph placed by [`layout`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct PositionedGlyph {
+    id: GlyphId,
+    /// Origin of the glyph on the baseline, in pixels.
+    position: Vec2,
+}
+
+/// Line placed by [`layout`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Line {
+    x: f32,
+    baseline: f32,
+    width: f32,
+}
+
+#[derive(Debug, Default)]
+struct Layout {
+    glyphs: Vec<PositionedGlyph>,
+    lines: Vec<Line>,
+    bounds: Rect,
+}
+
+/// Place the glyphs of `string` on lines, breaking them at `\n` and, when
+/// they are wider than the maximum width, after the last space or else
+/// before the character overflowing.
+fn layout(font: &FontArc, string: &str, options: &LayoutOptions) -> Layout {
+    let font = font.as_scaled(px_scale(font, options.size));
+    let line_height = ((font.height() + font.line_gap()) * options.line_spacing).round();
+    let ascent = font.ascent().round();
+
+    // Position of each character of a line from its start, kerning included
+    let place = |chars: &[char]| -> Vec<(GlyphId, f32)> {
+        let mut x = 0.;
+        let mut previous = None;
+        chars
+            .iter()
+            .map(|c| {
+                let id = font.glyph_id(*c);
+                if let Some(previous) = previous {
+                    x += font.kern(previous, id) + options.letter_spacing;
+                }
+                previous = Some(id);
+                let position = x;
+                x += font.h_advance(id);
+                (id, position)
+            })
+            .collect()
+    };
+
+    let mut placed_lines: Vec<(Vec<(GlyphId, f32)>, f32)> = Vec::new();
+    for paragraph in string.split('\n') {
+        let chars: Vec<char> = paragraph.trim_end_matches('\r').chars().collect();
+        let mut start = 0;
+        loop {
+            let mut end = chars.len();
+            if let Some(max_width) = options.max_width {
+                let mut last_break = None;
+                for (i, (id, x)) in place(&chars[start..]).into_iter().enumerate() {
+                    let c = chars[start + i];
+                    if !c.is_whitespace() && i > 0 && x + font.h_advance(id) > max_width {
+                        end = last_break.unwrap_or(start + i);
+                        break;
+                    }
+                    if c.is_whitespace() {
+                        last_break = Some(start + i + 1);
+                    }
+                }
+            }
+
+            let line = &chars[start..end];
+            let glyphs = place(line);
+            // Trailing spaces do not count in the width of the line
+            let width = line
+                .iter()
+                .zip(&glyphs)
+                .rev()
+                .find(|(c, _)| !c.is_whitespace())
+                .map_or(0., |(_, (id, x))| x + font.h_advance(*id));
+            placed_lines.push((glyphs, width));
+
+            start = end;
+            if start >= chars.len() {
+                break;
+            }
+        }
+    }
+
+    let widest = placed_lines.iter().map(|(_, w)| *w).fold(0., f32::max);
+    let container = options.max_width.unwrap_or(widest);
+    let mut result = Layout::default();
+    for (i, (glyphs, width)) in placed_lines.into_iter().enumerate() {
+        let x = match options.alignment {
+            TextAlignment::Left => 0.,
+            TextAlignment::Center => ((container - width) / 2.).round(),
+            TextAlignment::Right => (container - width).round(),
+        };
+        let baseline = ascent + i as f32 * line_height;
+        result
+            .glyphs
+            .extend(glyphs.into_iter().map(|(id, glyph_x)| PositionedGlyph {
+                id,
+                position: Vec2::new((x + glyph_x).round(), baseline),
+            }));
+        result.lines.push(Line { x, baseline, width });
+    }
+
+    let left = result.lines.iter().map(|l| l.x).fold(f32::MAX, f32::min);
+    let right = result
+        .lines
+        .iter()
+        .map(|l| l.x + l.width)
+        .fold(f32::MIN, f32::max);
+    result.bounds = Rect {
+        x: left,
+        y: 0.,
+        width: right - left,
+        height: (result.lines.len() - 1) as f32 * line_height
+            + (font.ascent() - font.descent()).round(),
+    };
+
+    result
+}
+
+/// Vertices of the quads of a text, with the texture they sample.
+type Geometry = (Vec<ShapeVertex>, Option<Rc<Texture>>);
+
+/// Appearance of a [`Text`], set at once by [`Text::set_style`].
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct TextStyle {
+    pub character_size: f32,
+    pub color: color::Color,
+    pub letter_spacing: f32,
+    pub bold: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub strike_through: bool,
+}
+
+/// String drawn with a [`Font`].
+///
+/// Lines are broken at `\n` and, when a maximum width is set, wrapped
+/// between words. Italic and bold are synthesized from the font by shearing
+/// the glyphs and drawing them twice slightly apart; use the italic or bold
+/// file of the font family instead for a better rendering.
+///
+/// When the glyphs cannot be rasterized, the text is not drawn and the
+/// error is kept in [`Text::error`].
+pub struct Text {
+    ctx: Ctx,
+    mesh: Mesh,
+    font: Rc<Font>,
+    string: String,
+    character_size: f32,
+    color: color::Color,
+    line_spacing: f32,
+    letter_spacing: f32,
+    alignment: TextAlignment,
+    max_width: Option<f32>,
+    bold: bool,
+    italic: bool,
+    underlined: bool,
+    strike_through: bool,
+    bounds: Rect,
+    error: Option<Error>,
+}
+
+impl Text {
+    /// `string` drawn with `font`, an em being `character_size` pixels high.
+    pub fn new(ctx: &Ctx, font: Rc<Font>, string: impl Into<String>, character_size: f32) -> Self {
+        let style = TextStyle {
+            character_size,
+            color: color::WHITE,
+            letter_spacing: 0.,
+            bold: false,
+            italic: false,
+            underlined: false,
+            strike_through: false,
+        };
+
+        Self::with_style(ctx, font, string, style)
+    }
+
+    /// `string` drawn with `font` and `style`, laid out once.
+    pub(crate) fn with_style(
+        ctx: &Ctx,
+        font: Rc<Font>,
+        string: impl Into<String>,
+        style: TextStyle,
+    ) -> Self {
+        let mut text = Self {
+            ctx: ctx.clone(),
+            mesh: empty_mesh(ctx),
+            font,
+            string: string.into(),
+            character_size: style.character_size,
+            color: style.color,
+            line_spacing: 1.,
+            letter_spacing: style.letter_spacing,
+            alignment: TextAlignment::default(),
+            max_width: None,
+            bold: style.bold,
+            italic: style.italic,
+            underlined: style.underlined,
+            strike_through: style.strike_through,
+            bounds: Rect::default(),
+            error: None,
+        };
+        text.update();
+
+        text
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    pub fn mesh_mut(&mut self) -> &mut Mesh {
+        &mut self.mesh
+    }
+
+    pub fn set_string(&mut self, string: impl Into<String>) {
+        self.string = string.into();
+        self.update();
+    }
+
+    pub fn string(&self) -> &str {
+        &self.string
+    }
+
+    pub fn set_font(&mut self, font: Rc<Font>) {
+        self.font = font;
+        self.update();
+    }
+
+    pub fn font(&self) -> &Rc<Font> {
+        &self.font
+    }
+
+    /// Set the height of an em, in pixels.
+    pub fn set_character_size(&mut self, character_size: f32) {
+        self.character_size = character_size;
+        self.update();
+    }
+
+    pub fn character_size(&self) -> f32 {
+        self.character_size
+    }
+
+    pub fn set_color(&mut self, color: color::Color) {
+        self.color = color;
+        self.update();
+    }
+
+    pub fn color(&self) -> color::Color {
+        self.color
+    }
+
+    /// Set the factor applied to the line height of the font, 1 by default.
+    pub fn set_line_spacing(&mut self, line_spacing: f32) {
+        self.line_spacing = line_spacing;
+        self.update();
+    }
+
+    pub fn line_spacing(&self) -> f32 {
+        self.line_spacing
+    }
+
+    /// Set the space added between characters, in pixels, 0 by default.
+    pub fn set_letter_spacing(&mut self, letter_spacing: f32) {
+        self.letter_spacing = letter_spacing;
+        self.update();
+    }
+
+    pub fn letter_spacing(&self) -> f32 {
+        self.letter_spacing
+    }
+
+    /// Set how lines are aligned within the maximum width, or the widest
+    /// line without maximum width.
+    pub fn set_alignment(&mut self, alignment: TextAlignment) {
+        self.alignment = alignment;
+        self.update();
+    }
+
+    pub fn alignment(&self) -> TextAlignment {
+        self.alignment
+    }
+
+    /// Wrap the lines wider than `max_width` pixels, or never with `None`.
+    pub fn set_max_width(&mut self, max_width: Option<f32>) {
+        self.max_width = max_width;
+        self.update();
+    }
+
+    pub fn max_width(&self) -> Option<f32> {
+        self.max_width
+    }
+
+    pub fn set_bold(&mut self, bold: bool) {
+        self.bold = bold;
+        self.update();
+    }
+
+    pub fn is_bold(&self) -> bool {
+        self.bold
+    }
+
+    pub fn set_italic(&mut self, italic: bool) {
+        self.italic = italic;
+        self.update();
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.italic
+    }
+
+    pub fn set_underlined(&mut self, underlined: bool) {
+        self.underlined = underlined;
+        self.update();
+    }
+
+    pub fn is_underlined(&self) -> bool {
+        self.underlined
+    }
+
+    pub fn set_strike_through(&mut self, strike_through: bool) {
+        self.strike_through = strike_through;
+        self.update();
+    }
+
+    pub fn is_strike_through(&self) -> bool {
+        self.strike_through
+    }
+
+    /// Set the character size, color, letter spacing and appearance with a
+    /// single layout of the text.
+    #[cfg(feature = "ui")]
+    pub(crate) fn set_style(&mut self, style: TextStyle) {
+        self.character_size = style.character_size;
+        self.color = style.color;
+        self.letter_spacing = style.letter_spacing;
+        self.bold = style.bold;
+        self.italic = style.italic;
+        self.underlined = style.underlined;
+        self.strike_through = style.strike_through;
+        self.update();
+    }
+
+    /// Error raised rasterizing the glyphs at the last change of the text,
+    /// e.g. when the glyph cache texture could not be created, in which case
+    /// nothing is drawn.
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    /// Area covered by the lines of the text, in local coordinates.
+    ///
+    /// Lines are as high as the ascent and descent of the font and as wide
+    /// as the advances of their characters, so the rectangle depends on the
+    /// string rather than the exact shape of its glyphs.
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn options(&self) -> LayoutOptions {
+        LayoutOptions {
+            size: self.character_size,
+            line_spacing: self.line_spacing,
+            letter_spacing: self.letter_spacing,
+            alignment: self.alignment,
+            max_width: self.max_width,
+        }
+    }
+
+    fn update(&mut self) {
+        let layout = layout(&self.font.font, &self.string, &self.options());
+        self.bounds = layout.bounds;
+
+        let color = self.color.to_linear();
+        let mut cache = self.font.cache.borrow_mut();
+        let geometry = match self.geometry(&mut cache, &layout, color, true) {
+            Ok(Some(geometry)) => Ok(geometry),
+            // The cache was replaced while rasterizing the glyphs, the areas
+            // of those rasterized before are not valid anymore
+            Ok(None) => self
+                .geometry(&mut cache, &layout, color, false)
+                .map(Option::unwrap_or_default),
+            Err(error) => Err(error),
+        };
+        drop(cache);
+        let (vertices, texture) = match geometry {
+            Ok(geometry) => {
+                self.error = None;
+                geometry
+            }
+            Err(error) => {
+                self.error = Some(error);
+                Default::default()
+            }
+        };
+
+        let indices: Vec<u32> = (0..vertices.len() as u32 / 4)
+            .flat_map(|quad| QUAD_INDICES.map(|i| quad * 4 + i))
+            .collect();
+        self.mesh
+            .write_geometry(&vertices, &indices, indices.len() as u32);
+        self.mesh.set_texture(texture);
+    }
+
+    /// Quads of the glyphs and lines of the text, with the texture they
+    /// sample, or `None` when the glyph cache was replaced meanwhile.
+    fn geometry(
+        &self,
+        cache: &mut GlyphCache,
+        layout: &Layout,
+        color: [f32; 4],
+        allow_reset: bool,
+    ) -> Result<Option<Geometry>> {
+        if cache.texture.is_none() {
+            cache.reset(&self.ctx, INITIAL_CACHE_SIZE)?;
+        }
+        let generation = cache.generation;
+        let bold_offset = if self.bold {
+            (self.character_size / 24.).round().max(1.)
+        } else {
+            0.
+        };
+        let shear = if self.italic { ITALIC_SHEAR } else { 0. };
+
+        let mut vertices = Vec::new();
+        for glyph in &layout.glyphs {
+            let cached = cache.glyph(
+                &self.ctx,
+                &self.font.font,
+                glyph.id,
+                self.character_size,
+                allow_reset,
+            )?;
+            if cache.generation != generation {
+                return Ok(None);
+            }
+            let Some(cached) = cached else {
+                continue;
+            };
+            let position = glyph.position + cached.offset;
+            let baseline = glyph.position.y;
+            let copies: &[f32] = if self.bold { &[0., bold_offset] } else { &[0.] };
+            for offset in copies {
+                let rect = Rect {
+                    x: position.x + offset,
+                    y: position.y,
+                    ..cached.rect
+                };
+                vertices.extend(quad(&rect, &cached.rect, color, shear, baseline));
+            }
+        }
+
+        // Underline and strike through, sampling the center of the opaque
+        // block so they are not blended with its transparent border
+        let white = cache.white;
+        let center = Rect {
+            x: white.x + white.width / 2.,
+            y: white.y + white.height / 2.,
+            width: 0.,
+            height: 0.,
+        };
+        let thickness = (self.character_size / 14.).round().max(1.);
+        for line in layout.lines.iter().filter(|line| line.width > 0.) {
+            let mut offsets = Vec::new();
+            if self.underlined {
+                offsets.push((self.character_size / 10.).round());
+            }
+            if self.strike_through {
+                offsets.push(-(self.character_size * 0.3).round());
+            }
+            for offset in offsets {
+                let rect = Rect {
+                    x: line.x,
+                    y: line.baseline + offset - (thickness / 2.).floor(),
+                    width: line.width + bold_offset,
+                    height: thickness,
+                };
+                vertices.extend(quad(&rect, &center, color, 0., line.baseline));
+            }
+        }
+
+        Ok(Some((vertices, cache.texture.clone())))
+    }
+}
+
+/// Vertices of the quad covering `rect` and sampling `tex_rect`, sheared
+/// horizontally around `baseline`.
+fn quad(
+    rect: &Rect,
+    tex_rect: &Rect,
+    color: [f32; 4],
+    shear: f32,
+    baseline: f32,
+) -> [ShapeVertex; 4] {
+    let vertex = |x: f32, y: f32, u: f32, v: f32| ShapeVertex {
+        position: [x + (baseline - y) * shear, y, 0.],
+        color,
+        tex_coords: [u, v],
+    };
+    let (left, top) = (rect.x, rect.y);
+    let (right, bottom) = (rect.x + rect.width, rect.y + rect.height);
+    let (u0, v0) = (tex_rect.x, tex_rect.y);
+    let (u1, v1) = (tex_rect.x + tex_rect.width, tex_rect.y + tex_rect.height);
+
+    [
+        vertex(left, top, u0, v0),
+        vertex(left, bottom, u0, v1),
+        vertex(right, bottom, u1, v1),
+        vertex(right, top, u1, v0),
+    ]
+}
+
+impl Transformable for Text {
+    fn set_position(&mut self, position: Vec2) {
+        self.mesh.transform.position = position;
+        self.mesh.update_transform();
+    }
+
+    fn position(&self) -> &Vec2 {
+        &self.mesh.transform.position
+    }
+
+    fn r#move(&mut self, offset: Vec2) {
+        self.mesh.transform.position += offset;
+        self.mesh.update_transform();
+    }
+
+    fn set_rotation(&mut self, rotation: f32) {
+        self.mesh.transform.rotation = rotation;
+        self.mesh.update_transform();
+    }
+
+    fn rotation(&self) -> f32 {
+        self.mesh.transform.rotation
+    }
+
+    fn rotate(&mut self, angle: f32) {
+        self.mesh.transform.rotation += angle;
+        self.mesh.update_transform();
+    }
+
+    fn set_scale(&mut self, scale: f32) {
+        self.set_scale_xy(Vec2::splat(scale));
+    }
+
+    fn scale(&self) -> f32 {
+        self.mesh.transform.scale.x
+    }
+
+    fn set_scale_xy(&mut self, scale: Vec2) {
+        self.mesh.transform.scale = scale;
+        self.mesh.update_transform();
+    }
+
+    fn scale_xy(&self) -> Vec2 {
+        self.mesh.transform.scale
+    }
+
+    fn set_origin(&mut self, origin: Vec2) {
+        self.mesh.transform.origin = origin;
+        self.mesh.update_transform();
+    }
+
+    fn origin(&self) -> &Vec2 {
+        &self.mesh.transform.origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEJAVU_SANS: &[u8] = include_bytes!("../../../assets/fonts/DejaVuSans.ttf");
+
+    fn font() -> FontArc {
+        FontArc::try_from_slice(DEJAVU_SANS).unwrap()
+    }
+
+    fn options() -> LayoutOptions {
+        LayoutOptions {
+            size: 20.,
+            line_spacing: 1.,
+            letter_spacing: 0.,
+            alignment: TextAlignment::Left,
+            max_width: None,
+        }
+    }
+
+    fn line_starts(layout: &Layout) -> Vec<f32> {
+        layout.lines.iter().map(|line| line.baseline).collect()
+    }
+
+    #[test]
+    fn invalid_fonts_are_rejected() {
+        assert!(matches!(
+            Font::from_bytes(b"not a font".to_vec()),
+            Err(Error::InvalidFont(_))
+        ));
+    }
+
+    #[test]
+    fn kerning_pulls_pairs_together() {
+        let font = font();
+        let kerned = layout(&font, "AV", &options());
+        let scaled = font.as_scaled(px_scale(&font, 20.));
+        let a = scaled.glyph_id('A');
+        assert!(scaled.kern(a, scaled.glyph_id('V')) < 0.);
+        assert!(kerned.glyphs[1].position.x < scaled.h_advance(a).round());
+    }
+
+    #[test]
+    fn letter_spacing_separates_characters() {
+        let font = font();
+        let tight = layout(&font, "ab", &options());
+        let spaced = layout(
+            &font,
+            "ab",
+            &LayoutOptions {
+                letter_spacing: 5.,
+                ..options()
+            },
+        );
+        assert_eq!(spaced.glyphs[1].position.x - tight.glyphs[1].position.x, 5.);
+        assert!((spaced.bounds.width - tight.bounds.width - 5.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn newlines_and_line_spacing() {
+        let font = font();
+        let single = layout(&font, "a\nb\n\nc", &options());
+        assert_eq!(single.lines.len(), 4);
+        let line_height = Font::from_bytes(DEJAVU_SANS.to_vec())
+            .unwrap()
+            .line_height(20.);
+        assert_eq!(
+            single.lines[1].baseline - single.lines[0].baseline,
+            line_height.round()
+        );
+
+        let double = layout(
+            &font,
+            "a\nb",
+            &LayoutOptions {
+                line_spacing: 2.,
+                ..options()
+            },
+        );
+        assert_eq!(
+            double.lines[1].baseline - double.lines[0].baseline,
+            (line_height * 2.).round()
+        );
+    }
+
+    #[test]
+    fn lines_wrap_between_words() {
+        let font = font();
+        let unwrapped = layout(&font, "one two three", &options());
+        let two = layout(&font, "one two", &options()).bounds.width;
+        let wrapped = layout(
+            &font,
+            "one two three",
+            &LayoutOptions {
+                max_width: Some(two + 1.),
+                ..options()
+            },
+        );
+        assert_eq!(unwrapped.lines.len(), 1);
+        assert_eq!(wrapped.lines.len(), 2);
+        assert_eq!(wrapped.lines[0].width, two);
+        // The second line starts with "three"
+        let t = font.glyph_id('t');
+        let second: Vec<_> = wrapped
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.position.y == wrapped.lines[1].baseline)
+            .collect();
+        assert_eq!(second[0].id, t);
+        assert_eq!(second[0].position.x, 0.);
+        assert!(wrapped.bounds.width <= two + 1.);
+    }
+
+    #[test]
+    fn words_wider_than_the_line_are_broken() {
+        let font = font();
+        let wrapped = layout(
+            &font,
+            "abcdefgh",
+            &LayoutOptions {
+                max_width: Some(30.),
+                ..options()
+            },
+        );
+        assert!(wrapped.lines.len() > 2);
+        assert!(wrapped.lines.iter().all(|line| line.width <= 30.));
+        assert_eq!(wrapped.glyphs.len(), 8);
+    }
+
+    #[test]
+    fn alignment_within_max_width() {
+        let font = font();
+        let aligned = |alignment| {
+            layout(
+                &font,
+                "ab\nabcd",
+                &LayoutOptions {
+                    alignment,
+                    ..options()
+                },
+            )
+        };
+        let left = aligned(TextAlignment::Left);
+        let center = aligned(TextAlignment::Center);
+        let right = aligned(TextAlignment::Right);
+        let difference = left.lines[1].width - left.lines[0].width;
+
+        assert_eq!(left.lines[0].x, 0.);
+        assert_eq!(center.lines[0].x, (difference / 2.).round());
+        assert_eq!(right.lines[0].x, difference.round());
+        // The widest line fills the bounds whatever the alignment
+        assert_eq!(right.lines[1].x, 0.);
+        assert_eq!(line_starts(&left), line_starts(&right));
+        assert!((left.bounds.width - right.bounds.width).abs() < 1.);
+    }
+
+    #[test]
+    fn bounds_measure_lines() {
+        let font = font();
+        let scaled = font.as_scaled(px_scale(&font, 20.));
+        let empty = layout(&font, "", &options());
+        assert_eq!(empty.bounds.width, 0.);
+        assert_eq!(
+            empty.bounds.height,
+            (scaled.ascent() - scaled.descent()).round()
+        );
+
+        let two_lines = layout(&font, "ab\nc", &options());
+        let line_height = two_lines.lines[1].baseline - two_lines.lines[0].baseline;
+        assert_eq!(two_lines.bounds.height, empty.bounds.height + line_height);
+        assert_eq!(two_lines.bounds.width, two_lines.lines[0].width);
+        let b_end = two_lines.glyphs[1].position.x + scaled.h_advance(scaled.glyph_id('b'));
+        assert!((two_lines.lines[0].width - b_end).abs() <= 0.5);
+    }
+}
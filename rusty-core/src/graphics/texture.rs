@@ -1,186 +1,949 @@
-use anyhow::*;
+use std::sync::{Arc, Mutex};
+
 use glam::Vec2;
-use image::GenericImageView;
 use wgpu::util::DeviceExt;
 
-use crate::Context;
+use super::color::{linear_to_srgb, srgb_to_linear};
+#[cfg(any(feature = "ktx2", feature = "dds"))]
+use super::compressed::{self, CompressedImage};
+use crate::{Context, Ctx, Error, Result};
+
+/// First bytes of KTX2 files.
+pub(super) const KTX2_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+/// First bytes of DDS files.
+pub(super) const DDS_MAGIC: [u8; 4] = *b"DDS ";
+
+/// How a texture is stored and sampled.
+///
+/// The default matches what textures used before options existed: linear
+/// magnification, nearest minification, clamped, without mipmaps, in sRGB.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextureOptions {
+    /// Filter used when the texture is drawn bigger than its size.
+    pub mag_filter: wgpu::FilterMode,
+    /// Filter used when the texture is drawn smaller than its size.
+    pub min_filter: wgpu::FilterMode,
+    /// Filter used between mipmap levels.
+    pub mipmap_filter: wgpu::FilterMode,
+    /// What is sampled outside of the texture horizontally.
+    pub address_mode_u: wgpu::AddressMode,
+    /// What is sampled outside of the texture vertically.
+    pub address_mode_v: wgpu::AddressMode,
+    /// Maximum anisotropy, from 1 (disabled) to 16. Only used when every
+    /// filter is linear.
+    pub anisotropy: u16,
+    /// Generate mipmaps when uploading the image, so the texture does not
+    /// alias when drawn smaller.
+    pub mipmaps: bool,
+    /// Whether the image holds sRGB colors, converted to linear when
+    /// sampled, or raw data such as normal maps.
+    pub srgb: bool,
+}
+
+impl Default for TextureOptions {
+    fn default() -> Self {
+        Self {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            anisotropy: 1,
+            mipmaps: false,
+            srgb: true,
+        }
+    }
+}
+
+impl TextureOptions {
+    /// Sharp pixels at any scale.
+    pub fn pixel_art() -> Self {
+        Self {
+            mag_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        }
+    }
+
+    /// Smooth at any scale, with trilinear filtering of mipmaps.
+    pub fn smooth() -> Self {
+        Self {
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            mipmaps: true,
+            ..Default::default()
+        }
+    }
+
+    /// Repeat the texture in both directions, e.g. for tiled backgrounds.
+    pub fn repeated(self) -> Self {
+        Self {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            ..self
+        }
+    }
+
+    fn format(&self) -> wgpu::TextureFormat {
+        if self.srgb {
+            wgpu::TextureFormat::Rgba8UnormSrgb
+        } else {
+            wgpu::TextureFormat::Rgba8Unorm
+        }
+    }
+
+    fn sampler_descriptor(&self) -> wgpu::SamplerDescriptor<'static> {
+        let linear = [self.mag_filter, self.min_filter, self.mipmap_filter]
+            .iter()
+            .all(|filter| *filter == wgpu::FilterMode::Linear);
 
+        wgpu::SamplerDescriptor {
+            address_mode_u: self.address_mode_u,
+            address_mode_v: self.address_mode_v,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_filter,
+            anisotropy_clamp: if linear {
+                self.anisotropy.clamp(1, 16)
+            } else {
+                1
+            },
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct Texture {
     pub texture: wgpu::Texture,
     pub view: wgpu::TextureView,
-    pub sampler: wgpu::Sampler,
-    pub bind_group: wgpu::BindGroup,
+    size_buffer: wgpu::Buffer,
+    /// Replaced by the setters, which only need a shared reference since
+    /// textures are shared between sprites and asset managers.
+    sampling: Mutex<Sampling>,
+}
+
+/// Sampler of a texture, the bind group using it and the options they were
+/// created from.
+#[derive(Debug)]
+struct Sampling {
+    options: TextureOptions,
+    sampler: Arc<wgpu::Sampler>,
+    bind_group: Arc<wgpu::BindGroup>,
 }
 
 impl Texture {
-    pub fn from_bytes(bytes: &[u8], label: &str) -> Result<Self> {
-        let img = image::load_from_memory(bytes)?;
-        Self::from_image(&img, Some(label))
+    /// Decode an image, or upload a KTX2 or DDS texture as is, see
+    /// [`Texture::from_path`].
+    ///
+    /// Formats are recognized from their first bytes, so TGA images, which
+    /// have none, can only be loaded from a path.
+    pub fn from_bytes(
+        ctx: &Context,
+        bytes: &[u8],
+        label: &str,
+        options: TextureOptions,
+    ) -> Result<Self> {
+        Self::from_encoded(ctx, bytes, None, Some(label), options)
+    }
+
+    /// 1x1 transparent texture.
+    pub fn empty(ctx: &Context) -> Result<Self> {
+        let image = image::RgbaImage::from_pixel(1, 1, image::Rgba([0, 0, 0, 0]));
+
+        Ok(Self::from_rgba(
+            ctx,
+            &image,
+            None,
+            TextureOptions::default(),
+        ))
+    }
+
+    /// 1x1 white texture, which leaves vertex colors untouched when sampled.
+    ///
+    /// Meshes without texture are drawn with the one shared by the context,
+    /// see [`Context::white_texture`].
+    pub fn white(ctx: &Context) -> Self {
+        let image = image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255]));
+
+        Self::from_rgba(
+            ctx,
+            &image,
+            Some("white texture"),
+            TextureOptions::default(),
+        )
+    }
+
+    /// Load an image file, or a texture compressed ahead of time.
+    ///
+    /// PNG and JPEG are always supported. WebP, BMP, TGA, GIF (first frame)
+    /// and QOI images need the feature of the same name, KTX2 and DDS
+    /// textures the `ktx2` and `dds` features. Compressed textures keep
+    /// their mipmaps and are uploaded without decoding, which fails with
+    /// [`Error::UnsupportedByAdapter`] when their format is not
+    /// supported, e.g. BC formats on mobile GPUs.
+    pub fn from_path(
+        ctx: &Context,
+        path: &std::path::Path,
+        label: Option<&str>,
+        options: TextureOptions,
+    ) -> Result<Self> {
+        let bytes = std::fs::read(path).map_err(|err| Error::io(path, err))?;
+        let format = image::ImageFormat::from_path(path).ok();
+
+        Self::from_encoded(ctx, &bytes, format, label, options)
+    }
+
+    fn from_encoded(
+        ctx: &Context,
+        bytes: &[u8],
+        format: Option<image::ImageFormat>,
+        label: Option<&str>,
+        options: TextureOptions,
+    ) -> Result<Self> {
+        #[cfg(feature = "ktx2")]
+        if bytes.starts_with(&KTX2_MAGIC) {
+            let image =
+                compressed::parse_ktx2(bytes, ctx.device.limits().max_texture_dimension_2d)?;
+            return Self::from_compressed(ctx, &image, label, options);
+        }
+        #[cfg(feature = "dds")]
+        if bytes.starts_with(&DDS_MAGIC) {
+            let image = compressed::parse_dds(
+                bytes,
+                options.srgb,
+                ctx.device.limits().max_texture_dimension_2d,
+            )?;
+            return Self::from_compressed(ctx, &image, label, options);
+        }
+
+        let image = decode(bytes, format)?;
+        Self::from_image(ctx, &image, label, options)
+    }
+
+    pub fn from_image(
+        ctx: &Context,
+        img: &image::DynamicImage,
+        label: Option<&str>,
+        options: TextureOptions,
+    ) -> Result<Self> {
+        Ok(Self::from_rgba(ctx, &img.to_rgba8(), label, options))
     }
 
-    pub fn empty() -> Result<Self> {
-        let label = None;
+    /// Blank `width` x `height` texture, transparent black, to be filled
+    /// with [`Texture::update`], e.g. a canvas or the frames of a video.
+    ///
+    /// `format` must be one of the 8-bit RGBA or BGRA formats. Null sizes
+    /// are raised to 1.
+    pub fn create(
+        ctx: &Context,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Result<Self> {
+        if channels(format).is_none() {
+            return Err(Error::UnsupportedFormat(format!("{format:?} textures")));
+        }
+
+        let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("blank texture"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let options = TextureOptions {
+            srgb: format.is_srgb(),
+            ..Default::default()
+        };
+
+        Ok(Self::from_wgpu(
+            ctx,
+            texture,
+            Some("blank texture"),
+            options,
+        ))
+    }
+
+    fn from_rgba(
+        ctx: &Context,
+        rgba: &image::RgbaImage,
+        label: Option<&str>,
+        options: TextureOptions,
+    ) -> Self {
+        let dimensions = rgba.dimensions();
         let size = wgpu::Extent3d {
-            width: 1,
-            height: 1,
+            width: dimensions.0,
+            height: dimensions.1,
             depth_or_array_layers: 1,
         };
-        let gl_context = Context::get();
-        let texture = gl_context.device.create_texture(&wgpu::TextureDescriptor {
+        let mip_level_count = if options.mipmaps {
+            size.max_mips(wgpu::TextureDimension::D2)
+        } else {
+            1
+        };
+        let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
             label,
             size,
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            format: options.format(),
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         });
 
-        gl_context.queue.write_texture(
-            wgpu::ImageCopyTexture {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &[0, 0, 0, 0],
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4),
-                rows_per_image: Some(1),
-            },
-            size,
-        );
+        // Each level is downscaled from the previous one, in linear space for
+        // sRGB images so that averaged colors are not darkened
+        let mut level = std::borrow::Cow::Borrowed(rgba);
+        let mut linear = (mip_level_count > 1 && options.srgb).then(|| to_linear(rgba));
+        for mip_level in 0..mip_level_count {
+            if mip_level > 0 {
+                let (width, height) = level.dimensions();
+                let (width, height) = ((width / 2).max(1), (height / 2).max(1));
+                let filter = image::imageops::FilterType::Triangle;
+                level = std::borrow::Cow::Owned(match &mut linear {
+                    Some(linear) => {
+                        *linear = image::imageops::resize(linear, width, height, filter);
+                        to_srgb(linear)
+                    }
+                    None => image::imageops::resize(level.as_ref(), width, height, filter),
+                });
+            }
+            let (width, height) = level.dimensions();
 
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = gl_context.device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
+            ctx.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                &level,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        Self::from_wgpu(ctx, texture, label, options)
+    }
+
+    /// Upload the levels of a compressed texture, which replace the mipmaps
+    /// of `options`.
+    #[cfg(any(feature = "ktx2", feature = "dds"))]
+    fn from_compressed(
+        ctx: &Context,
+        image: &CompressedImage,
+        label: Option<&str>,
+        options: TextureOptions,
+    ) -> Result<Self> {
+        let features = image.format.required_features();
+        if !ctx.device.features().contains(features) {
+            return Err(Error::UnsupportedByAdapter(image.format));
+        }
+
+        let mip_level_count = image.levels.len() as u32;
+        let texture = ctx.device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width: image.width,
+                height: image.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: image.format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
         });
+        for (mip_level, level) in (0..mip_level_count).zip(&image.levels) {
+            ctx.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                level,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(image.bytes_per_row(mip_level)),
+                    rows_per_image: Some(image.rows(mip_level)),
+                },
+                image.level_size(mip_level),
+            );
+        }
 
-        let texture_bind_layout = gl_context.bind_group_layouts.get("texture").unwrap();
-        let bind_group = gl_context
+        let options = TextureOptions {
+            mipmaps: mip_level_count > 1,
+            srgb: image.format.is_srgb(),
+            ..options
+        };
+
+        Ok(Self::from_wgpu(ctx, texture, label, options))
+    }
+
+    /// Wrap a texture created by the caller, which must have the
+    /// `TEXTURE_BINDING` usage, so it can be drawn like any other.
+    pub(crate) fn from_wgpu(
+        ctx: &Context,
+        texture: wgpu::Texture,
+        label: Option<&str>,
+        options: TextureOptions,
+    ) -> Self {
+        let dimensions = (texture.width(), texture.height());
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = ctx.device.create_sampler(&options.sampler_descriptor());
+        let size = Vec2 {
+            x: dimensions.0 as f32,
+            y: dimensions.1 as f32,
+        };
+        let size_buffer = ctx
             .device
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: texture_bind_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&sampler),
-                    },
-                ],
-                label,
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("texture size"),
+                contents: bytemuck::cast_slice(&[size]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             });
+        let bind_group = bind_group(ctx, &view, &sampler, &size_buffer, label);
 
-        Ok(Self {
+        Self {
             texture,
             view,
-            sampler,
-            bind_group,
-        })
+            size_buffer,
+            sampling: Mutex::new(Sampling {
+                options,
+                sampler: Arc::new(sampler),
+                bind_group: Arc::new(bind_group),
+            }),
+        }
     }
 
-    pub fn from_path(path: &std::path::Path, label: Option<&str>) -> Result<Self> {
-        let image = image::open(path).unwrap();
-        Self::from_image(&image, label)
+    /// Options the texture was created with, updated by the setters.
+    ///
+    /// The format and mipmaps cannot change after creation.
+    pub fn options(&self) -> TextureOptions {
+        self.sampling.lock().unwrap().options
     }
 
-    pub fn from_image(img: &image::DynamicImage, label: Option<&str>) -> Result<Self> {
-        let gl_context = Context::get();
-        let rgba = img.to_rgba8();
-        let dimensions = img.dimensions();
-        let size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
-            depth_or_array_layers: 1,
+    pub fn sampler(&self) -> Arc<wgpu::Sampler> {
+        self.sampling.lock().unwrap().sampler.clone()
+    }
+
+    /// Bind group of the texture, its view, sampler and size, as expected
+    /// by the standard pipeline.
+    pub fn bind_group(&self) -> Arc<wgpu::BindGroup> {
+        self.sampling.lock().unwrap().bind_group.clone()
+    }
+
+    /// Filter the texture linearly, or show sharp pixels when `smooth` is
+    /// false.
+    ///
+    /// Like the other setters, it applies to every sprite sharing the
+    /// texture, but not to the materials created before, which keep the
+    /// previous sampler.
+    pub fn set_smooth(&self, ctx: &Context, smooth: bool) {
+        let filter = if smooth {
+            wgpu::FilterMode::Linear
+        } else {
+            wgpu::FilterMode::Nearest
         };
-        let format = wgpu::TextureFormat::Rgba8UnormSrgb;
-        let texture = gl_context.device.create_texture(&wgpu::TextureDescriptor {
-            label,
-            size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
 
-        gl_context.queue.write_texture(
+        self.set_options(
+            ctx,
+            TextureOptions {
+                mag_filter: filter,
+                min_filter: filter,
+                mipmap_filter: filter,
+                ..self.options()
+            },
+        );
+    }
+
+    /// Whether every filter is linear, as set by [`Texture::set_smooth`].
+    pub fn is_smooth(&self) -> bool {
+        let options = self.options();
+
+        [
+            options.mag_filter,
+            options.min_filter,
+            options.mipmap_filter,
+        ]
+        .iter()
+        .all(|filter| *filter == wgpu::FilterMode::Linear)
+    }
+
+    /// Repeat the texture in both directions, or clamp it to its edges when
+    /// `repeated` is false.
+    pub fn set_repeated(&self, ctx: &Context, repeated: bool) {
+        let address_mode = if repeated {
+            wgpu::AddressMode::Repeat
+        } else {
+            wgpu::AddressMode::ClampToEdge
+        };
+
+        self.set_options(
+            ctx,
+            TextureOptions {
+                address_mode_u: address_mode,
+                address_mode_v: address_mode,
+                ..self.options()
+            },
+        );
+    }
+
+    pub fn is_repeated(&self) -> bool {
+        self.options().address_mode_u == wgpu::AddressMode::Repeat
+    }
+
+    /// Replace the pixels of the texture from `origin`, its top-left corner
+    /// being `(0, 0)`, with those of `image`.
+    ///
+    /// Fails if `image` does not fit in the texture. Only the first mipmap
+    /// level is updated.
+    pub fn update(
+        &self,
+        ctx: &Context,
+        image: &image::RgbaImage,
+        origin: (u32, u32),
+    ) -> Result<()> {
+        let (width, height) = image.dimensions();
+        let size = self.texture.size();
+        let fits = |origin: u32, length: u32, limit: u32| {
+            origin.checked_add(length).is_some_and(|end| end <= limit)
+        };
+        if !fits(origin.0, width, size.width) || !fits(origin.1, height, size.height) {
+            return Err(Error::InvalidArgument(format!(
+                "{width}x{height} image at {origin:?} does not fit in the {}x{} texture",
+                size.width, size.height
+            )));
+        }
+        let Some(channels) = channels(self.texture.format()) else {
+            return Err(Error::UnsupportedFormat(format!(
+                "updates of {:?} textures",
+                self.texture.format()
+            )));
+        };
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+
+        let pixels = swizzle(image.as_raw(), channels);
+        // Unlike buffer copies, writes from the CPU have no row alignment
+        // requirement, so rows are uploaded tightly packed
+        ctx.queue.write_texture(
             wgpu::ImageCopyTexture {
                 aspect: wgpu::TextureAspect::All,
-                texture: &texture,
+                texture: &self.texture,
                 mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
+                origin: wgpu::Origin3d {
+                    x: origin.0,
+                    y: origin.1,
+                    z: 0,
+                },
             },
-            &rgba,
+            &pixels,
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
+                bytes_per_row: Some(4 * width),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
             },
-            size,
         );
 
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = gl_context.device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
+        Ok(())
+    }
+
+    /// Copy the first mipmap level of the texture back to the CPU, once
+    /// every command submitted before has been executed.
+    ///
+    /// Awaiting it does not block the thread while the GPU works: on native
+    /// backends, a thread waits for the copy and wakes the future once it is
+    /// done. See [`Texture::read_pixels_blocking`] to wait on the current
+    /// thread instead.
+    ///
+    /// The texture needs the `COPY_SRC` usage, which every texture created
+    /// by this module has.
+    pub async fn read_pixels(&self, ctx: &Ctx) -> Result<image::RgbaImage> {
+        read_texture(ctx, &self.texture).await
+    }
+
+    /// Same as [`Texture::read_pixels`], blocking the thread until the copy
+    /// is done. Not available on the web, where the thread cannot wait for
+    /// the GPU.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_pixels_blocking(&self, ctx: &Context) -> Result<image::RgbaImage> {
+        read_texture_blocking(ctx, &self.texture)
+    }
+
+    /// Recreate the sampler and bind group from `options`, whose format and
+    /// mipmaps are ignored.
+    fn set_options(&self, ctx: &Context, options: TextureOptions) {
+        let sampler = ctx.device.create_sampler(&options.sampler_descriptor());
+        let bind_group = bind_group(ctx, &self.view, &sampler, &self.size_buffer, None);
+
+        *self.sampling.lock().unwrap() = Sampling {
+            options,
+            sampler: Arc::new(sampler),
+            bind_group: Arc::new(bind_group),
+        };
+    }
+}
+
+/// Decode an image whose format is recognized from its first bytes, or
+/// `format` otherwise.
+fn decode(bytes: &[u8], format: Option<image::ImageFormat>) -> Result<image::DynamicImage> {
+    // Only reached when their features are disabled
+    if bytes.starts_with(&KTX2_MAGIC) {
+        return Err(Error::MissingFeature {
+            format: "KTX2",
+            feature: "ktx2",
         });
-        let size = Vec2 {
-            x: dimensions.0 as f32,
-            y: dimensions.1 as f32,
+    }
+    if bytes.starts_with(&DDS_MAGIC) {
+        return Err(Error::MissingFeature {
+            format: "DDS",
+            feature: "dds",
+        });
+    }
+
+    let format = image::guess_format(bytes)
+        .ok()
+        .or(format)
+        .ok_or_else(|| Error::UnsupportedFormat("unknown image format".to_owned()))?;
+    if !format.reading_enabled() {
+        let feature = match format {
+            image::ImageFormat::WebP => Some(("WebP", "webp")),
+            image::ImageFormat::Bmp => Some(("BMP", "bmp")),
+            image::ImageFormat::Tga => Some(("TGA", "tga")),
+            image::ImageFormat::Gif => Some(("GIF", "gif")),
+            image::ImageFormat::Qoi => Some(("QOI", "qoi")),
+            _ => None,
         };
-        let size_buffer = gl_context
+        return Err(match feature {
+            Some((format, feature)) => Error::MissingFeature { format, feature },
+            None => Error::UnsupportedFormat(format!("{format:?} images")),
+        });
+    }
+
+    image::load_from_memory_with_format(bytes, format).map_err(Error::Image)
+}
+
+/// Order of the red, green, blue and alpha bytes in the pixels of 8-bit
+/// color `format`s.
+fn channels(format: wgpu::TextureFormat) -> Option<[usize; 4]> {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => Some([0, 1, 2, 3]),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => Some([2, 1, 0, 3]),
+        _ => None,
+    }
+}
+
+/// Convert RGBA pixels to the byte order of `channels` and back, swapping
+/// the same bytes both ways.
+fn swizzle(pixels: &[u8], channels: [usize; 4]) -> std::borrow::Cow<'_, [u8]> {
+    if channels == [0, 1, 2, 3] {
+        return std::borrow::Cow::Borrowed(pixels);
+    }
+
+    pixels
+        .chunks_exact(4)
+        .flat_map(|pixel| channels.map(|channel| pixel[channel]))
+        .collect()
+}
+
+/// Number of bytes of the rows of a `width` pixels wide 8-bit color
+/// texture, rounded up to [`wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`] as
+/// required when copying to a buffer.
+/// Colors of an sRGB image in linear space, alpha being kept as is.
+fn to_linear(image: &image::RgbaImage) -> image::Rgba32FImage {
+    image::Rgba32FImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0.map(|channel| channel as f32 / 255.);
+        image::Rgba([srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a])
+    })
+}
+
+/// Inverse of [`to_linear`].
+fn to_srgb(image: &image::Rgba32FImage) -> image::RgbaImage {
+    let to_u8 = |channel: f32| (channel.clamp(0., 1.) * 255.).round() as u8;
+
+    image::RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b, a] = image.get_pixel(x, y).0;
+        image::Rgba([
+            to_u8(linear_to_srgb(r)),
+            to_u8(linear_to_srgb(g)),
+            to_u8(linear_to_srgb(b)),
+            to_u8(a),
+        ])
+    })
+}
+
+fn padded_bytes_per_row(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    (4 * width).div_ceil(align) * align
+}
+
+/// Read the first mipmap level of an 8-bit color texture back to the CPU.
+pub(crate) async fn read_texture(ctx: &Ctx, texture: &wgpu::Texture) -> Result<image::RgbaImage> {
+    let readback = Readback::start(ctx, texture)?;
+    // Native backends only run the callback while polling the device, the
+    // web one resolves it from the browser's event loop
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let ctx = ctx.clone();
+        let submission = readback.submission.clone();
+        std::thread::spawn(move || {
+            ctx.device
+                .poll(wgpu::Maintain::WaitForSubmissionIndex(submission))
+        });
+    }
+    std::future::poll_fn(|cx| {
+        let mut state = readback.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => std::task::Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                std::task::Poll::Pending
+            }
+        }
+    })
+    .await
+    .map_err(|_| Error::DeviceLost)?;
+
+    Ok(readback.pixels())
+}
+
+/// Same as [`read_texture`], blocking the thread until the copy is done.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn read_texture_blocking(
+    ctx: &Context,
+    texture: &wgpu::Texture,
+) -> Result<image::RgbaImage> {
+    let readback = Readback::start(ctx, texture)?;
+    ctx.device.poll(wgpu::Maintain::WaitForSubmissionIndex(
+        readback.submission.clone(),
+    ));
+    let result = readback.state.lock().unwrap().result.take();
+    match result {
+        Some(Ok(())) => Ok(readback.pixels()),
+        _ => Err(Error::DeviceLost),
+    }
+}
+
+/// Copy of a texture into a buffer being mapped for reading.
+struct Readback {
+    buffer: wgpu::Buffer,
+    size: wgpu::Extent3d,
+    channels: [usize; 4],
+    submission: wgpu::SubmissionIndex,
+    state: std::sync::Arc<std::sync::Mutex<MapState>>,
+}
+
+impl Readback {
+    /// Submit the copy of the first mipmap level of `texture` and request
+    /// the mapping of the buffer.
+    fn start(ctx: &Context, texture: &wgpu::Texture) -> Result<Self> {
+        let Some(channels) = channels(texture.format()) else {
+            return Err(Error::UnsupportedFormat(format!(
+                "readback of {:?} textures",
+                texture.format()
+            )));
+        };
+        let size = wgpu::Extent3d {
+            depth_or_array_layers: 1,
+            ..texture.size()
+        };
+        let padded_bytes_per_row = padded_bytes_per_row(size.width);
+
+        let buffer = ctx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback buffer"),
+            size: (padded_bytes_per_row * size.height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = ctx
             .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("texture size"),
-                contents: bytemuck::cast_slice(&[size]),
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Readback Encoder"),
             });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        let submission = ctx.queue.submit(std::iter::once(encoder.finish()));
 
-        let texture_bind_layout = gl_context.bind_group_layouts.get("texture").unwrap();
-        let bind_group = gl_context
-            .device
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: texture_bind_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&sampler),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: size_buffer.as_entire_binding(),
-                    },
-                ],
-                label,
+        let state = std::sync::Arc::new(std::sync::Mutex::new(MapState::default()));
+        let callback_state = state.clone();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let mut state = callback_state.lock().unwrap();
+                state.result = Some(result);
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
             });
 
         Ok(Self {
-            texture,
-            view,
-            sampler,
-            bind_group,
+            buffer,
+            size,
+            channels,
+            submission,
+            state,
         })
     }
+
+    /// Pixels of the mapped buffer, without the padding of its rows.
+    fn pixels(self) -> image::RgbaImage {
+        let unpadded_bytes_per_row = 4 * self.size.width;
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * self.size.height) as usize);
+        for row in self
+            .buffer
+            .slice(..)
+            .get_mapped_range()
+            .chunks(padded_bytes_per_row(self.size.width) as usize)
+        {
+            pixels.extend_from_slice(&swizzle(
+                &row[..unpadded_bytes_per_row as usize],
+                self.channels,
+            ));
+        }
+        self.buffer.unmap();
+
+        image::RgbaImage::from_raw(self.size.width, self.size.height, pixels)
+            .expect("the readback buffer holds every row of the texture")
+    }
+}
+
+/// Shared between a buffer mapping callback and the future awaiting it.
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<std::task::Waker>,
+}
+
+fn bind_group(
+    ctx: &Context,
+    view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+    size_buffer: &wgpu::Buffer,
+    label: Option<&str>,
+) -> wgpu::BindGroup {
+    ctx.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &ctx.bind_group_layouts.texture,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: size_buffer.as_entire_binding(),
+            },
+        ],
+        label,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, padded_bytes_per_row, swizzle, TextureOptions};
+    use crate::Error;
+
+    #[test]
+    fn disabled_formats_name_their_feature() {
+        #[cfg(not(feature = "webp"))]
+        assert!(matches!(
+            decode(b"RIFF\0\0\0\0WEBPVP8 ", None),
+            Err(Error::MissingFeature {
+                feature: "webp",
+                ..
+            })
+        ));
+        #[cfg(not(feature = "dds"))]
+        assert!(matches!(
+            decode(b"DDS |\0\0\0", None),
+            Err(Error::MissingFeature { feature: "dds", .. })
+        ));
+        assert!(matches!(
+            decode(b"not an image", None),
+            Err(Error::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            decode(b"\x89PNG\r\n\x1a\n", None),
+            Err(Error::Image(_))
+        ));
+    }
+
+    #[test]
+    fn rows_are_padded_for_buffer_copies() {
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+    }
+
+    #[test]
+    fn bgra_pixels_are_swizzled() {
+        let rgba = [1, 2, 3, 4, 5, 6, 7, 8];
+
+        assert_eq!(*swizzle(&rgba, [0, 1, 2, 3]), rgba);
+        assert_eq!(*swizzle(&rgba, [2, 1, 0, 3]), [3, 2, 1, 4, 7, 6, 5, 8]);
+    }
+
+    #[test]
+    fn anisotropy_requires_linear_filters() {
+        let options = TextureOptions {
+            anisotropy: 32,
+            ..TextureOptions::smooth()
+        };
+        assert_eq!(options.sampler_descriptor().anisotropy_clamp, 16);
+
+        let options = TextureOptions {
+            anisotropy: 8,
+            ..TextureOptions::pixel_art()
+        };
+        assert_eq!(options.sampler_descriptor().anisotropy_clamp, 1);
+    }
 }
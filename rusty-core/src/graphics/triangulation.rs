@@ -0,0 +1,282 @@
+//! Ear-clipping triangulation of simple polygons with holes.
+
+use glam::Vec2;
+
+/// Triangulate the polygon `outer`, minus the `holes` it contains.
+///
+/// Points are indexed as if `outer` and every hole were concatenated, in
+/// order. Contours can be given in any winding order but must not
+/// self-intersect nor intersect each other.
+pub(crate) fn triangulate(outer: &[Vec2], holes: &[Vec<Vec2>]) -> Vec<[usize; 3]> {
+    let points: Vec<Vec2> = outer
+        .iter()
+        .chain(holes.iter().flatten())
+        .copied()
+        .collect();
+    if outer.len() < 3 {
+        return Vec::new();
+    }
+
+    // Outer contour counter-clockwise, holes clockwise (y pointing up)
+    let mut ring: Vec<usize> = (0..outer.len()).collect();
+    if signed_area(&points, &ring) < 0. {
+        ring.reverse();
+    }
+
+    let mut contours = Vec::with_capacity(holes.len());
+    let mut start = outer.len();
+    for hole in holes {
+        let mut contour: Vec<usize> = (start..start + hole.len()).collect();
+        start += hole.len();
+        if contour.len() < 3 {
+            continue;
+        }
+        if signed_area(&points, &contour) > 0. {
+            contour.reverse();
+        }
+        contours.push(contour);
+    }
+
+    // Holes are merged right to left, so bridges never cross a hole that is
+    // yet to be merged
+    let rightmost = |contour: &Vec<usize>| {
+        contour
+            .iter()
+            .copied()
+            .max_by(|a, b| points[*a].x.total_cmp(&points[*b].x))
+            .unwrap()
+    };
+    contours.sort_by(|a, b| points[rightmost(b)].x.total_cmp(&points[rightmost(a)].x));
+    for (i, hole) in contours.iter().enumerate() {
+        bridge_hole(&points, &mut ring, hole, &contours[i + 1..]);
+    }
+
+    clip_ears(&points, ring)
+}
+
+/// Merge `hole` into `ring` through a two-way bridge between the rightmost
+/// point of the hole and a visible point of the ring.
+fn bridge_hole(points: &[Vec2], ring: &mut Vec<usize>, hole: &[usize], others: &[Vec<usize>]) {
+    let start = (0..hole.len())
+        .max_by(|a, b| points[hole[*a]].x.total_cmp(&points[hole[*b]].x))
+        .unwrap();
+    let m = points[hole[start]];
+
+    let mut candidates: Vec<usize> = (0..ring.len()).collect();
+    candidates.sort_by(|a, b| {
+        points[ring[*a]]
+            .distance_squared(m)
+            .total_cmp(&points[ring[*b]].distance_squared(m))
+    });
+    let visible = candidates
+        .iter()
+        .copied()
+        .find(|i| {
+            let count = ring.len();
+            let (a, p, b) = (
+                points[ring[(i + count - 1) % count]],
+                points[ring[*i]],
+                points[ring[(i + 1) % count]],
+            );
+
+            locally_inside(a, p, b, m)
+                && !crosses_contour(points, ring, p, m)
+                && !crosses_contour(points, hole, p, m)
+                && others
+                    .iter()
+                    .all(|other| !crosses_contour(points, other, p, m))
+        })
+        // Degenerate input, bridge to the closest point anyway
+        .unwrap_or(candidates[0]);
+
+    let mut merged = Vec::with_capacity(ring.len() + hole.len() + 2);
+    merged.extend_from_slice(&ring[..=visible]);
+    merged.extend((0..=hole.len()).map(|i| hole[(start + i) % hole.len()]));
+    merged.extend_from_slice(&ring[visible..]);
+    *ring = merged;
+}
+
+fn clip_ears(points: &[Vec2], mut ring: Vec<usize>) -> Vec<[usize; 3]> {
+    let mut triangles = Vec::with_capacity(ring.len().saturating_sub(2));
+
+    while ring.len() > 3 {
+        let count = ring.len();
+        let corner = |i: usize| {
+            [
+                ring[(i + count - 1) % count],
+                ring[i],
+                ring[(i + 1) % count],
+            ]
+        };
+
+        let ear = (0..count).find(|i| is_ear(points, &ring, corner(*i)));
+        // Without ear the polygon is degenerate (e.g. collinear points):
+        // remove a flat corner without emitting it, or force progress.
+        let (i, emit) = match ear {
+            Some(i) => (i, true),
+            None => match (0..count).find(|i| {
+                let [a, b, c] = corner(*i).map(|j| points[j]);
+                cross(a, b, c).abs() <= f32::EPSILON
+            }) {
+                Some(i) => (i, false),
+                None => (0, true),
+            },
+        };
+
+        if emit {
+            triangles.push(corner(i));
+        }
+        ring.remove(i);
+    }
+
+    if ring.len() == 3 {
+        let [a, b, c] = [ring[0], ring[1], ring[2]].map(|i| points[i]);
+        if cross(a, b, c).abs() > f32::EPSILON {
+            triangles.push([ring[0], ring[1], ring[2]]);
+        }
+    }
+
+    triangles
+}
+
+fn is_ear(points: &[Vec2], ring: &[usize], [ia, ib, ic]: [usize; 3]) -> bool {
+    let (a, b, c) = (points[ia], points[ib], points[ic]);
+    if cross(a, b, c) <= 0. {
+        return false;
+    }
+
+    // Bridges duplicate points, which never prevent an ear
+    ring.iter()
+        .map(|i| points[*i])
+        .filter(|p| *p != a && *p != b && *p != c)
+        .all(|p| !in_triangle(p, a, b, c))
+}
+
+/// Whether the segment from `p` to `m` enters the interior of the ring at its
+/// corner `a`, `p`, `b`.
+fn locally_inside(a: Vec2, p: Vec2, b: Vec2, m: Vec2) -> bool {
+    if cross(a, p, b) >= 0. {
+        cross(a, p, m) > 0. && cross(p, b, m) > 0.
+    } else {
+        cross(a, p, m) > 0. || cross(p, b, m) > 0.
+    }
+}
+
+/// Whether the segment `p`-`q` properly crosses an edge of `contour`.
+fn crosses_contour(points: &[Vec2], contour: &[usize], p: Vec2, q: Vec2) -> bool {
+    (0..contour.len()).any(|i| {
+        let a = points[contour[i]];
+        let b = points[contour[(i + 1) % contour.len()]];
+
+        a != p && a != q && b != p && b != q && segments_cross(p, q, a, b)
+    })
+}
+
+fn segments_cross(p1: Vec2, q1: Vec2, p2: Vec2, q2: Vec2) -> bool {
+    let d1 = cross(p1, q1, p2);
+    let d2 = cross(p1, q1, q2);
+    let d3 = cross(p2, q2, p1);
+    let d4 = cross(p2, q2, q1);
+
+    d1 * d2 < 0. && d3 * d4 < 0.
+}
+
+fn in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    cross(a, b, p) >= 0. && cross(b, c, p) >= 0. && cross(c, a, p) >= 0.
+}
+
+/// Twice the signed area of triangle `o`, `a`, `b`, positive when it turns
+/// left.
+fn cross(o: Vec2, a: Vec2, b: Vec2) -> f32 {
+    (a - o).perp_dot(b - o)
+}
+
+fn signed_area(points: &[Vec2], contour: &[usize]) -> f32 {
+    (0..contour.len())
+        .map(|i| points[contour[i]].perp_dot(points[contour[(i + 1) % contour.len()]]))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::triangulate;
+    use glam::Vec2;
+
+    fn points(coordinates: &[(f32, f32)]) -> Vec<Vec2> {
+        coordinates.iter().map(|(x, y)| Vec2::new(*x, *y)).collect()
+    }
+
+    /// Sum of the areas of the triangles.
+    fn area(points: &[Vec2], triangles: &[[usize; 3]]) -> f32 {
+        triangles
+            .iter()
+            .map(|[a, b, c]| {
+                (points[*b] - points[*a])
+                    .perp_dot(points[*c] - points[*a])
+                    .abs()
+                    / 2.
+            })
+            .sum()
+    }
+
+    #[test]
+    fn square() {
+        let square = points(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.)]);
+        let triangles = triangulate(&square, &[]);
+
+        assert_eq!(triangles.len(), 2);
+        assert_eq!(area(&square, &triangles), 100.);
+    }
+
+    #[test]
+    fn concave_polygon() {
+        // U shape, in clockwise order
+        let shape = points(&[
+            (0., 0.),
+            (0., 30.),
+            (30., 30.),
+            (30., 0.),
+            (20., 0.),
+            (20., 20.),
+            (10., 20.),
+            (10., 0.),
+        ]);
+        let triangles = triangulate(&shape, &[]);
+
+        assert_eq!(triangles.len(), 6);
+        assert_eq!(area(&shape, &triangles), 700.);
+    }
+
+    #[test]
+    fn polygon_with_holes() {
+        let outer = points(&[(0., 0.), (40., 0.), (40., 20.), (0., 20.)]);
+        let holes = vec![
+            points(&[(5., 5.), (15., 5.), (15., 15.), (5., 15.)]),
+            points(&[(25., 5.), (35., 5.), (30., 15.)]),
+        ];
+        let triangles = triangulate(&outer, &holes);
+        let all: Vec<Vec2> = outer
+            .iter()
+            .chain(holes.iter().flatten())
+            .copied()
+            .collect();
+
+        // 4 + 4 + 3 points and 2 holes: n + 2h - 2 triangles
+        assert_eq!(triangles.len(), 13);
+        assert!((area(&all, &triangles) - (800. - 100. - 50.)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn collinear_points_are_skipped() {
+        let shape = points(&[(0., 0.), (5., 0.), (10., 0.), (10., 10.), (0., 10.)]);
+        let triangles = triangulate(&shape, &[]);
+
+        assert_eq!(area(&shape, &triangles), 100.);
+    }
+
+    #[test]
+    fn degenerate_polygons() {
+        assert!(triangulate(&points(&[(0., 0.), (1., 1.)]), &[]).is_empty());
+        assert!(triangulate(&points(&[(0., 0.), (1., 1.), (2., 2.)]), &[]).is_empty());
+    }
+}
@@ -1,8 +1,16 @@
-use std::{collections::HashMap, sync::OnceLock};
+use std::sync::Arc;
+
+#[cfg(feature = "graphics")]
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
 
 #[cfg(feature = "audio")]
 pub mod audio;
 
+mod error;
+
 #[cfg(feature = "graphics")]
 pub mod graphics;
 
@@ -11,41 +19,174 @@ pub mod ui;
 
 pub mod math;
 
+pub use error::{Error, Result};
+
 pub use glam;
 pub use wgpu;
 pub use winit;
 
-pub static mut GL_CONTEXT: OnceLock<Context> = OnceLock::new();
+/// Shared handle on a [`Context`], cloned into every GPU resource that has to
+/// write to its buffers after creation.
+pub type Ctx = Arc<Context>;
 
+/// GPU device and the resources shared by everything drawn with it.
+///
+/// A `Context` is created once per device and handed around as a [`Ctx`], so
+/// several renderers can live side by side and dropping the last handle
+/// releases the device.
 #[derive(Debug)]
 pub struct Context {
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
-    pub config: wgpu::SurfaceConfiguration,
-    pub render_pipelines: HashMap<String, wgpu::RenderPipeline>,
-    pub bind_group_layouts: HashMap<String, wgpu::BindGroupLayout>,
+    /// Color format of the targets every pipeline of this context renders to.
+    pub format: wgpu::TextureFormat,
+    /// Pipelines created so far, see [`graphics::Shader::pipeline`].
+    #[cfg(feature = "graphics")]
+    pub render_pipelines: Mutex<HashMap<graphics::PipelineKey, Arc<wgpu::RenderPipeline>>>,
+    pub bind_group_layouts: BindGroupLayouts,
+    #[cfg(feature = "graphics")]
+    white_texture: OnceLock<graphics::texture::Texture>,
+    #[cfg(feature = "graphics")]
+    standard_shader: OnceLock<graphics::Shader>,
 }
 
 impl Context {
-    pub fn init(device: wgpu::Device, queue: wgpu::Queue, config: wgpu::SurfaceConfiguration) {
-        unsafe {
-            GL_CONTEXT
-                .set(Self {
-                    device,
-                    queue,
-                    config,
-                    render_pipelines: HashMap::new(),
-                    bind_group_layouts: HashMap::new(),
-                })
-                .unwrap()
+    pub fn new(device: wgpu::Device, queue: wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+        let bind_group_layouts = BindGroupLayouts::new(&device);
+
+        Self {
+            device,
+            queue,
+            format,
+            #[cfg(feature = "graphics")]
+            render_pipelines: Mutex::new(HashMap::new()),
+            bind_group_layouts,
+            #[cfg(feature = "graphics")]
+            white_texture: OnceLock::new(),
+            #[cfg(feature = "graphics")]
+            standard_shader: OnceLock::new(),
         }
     }
 
-    pub fn get() -> &'static Self {
-        unsafe { GL_CONTEXT.get().unwrap() }
+    /// 1x1 white texture bound when drawing meshes without texture, so their
+    /// vertex colors are drawn as is.
+    #[cfg(feature = "graphics")]
+    pub fn white_texture(&self) -> &graphics::texture::Texture {
+        self.white_texture
+            .get_or_init(|| graphics::texture::Texture::white(self))
+    }
+
+    /// Shader of the standard pipeline, drawing vertex colors multiplied by
+    /// the texture.
+    #[cfg(feature = "graphics")]
+    pub fn standard_shader(&self) -> &graphics::Shader {
+        self.standard_shader
+            .get_or_init(|| graphics::Shader::standard(self))
+    }
+}
+
+/// Bind group layouts of the standard 2D pipeline, in bind group order.
+#[derive(Debug)]
+pub struct BindGroupLayouts {
+    pub mouse: wgpu::BindGroupLayout,
+    pub resolution: wgpu::BindGroupLayout,
+    pub projection: wgpu::BindGroupLayout,
+    pub transform: wgpu::BindGroupLayout,
+    pub texture: wgpu::BindGroupLayout,
+    /// Uniforms of a post-processing effect: the shared globals, then the
+    /// parameters of the effect.
+    pub effect: wgpu::BindGroupLayout,
+}
+
+impl BindGroupLayouts {
+    fn new(device: &wgpu::Device) -> Self {
+        let texture = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    // This should match the filterable field of the
+                    // corresponding Texture entry above.
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("texture bind group layout"),
+        });
+
+        let uniform = |binding, visibility| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let effect = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                uniform(0, wgpu::ShaderStages::VERTEX_FRAGMENT),
+                uniform(1, wgpu::ShaderStages::FRAGMENT),
+            ],
+            label: Some("effect bind group layout"),
+        });
+
+        Self {
+            mouse: uniform_layout(device, "mouse bind group layout"),
+            resolution: uniform_layout(device, "resolution bind group layout"),
+            projection: uniform_layout(device, "projection bind group layout"),
+            transform: uniform_layout(device, "transform bind group layout"),
+            texture,
+            effect,
+        }
     }
 
-    pub fn get_mut() -> &'static mut Self {
-        unsafe { GL_CONTEXT.get_mut().unwrap() }
+    /// Layouts in the order expected by `shader.wgsl`.
+    pub fn as_slice(&self) -> [&wgpu::BindGroupLayout; 5] {
+        [
+            &self.mouse,
+            &self.resolution,
+            &self.projection,
+            &self.transform,
+            &self.texture,
+        ]
     }
 }
+
+/// Layout of a single uniform buffer read by the vertex stage.
+fn uniform_layout(device: &wgpu::Device, label: &str) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+        label: Some(label),
+    })
+}
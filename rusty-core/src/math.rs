@@ -1,6 +1,6 @@
 use glam::Vec2;
 
-#[derive(Copy, Clone, Default, Debug)]
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
 pub struct Rect {
     pub x: f32,
     pub y: f32,
@@ -1,13 +1,13 @@
-use crate::graphics::shape::RectangleShape;
+use crate::{graphics::shape::RectangleShape, Ctx};
 
 struct Button {
     shape: RectangleShape, // text:
 }
 
 impl Button {
-    pub fn new(text: &str) -> Self {
+    pub fn new(ctx: &Ctx, text: &str) -> Self {
         Self {
-            shape: RectangleShape::new((0., 0.).into()),
+            shape: RectangleShape::new(ctx, (0., 0.).into()),
         }
     }
 }
@@ -1,20 +1,36 @@
-use crate::graphics::color;
+use std::rc::Rc;
 
-#[derive(Default)]
+use glam::Vec2;
+
+use crate::{
+    graphics::{
+        color,
+        text::{Font, TextStyle},
+        Mesh, Text, Transformable,
+    },
+    math::Rect,
+    Ctx,
+};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum Appearence {
     #[default]
     Regular,
+    /// Synthesized from the font, see [`Text`].
     Bold,
+    /// Synthesized from the font, see [`Text`].
     Italic,
     Underlined,
     StrikeThrough,
 }
 
+#[derive(Copy, Clone, Debug)]
 pub struct Style {
+    /// Height of an em, in pixels.
     pub font_size: f32,
     pub color: color::Color,
     pub appearence: Appearence,
-    // Font
+    /// Space added between characters, in pixels.
     pub letter_spacing: f32,
 }
 
@@ -29,25 +45,26 @@ impl Default for Style {
     }
 }
 
-struct Label {
+/// Single style text of a user interface.
+pub struct Label {
     style: Style,
-    text: String,
+    text: Text,
 }
 
 impl Label {
-    pub fn new(text: &str, style: Style) -> Self {
+    pub fn new(ctx: &Ctx, font: Rc<Font>, text: &str, style: Style) -> Self {
         Self {
             style,
-            text: text.to_owned(),
+            text: Text::with_style(ctx, font, text, text_style(&style)),
         }
     }
 
     pub fn set_text(&mut self, text: &str) {
-        self.text = text.to_owned();
+        self.text.set_string(text);
     }
 
-    pub fn text(&self) -> &String {
-        &self.text
+    pub fn text(&self) -> &str {
+        self.text.string()
     }
 
     pub fn style(&self) -> &Style {
@@ -56,5 +73,40 @@ impl Label {
 
     pub fn set_style(&mut self, style: Style) {
         self.style = style;
+        self.text.set_style(text_style(&style));
+    }
+
+    pub fn set_position(&mut self, position: Vec2) {
+        self.text.set_position(position);
+    }
+
+    pub fn position(&self) -> &Vec2 {
+        self.text.position()
+    }
+
+    /// Area covered by the label, in local coordinates.
+    pub fn bounds(&self) -> Rect {
+        self.text.bounds()
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        self.text.mesh()
+    }
+
+    /// Drawable text of the label, e.g. to align or wrap it.
+    pub fn text_mut(&mut self) -> &mut Text {
+        &mut self.text
+    }
+}
+
+fn text_style(style: &Style) -> TextStyle {
+    TextStyle {
+        character_size: style.font_size,
+        color: style.color,
+        letter_spacing: style.letter_spacing,
+        bold: style.appearence == Appearence::Bold,
+        italic: style.appearence == Appearence::Italic,
+        underlined: style.appearence == Appearence::Underlined,
+        strike_through: style.appearence == Appearence::StrikeThrough,
     }
 }
@@ -1,7 +1,7 @@
 use winit::event::WindowEvent;
 
 // pub mod button;
-// pub mod label;
+pub mod label;
 
 pub trait Widget {
     fn process_events(&mut self, event: &WindowEvent);
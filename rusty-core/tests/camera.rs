@@ -0,0 +1,219 @@
+//! Camera conversions and multi-viewport rendering. Tests are skipped when no
+//! adapter, not even a software one, is available.
+
+#![cfg(feature = "graphics")]
+
+use rusty_core::{
+    glam::Vec2,
+    graphics::{shape::RectangleShape, Camera2D, Drawable, RenderTexture, Renderer, Transformable},
+    math::Rect,
+    wgpu,
+};
+
+const SIZE: u32 = 64;
+
+fn renderer() -> Option<Renderer> {
+    match pollster::block_on(Renderer::headless(SIZE, SIZE)) {
+        Ok(renderer) => Some(renderer),
+        Err(err) => {
+            eprintln!("skipping camera test: {err}");
+            None
+        }
+    }
+}
+
+fn assert_vec2_eq(a: Vec2, b: Vec2) {
+    assert!(a.abs_diff_eq(b, 1e-3), "{a} != {b}");
+}
+
+#[test]
+fn default_camera_maps_world_to_pixels() {
+    let Some(renderer) = renderer() else { return };
+    let target = Vec2::splat(SIZE as f32);
+    let camera = Camera2D::new(renderer.context(), target);
+
+    assert_vec2_eq(
+        camera.world_to_screen(Vec2::new(10., 20.), target),
+        Vec2::new(10., 20.),
+    );
+    assert_vec2_eq(
+        camera.screen_to_world(Vec2::new(10., 20.), target),
+        Vec2::new(10., 20.),
+    );
+}
+
+#[test]
+fn zoom_and_rotation_round_trip() {
+    let Some(renderer) = renderer() else { return };
+    let target = Vec2::splat(SIZE as f32);
+    let mut camera = Camera2D::new(renderer.context(), target);
+    camera.set_center(Vec2::new(100., 100.));
+    camera.set_zoom(2.);
+
+    // The center is always in the middle of the viewport
+    assert_vec2_eq(
+        camera.world_to_screen(Vec2::new(100., 100.), target),
+        target / 2.,
+    );
+    // Zooming in doubles distances on screen
+    assert_vec2_eq(
+        camera.world_to_screen(Vec2::new(110., 100.), target),
+        Vec2::new(52., 32.),
+    );
+
+    camera.set_rotation(0.7);
+    let point = Vec2::new(12., 50.);
+    assert_vec2_eq(
+        camera.screen_to_world(camera.world_to_screen(point, target), target),
+        point,
+    );
+}
+
+#[test]
+fn viewport_offsets_conversions() {
+    let Some(renderer) = renderer() else { return };
+    let target = Vec2::splat(SIZE as f32);
+    let mut camera = Camera2D::new(renderer.context(), Vec2::new(32., 64.));
+    camera.set_viewport(Rect {
+        x: 0.5,
+        y: 0.,
+        width: 0.5,
+        height: 1.,
+    });
+
+    assert_vec2_eq(
+        camera.world_to_screen(Vec2::ZERO, target),
+        Vec2::new(32., 0.),
+    );
+    assert_vec2_eq(
+        camera.screen_to_world(Vec2::new(48., 32.), target),
+        Vec2::new(16., 32.),
+    );
+}
+
+#[test]
+fn follow_ignores_the_dead_zone() {
+    let Some(renderer) = renderer() else { return };
+    let mut camera = Camera2D::new(renderer.context(), Vec2::splat(SIZE as f32));
+    camera.set_center(Vec2::ZERO);
+    camera.set_dead_zone(Vec2::new(20., 20.));
+
+    camera.follow(Vec2::new(8., -8.), 1. / 60.);
+    assert_eq!(camera.center(), Vec2::ZERO);
+
+    camera.follow(Vec2::new(30., 0.), 1. / 60.);
+    assert_eq!(camera.center(), Vec2::new(20., 0.));
+
+    // Smoothed follow only covers part of the distance
+    camera.set_follow_speed(5.);
+    camera.follow(Vec2::new(60., 0.), 1. / 60.);
+    assert!(camera.center().x > 20. && camera.center().x < 50.);
+}
+
+#[test]
+fn bounds_clamp_the_visible_area() {
+    let Some(renderer) = renderer() else { return };
+    let mut camera = Camera2D::new(renderer.context(), Vec2::new(40., 20.));
+    camera.set_bounds(Some(Rect {
+        x: 0.,
+        y: 0.,
+        width: 100.,
+        height: 100.,
+    }));
+
+    camera.set_center(Vec2::new(-50., 95.));
+    assert_eq!(camera.center(), Vec2::new(20., 90.));
+
+    // Zooming out beyond the bounds centers the camera on them
+    camera.set_zoom(0.2);
+    assert_eq!(camera.center(), Vec2::new(50., 50.));
+}
+
+#[test]
+fn shake_fades_out() {
+    let Some(renderer) = renderer() else { return };
+    let target = Vec2::splat(SIZE as f32);
+    let mut camera = Camera2D::new(renderer.context(), target);
+    let center = camera.world_to_screen(camera.center(), target);
+
+    camera.shake(4., 0.5);
+    camera.update(0.1);
+    let shaken = camera.world_to_screen(camera.center(), target);
+    assert!(shaken != center && shaken.distance(center) <= 4. * 2f32.sqrt());
+
+    camera.update(1.);
+    assert_vec2_eq(camera.world_to_screen(camera.center(), target), center);
+}
+
+#[test]
+fn split_screen() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    let mut rect = RectangleShape::new(ctx, (8., 8.).into());
+    rect.set_position((100., 100.).into());
+
+    // Both halves look at the rectangle, the right one twice as close
+    let mut left = Camera2D::new(ctx, Vec2::new(32., 64.));
+    left.set_center(Vec2::new(104., 104.));
+    left.set_viewport(Rect {
+        x: 0.,
+        y: 0.,
+        width: 0.5,
+        height: 1.,
+    });
+    let mut right = Camera2D::new(ctx, Vec2::new(32., 64.));
+    right.set_center(Vec2::new(104., 104.));
+    right.set_zoom(2.);
+    right.set_viewport(Rect {
+        x: 0.5,
+        y: 0.,
+        width: 0.5,
+        height: 1.,
+    });
+
+    let mut frame = renderer.begin_frame().unwrap();
+    {
+        let mut render_pass = renderer.begin_pass(&mut frame, Some(wgpu::Color::BLACK));
+        for camera in [&left, &right] {
+            renderer.set_camera(&mut render_pass, camera, renderer.size());
+            render_pass.draw_mesh(rect.mesh());
+        }
+    }
+    renderer.end_frame(frame);
+    let image = renderer.capture().unwrap();
+
+    // Left: 8x8 square centered on (16, 32)
+    assert_eq!(image.get_pixel(16, 32).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(22, 32).0, [0, 0, 0, 255]);
+    // Right: 16x16 square centered on (48, 32), clipped to the viewport
+    assert_eq!(image.get_pixel(54, 32).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(58, 32).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn camera_in_texture_pass() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    let mut rect = RectangleShape::new(ctx, (8., 8.).into());
+    rect.set_position((100., 100.).into());
+    let mut camera = Camera2D::new(ctx, Vec2::new(16., 16.));
+    camera.set_center(Vec2::new(104., 104.));
+
+    // The viewport follows the texture rather than the frame
+    let target = RenderTexture::new(ctx, 16, 16);
+    let mut frame = renderer.begin_frame().unwrap();
+    {
+        let mut render_pass =
+            renderer.begin_texture_pass(&mut frame, &target, Some(wgpu::Color::BLACK));
+        renderer.set_camera(&mut render_pass, &camera, target.size());
+        render_pass.draw_mesh(rect.mesh());
+    }
+    renderer.end_frame(frame);
+    let image = pollster::block_on(target.texture().read_pixels(ctx)).unwrap();
+
+    assert_eq!(image.get_pixel(4, 4).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(11, 11).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(12, 8).0, [0, 0, 0, 255]);
+}
@@ -0,0 +1,1141 @@
+//! Golden-image tests rendering shapes and sprites with a headless renderer.
+//!
+//! Reference images live in `tests/golden`. Run with `UPDATE_GOLDEN=1` to
+//! (re)generate them after an intended rendering change. Tests are skipped
+//! when no adapter, not even a software one, is available.
+
+#![cfg(feature = "graphics")]
+
+use std::{f32::consts::PI, path::PathBuf, rc::Rc};
+
+use rusty_core::{
+    glam::Vec2,
+    graphics::{
+        color::{self, Color},
+        shape::{
+            CircleShape, ConvexShape, LineCap, LineJoin, LineShape, Path, PolygonShape, Polyline,
+            RectangleShape, Shape,
+        },
+        sprite::Sprite,
+        text::{Font, Text, TextAlignment},
+        texture::{Texture, TextureOptions},
+        BlendMode, DebugDraw, Drawable, Effect, InstancedSprites, Material, Mesh, PostProcessStack,
+        RenderTexture, Renderer, Shader, SpriteBatch, SpriteInstance, Transformable,
+    },
+    math::Rect,
+    wgpu, Error,
+};
+
+const SIZE: u32 = 64;
+/// Maximum difference allowed per channel, software rasterizers are not
+/// always bit-exact on edges.
+const CHANNEL_TOLERANCE: u8 = 2;
+/// Maximum number of pixels allowed to differ beyond the tolerance.
+const MAX_MISMATCHES: usize = (SIZE * SIZE / 100) as usize;
+
+fn renderer() -> Option<Renderer> {
+    match pollster::block_on(Renderer::headless(SIZE, SIZE)) {
+        Ok(renderer) => Some(renderer),
+        Err(err) => {
+            eprintln!("skipping golden test: {err}");
+            None
+        }
+    }
+}
+
+fn texture(renderer: &Renderer, image: image::RgbaImage) -> Rc<Texture> {
+    let image = image::DynamicImage::ImageRgba8(image);
+    Rc::new(
+        Texture::from_image(renderer.context(), &image, None, TextureOptions::default()).unwrap(),
+    )
+}
+
+fn render(renderer: &Renderer, meshes: &[&Mesh]) -> image::RgbaImage {
+    let mut frame = renderer.begin_frame().unwrap();
+    {
+        let mut render_pass = renderer.begin_pass(&mut frame, Some(wgpu::Color::BLACK));
+        for mesh in meshes {
+            render_pass.draw_mesh(mesh);
+        }
+    }
+    renderer.end_frame(frame);
+
+    renderer.capture().unwrap()
+}
+
+fn assert_golden(name: &str, actual: &image::RgbaImage) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save(&path).unwrap();
+        return;
+    }
+
+    let expected = image::open(&path)
+        .unwrap_or_else(|err| panic!("cannot open {}: {err}", path.display()))
+        .to_rgba8();
+    assert_eq!(expected.dimensions(), actual.dimensions());
+
+    let mismatches = expected
+        .pixels()
+        .zip(actual.pixels())
+        .filter(|(expected, actual)| {
+            expected
+                .0
+                .iter()
+                .zip(actual.0.iter())
+                .any(|(e, a)| e.abs_diff(*a) > CHANNEL_TOLERANCE)
+        })
+        .count();
+
+    if mismatches > MAX_MISMATCHES {
+        let actual_path = path.with_extension("actual.png");
+        actual.save(&actual_path).unwrap();
+        panic!(
+            "{mismatches} pixels differ from {}, output written to {}",
+            path.display(),
+            actual_path.display()
+        );
+    }
+}
+
+#[test]
+fn rectangle_shape() {
+    let Some(renderer) = renderer() else { return };
+
+    let mut rect = RectangleShape::new(renderer.context(), (32., 16.).into());
+    rect.set_position((16., 24.).into());
+
+    let image = render(&renderer, &[rect.mesh()]);
+
+    assert_eq!(image.get_pixel(32, 32).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(4, 4).0, [0, 0, 0, 255]);
+    assert_golden("rectangle_shape", &image);
+}
+
+#[test]
+fn circle_shape() {
+    let Some(renderer) = renderer() else { return };
+
+    let mut circle = CircleShape::new(renderer.context(), 20., 32);
+    circle.set_position((32., 32.).into());
+
+    let image = render(&renderer, &[&circle.mesh]);
+
+    assert_eq!(image.get_pixel(32, 32).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(4, 4).0, [0, 0, 0, 255]);
+    assert_golden("circle_shape", &image);
+}
+
+#[test]
+fn shapes_rotate_in_radians() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    let mut rect = RectangleShape::new(ctx, (8., 8.).into());
+    let mut circle = CircleShape::new(ctx, 4., 16);
+    let mut line = LineShape::new(ctx, (0., 0.).into(), (8., 0.).into(), 1.);
+    let shapes: [&mut dyn Transformable; 3] = [&mut rect, &mut circle, &mut line];
+    for shape in shapes {
+        shape.rotate(PI);
+        shape.rotate(1.5 * PI);
+        assert!((shape.rotation() - PI / 2.).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn sprite() {
+    let Some(renderer) = renderer() else { return };
+
+    // 16x16 texture split in four colored quadrants
+    let image = image::RgbaImage::from_fn(16, 16, |x, y| match (x < 8, y < 8) {
+        (true, true) => image::Rgba([255, 0, 0, 255]),
+        (false, true) => image::Rgba([0, 255, 0, 255]),
+        (true, false) => image::Rgba([0, 0, 255, 255]),
+        (false, false) => image::Rgba([255, 255, 255, 255]),
+    });
+    let mut sprite = Sprite::new(renderer.context(), texture(&renderer, image));
+    sprite.set_position((8., 8.).into());
+
+    let image = render(&renderer, &[sprite.mesh()]);
+
+    assert_eq!(image.get_pixel(10, 10).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(21, 10).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(10, 21).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(21, 21).0, [255, 255, 255, 255]);
+    assert_golden("sprite", &image);
+}
+
+#[test]
+fn fill_color() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    // Untextured shapes are drawn with their fill color
+    let mut rect = RectangleShape::new(ctx, (24., 24.).into());
+    rect.set_position((4., 4.).into());
+    rect.set_fill_color(color::RED);
+    let mut circle = CircleShape::new(ctx, 12., 32);
+    circle.set_position((48., 16.).into());
+    circle.set_fill_color(Color::rgb(0, 128, 255));
+
+    // Textures are modulated by the sprite color
+    let mut sprite = Sprite::new(
+        ctx,
+        texture(
+            &renderer,
+            image::RgbaImage::from_pixel(24, 24, image::Rgba([255, 255, 255, 255])),
+        ),
+    );
+    sprite.set_position((4., 36.).into());
+    sprite.set_color(color::YELLOW);
+
+    let image = render(&renderer, &[rect.mesh(), &circle.mesh, sprite.mesh()]);
+
+    assert_eq!(image.get_pixel(16, 16).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(48, 16).0, [0, 128, 255, 255]);
+    assert_eq!(image.get_pixel(16, 48).0, [255, 255, 0, 255]);
+    assert_eq!(image.get_pixel(48, 48).0, [0, 0, 0, 255]);
+    assert_golden("fill_color", &image);
+}
+
+#[test]
+fn polygon_shapes() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    // Triangle set point by point
+    let mut triangle = ConvexShape::new(ctx, 3);
+    triangle.set_point(0, Vec2::new(0., 0.));
+    triangle.set_point(1, Vec2::new(24., 0.));
+    triangle.set_point(2, Vec2::new(0., 24.));
+    triangle.set_position((4., 4.).into());
+    triangle.set_fill_color(color::RED);
+
+    // Concave U with a square hole in its base, textured half blue half green
+    let mut polygon = PolygonShape::new(
+        ctx,
+        &[
+            Vec2::new(0., 0.),
+            Vec2::new(8., 0.),
+            Vec2::new(8., 16.),
+            Vec2::new(24., 16.),
+            Vec2::new(24., 0.),
+            Vec2::new(32., 0.),
+            Vec2::new(32., 32.),
+            Vec2::new(0., 32.),
+        ],
+    );
+    polygon.add_hole(&[
+        Vec2::new(12., 20.),
+        Vec2::new(20., 20.),
+        Vec2::new(20., 28.),
+        Vec2::new(12., 28.),
+    ]);
+    polygon.set_position((28., 28.).into());
+    polygon.mesh.set_texture(Some(texture(
+        &renderer,
+        image::RgbaImage::from_fn(2, 1, |x, _| {
+            image::Rgba(if x == 0 {
+                [0, 0, 255, 255]
+            } else {
+                [0, 255, 0, 255]
+            })
+        }),
+    )));
+    polygon.set_texture_rect(Rect {
+        x: 0.,
+        y: 0.,
+        width: 2.,
+        height: 1.,
+    });
+
+    let image = render(&renderer, &[&triangle.mesh, &polygon.mesh]);
+
+    assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(24, 24).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(32, 32).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(56, 32).0, [0, 255, 0, 255]);
+    // Inside the U and inside the hole
+    assert_eq!(image.get_pixel(44, 36).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(44, 52).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(30, 52).0, [0, 0, 255, 255]);
+    assert_golden("polygon_shapes", &image);
+}
+
+#[test]
+fn lines() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    let mut line = LineShape::new(ctx, Vec2::new(8., 8.), Vec2::new(56., 8.), 4.);
+    line.set_color(color::RED);
+    line.set_cap(LineCap::Round);
+
+    let mut zigzag = Polyline::new(
+        ctx,
+        &[
+            Vec2::new(8., 20.),
+            Vec2::new(20., 32.),
+            Vec2::new(32., 20.),
+            Vec2::new(44., 32.),
+        ],
+        3.,
+    );
+    zigzag.set_join(LineJoin::Round);
+    zigzag.set_color(color::GREEN);
+
+    let mut path = Path::new();
+    path.move_to(Vec2::new(8., 56.))
+        .cubic_to(
+            Vec2::new(16., 36.),
+            Vec2::new(32., 36.),
+            Vec2::new(40., 56.),
+        )
+        .arc(Vec2::new(48., 56.), 8., PI, -PI)
+        .close();
+    let mut curve = Polyline::from_path(ctx, &path, 2.);
+    curve.set_color(color::BLUE);
+
+    let image = render(&renderer, &[&line.mesh, &zigzag.mesh, &curve.mesh]);
+
+    assert_eq!(image.get_pixel(32, 8).0, [255, 0, 0, 255]);
+    // Round caps
+    assert_eq!(image.get_pixel(7, 8).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(20, 31).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(20, 26).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(24, 56).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(24, 50).0, [0, 0, 0, 255]);
+    assert_golden("lines", &image);
+}
+
+#[test]
+fn debug_draw() {
+    let Some(renderer) = renderer() else { return };
+    let mut debug = DebugDraw::new(renderer.context());
+
+    // Primitives of a previous frame are cleared once drawn
+    debug.fill_rect(
+        Rect {
+            x: 0.,
+            y: 0.,
+            width: 64.,
+            height: 64.,
+        },
+        color::RED,
+    );
+    render_debug(&renderer, &mut debug);
+
+    debug.set_thickness(2.);
+    debug.line(Vec2::new(4., 4.), Vec2::new(60., 4.), color::RED);
+    debug.rect(
+        Rect {
+            x: 4.,
+            y: 10.,
+            width: 20.,
+            height: 20.,
+        },
+        color::GREEN,
+    );
+    debug.circle(Vec2::new(44., 20.), 10., color::BLUE);
+    debug.set_text_scale(1.);
+    debug.text(Vec2::new(4., 40.), "Hi!\n42");
+    let image = render_debug(&renderer, &mut debug);
+
+    assert_eq!(debug.stats().draw_calls, 1);
+    assert_eq!(image.get_pixel(32, 4).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(4, 20).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(14, 20).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(54, 20).0, [0, 0, 255, 255]);
+    // Left column of the H
+    assert_eq!(image.get_pixel(4, 44).0, [255, 255, 255, 255]);
+    assert_golden("debug_draw", &image);
+
+    // Drawn again in the same frame, e.g. in another viewport
+    assert_eq!(render_debug(&renderer, &mut debug), image);
+
+    debug.clear();
+    render_debug(&renderer, &mut debug);
+    assert_eq!(debug.stats().triangles, 0);
+}
+
+fn render_debug(renderer: &Renderer, debug: &mut DebugDraw) -> image::RgbaImage {
+    let mut frame = renderer.begin_frame().unwrap();
+    {
+        let mut render_pass = renderer.begin_pass(&mut frame, Some(wgpu::Color::BLACK));
+        debug.draw(&mut render_pass);
+    }
+    renderer.end_frame(frame);
+
+    renderer.capture().unwrap()
+}
+
+#[test]
+fn outline() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    // Textured rectangle, its outline is drawn untextured
+    let mut rect = RectangleShape::new(ctx, (20., 20.).into());
+    rect.set_position((8., 8.).into());
+    rect.mesh_mut().set_texture(Some(texture(
+        &renderer,
+        image::RgbaImage::from_pixel(20, 20, image::Rgba([0, 0, 255, 255])),
+    )));
+    rect.set_outline_color(color::RED);
+    rect.set_outline_thickness(4.);
+
+    let mut circle = CircleShape::new(ctx, 10., 32);
+    circle.set_position((44., 44.).into());
+    circle.set_fill_color(color::GREEN);
+    circle.set_outline_color(color::WHITE);
+    circle.set_outline_thickness(-3.);
+
+    let image = render(&renderer, &[rect.mesh(), &circle.mesh]);
+
+    assert_eq!(image.get_pixel(18, 18).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(6, 18).0, [255, 0, 0, 255]);
+    // Miter join covers the corner
+    assert_eq!(image.get_pixel(5, 5).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(44, 44).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(53, 44).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(56, 44).0, [0, 0, 0, 255]);
+    assert_golden("outline", &image);
+}
+
+#[test]
+fn sprite_batch() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    let red = texture(
+        &renderer,
+        image::RgbaImage::from_pixel(8, 8, image::Rgba([255, 0, 0, 255])),
+    );
+    let mut sprites = Vec::new();
+    for i in 0..4 {
+        let mut sprite = Sprite::new(ctx, red.clone());
+        sprite.set_position((4. + 12. * i as f32, 4.).into());
+        sprites.push(sprite);
+    }
+    let mut rect = RectangleShape::new(ctx, (56., 8.).into());
+    rect.set_position((4., 40.).into());
+
+    let mut batch = SpriteBatch::new(ctx);
+    batch.begin();
+    for sprite in &sprites {
+        batch.draw_sprite(sprite);
+    }
+    batch.draw_rectangle(&rect);
+    batch.end();
+
+    let stats = batch.stats();
+    assert_eq!(stats.draw_calls, 2);
+    assert_eq!(stats.vertices, 20);
+    assert_eq!(stats.triangles, 10);
+
+    let mut frame = renderer.begin_frame().unwrap();
+    {
+        let mut render_pass = renderer.begin_pass(&mut frame, Some(wgpu::Color::BLACK));
+        render_pass.draw_batch(&batch);
+    }
+    renderer.end_frame(frame);
+    let image = renderer.capture().unwrap();
+
+    assert_eq!(image.get_pixel(41, 8).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(32, 44).0, [255, 255, 255, 255]);
+    assert_golden("sprite_batch", &image);
+}
+
+#[test]
+fn instanced_sprites() {
+    let Some(renderer) = renderer() else { return };
+
+    let image = image::RgbaImage::from_fn(16, 16, |x, y| match (x < 8, y < 8) {
+        (true, true) => image::Rgba([255, 0, 0, 255]),
+        (false, true) => image::Rgba([0, 255, 0, 255]),
+        (true, false) => image::Rgba([0, 0, 255, 255]),
+        (false, false) => image::Rgba([255, 255, 255, 255]),
+    });
+    let mut sprites = InstancedSprites::new(renderer.context(), texture(&renderer, image));
+    let quadrant = |x, y| Rect {
+        x,
+        y,
+        width: 8.,
+        height: 8.,
+    };
+    // One instance per quadrant on the diagonal, the last one scaled up
+    sprites.push(SpriteInstance::new((8., 8.).into(), quadrant(0., 0.)));
+    sprites.push(SpriteInstance::new((24., 24.).into(), quadrant(8., 0.)));
+    sprites.push(SpriteInstance::new((40., 40.).into(), quadrant(0., 8.)));
+    sprites.push(SpriteInstance {
+        scale: [2., 2.],
+        rotation: std::f32::consts::FRAC_PI_4,
+        ..SpriteInstance::new((52., 12.).into(), quadrant(8., 8.))
+    });
+    sprites.upload();
+
+    let mut frame = renderer.begin_frame().unwrap();
+    {
+        let mut render_pass = renderer.begin_pass(&mut frame, Some(wgpu::Color::BLACK));
+        renderer.draw_instanced(&mut render_pass, &sprites);
+    }
+    renderer.end_frame(frame);
+    let image = renderer.capture().unwrap();
+
+    assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(24, 24).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(40, 40).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(52, 12).0, [255, 255, 255, 255]);
+    assert_golden("instanced_sprites", &image);
+}
+
+#[test]
+fn render_texture() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    // Blue 16x16 texture with a red square in its top-left quarter
+    let target = RenderTexture::new(ctx, 16, 16);
+    let mut rect = RectangleShape::new(ctx, (8., 8.).into());
+    rect.set_fill_color(color::RED);
+
+    let mut sprite = Sprite::new(ctx, target.texture().clone());
+    sprite.set_position((8., 8.).into());
+    let mut scaled = Sprite::new(ctx, target.texture().clone());
+    scaled.set_position((32., 24.).into());
+    scaled.set_scale(2.);
+
+    let mut frame = renderer.begin_frame().unwrap();
+    {
+        let blue = wgpu::Color {
+            r: 0.,
+            g: 0.,
+            b: 1.,
+            a: 1.,
+        };
+        let mut render_pass = renderer.begin_texture_pass(&mut frame, &target, Some(blue));
+        render_pass.draw_mesh(rect.mesh());
+    }
+    {
+        let mut render_pass = renderer.begin_pass(&mut frame, Some(wgpu::Color::BLACK));
+        render_pass.draw_mesh(sprite.mesh());
+        render_pass.draw_mesh(scaled.mesh());
+    }
+    renderer.end_frame(frame);
+    let image = renderer.capture().unwrap();
+
+    assert_eq!(target.size(), (16, 16));
+    assert_eq!(image.get_pixel(4, 4).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(10, 10).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(20, 20).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(36, 28).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(56, 48).0, [0, 0, 255, 255]);
+    assert_golden("render_texture", &image);
+}
+
+#[test]
+fn post_process() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    let mut rect = RectangleShape::new(ctx, (32., 32.).into());
+    rect.set_position((16., 16.).into());
+    rect.set_fill_color(color::RED);
+
+    let invert = Effect::new(
+        ctx,
+        "invert",
+        "@group(1) @binding(1)
+        var<uniform> amount: vec4<f32>;
+
+        @fragment
+        fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+            let color = textureSample(input_texture, input_sampler, in.uv);
+            return vec4<f32>(mix(color.rgb, 1.0 - color.rgb, amount.x), 1.0);
+        }",
+    )
+    .unwrap();
+    let mut post = PostProcessStack::new(ctx, SIZE, SIZE);
+    post.push(Effect::pixelate(ctx, 8.));
+    post.push(invert);
+    post.effect_mut("invert")
+        .unwrap()
+        .set_params([1f32, 0., 0., 0.]);
+
+    let render = |post: &PostProcessStack| {
+        let mut frame = renderer.begin_frame().unwrap();
+        {
+            let mut render_pass =
+                post.begin_scene_pass(&renderer, &mut frame, Some(wgpu::Color::BLACK));
+            render_pass.draw_mesh(rect.mesh());
+        }
+        post.apply(&mut frame);
+        renderer.end_frame(frame);
+
+        renderer.capture().unwrap()
+    };
+
+    let image = render(&post);
+    assert_eq!(image.get_pixel(4, 4).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(32, 32).0, [0, 255, 255, 255]);
+    assert_golden("post_process", &image);
+
+    // Without enabled effect the scene is copied as is
+    for effect in post.effects_mut() {
+        effect.set_enabled(false);
+    }
+    let image = render(&post);
+    assert_eq!(image.get_pixel(4, 4).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(32, 32).0, [255, 0, 0, 255]);
+
+    assert!(Effect::new(ctx, "broken", "fn fs_main() {").is_err());
+}
+
+#[test]
+fn builtin_effects() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    // Identity LUT of 4 slices of 4x4 pixels
+    let lut = texture(
+        &renderer,
+        image::RgbaImage::from_fn(16, 4, |x, y| {
+            let level = |i: u32| (i * 255 / 3) as u8;
+            image::Rgba([level(x % 4), level(y), level(x / 4), 255])
+        }),
+    );
+    let mut post = PostProcessStack::new(ctx, SIZE, SIZE);
+    post.push(Effect::bloom(ctx, 0.8, 1., 4.));
+    post.push(Effect::blur(ctx, 2.));
+    post.push(Effect::crt(ctx, 0.3, 2., 0.1));
+    post.push(Effect::color_grading(ctx, lut, 1.));
+    post.push(Effect::vignette(ctx, 0.5, 0.5, 0.5));
+    post.update(0.5);
+
+    let mut rect = RectangleShape::new(ctx, (32., 32.).into());
+    rect.set_position((16., 16.).into());
+    rect.set_fill_color(color::RED);
+
+    let mut frame = renderer.begin_frame().unwrap();
+    {
+        let mut render_pass =
+            post.begin_scene_pass(&renderer, &mut frame, Some(wgpu::Color::BLACK));
+        render_pass.draw_mesh(rect.mesh());
+    }
+    post.apply(&mut frame);
+    renderer.end_frame(frame);
+    let image = renderer.capture().unwrap();
+
+    // The center stays mostly red, the corners are bent off screen
+    let [r, g, b, _] = image.get_pixel(32, 32).0;
+    assert!(r > 200 && g < 50 && b < 50, "{r} {g} {b}");
+    assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn identity_color_grading() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    // Identity LUT of 16 slices of 16x16 pixels
+    let lut = image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(256, 16, |x, y| {
+        let level = |i: u32| (i * 255 / 15) as u8;
+        image::Rgba([level(x % 16), level(y), level(x / 16), 255])
+    }));
+
+    for srgb in [false, true] {
+        let options = TextureOptions {
+            srgb,
+            ..Default::default()
+        };
+        let lut = Rc::new(Texture::from_image(ctx, &lut, None, options).unwrap());
+        let mut post = PostProcessStack::new(ctx, SIZE, SIZE);
+        post.push(Effect::color_grading(ctx, lut, 1.));
+
+        let mut rect = RectangleShape::new(ctx, (SIZE as f32, SIZE as f32).into());
+        rect.set_fill_color(Color::rgb(128, 64, 200));
+
+        let mut frame = renderer.begin_frame().unwrap();
+        {
+            let mut render_pass =
+                post.begin_scene_pass(&renderer, &mut frame, Some(wgpu::Color::BLACK));
+            render_pass.draw_mesh(rect.mesh());
+        }
+        post.apply(&mut frame);
+        renderer.end_frame(frame);
+        let image = renderer.capture().unwrap();
+
+        let [r, g, b, _] = image.get_pixel(32, 32).0;
+        for (channel, expected) in [(r, 128), (g, 64), (b, 200)] {
+            assert!(
+                channel.abs_diff(expected) <= 2,
+                "srgb LUT: {srgb}, got {r} {g} {b}"
+            );
+        }
+    }
+}
+
+#[test]
+fn material() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    // Tints the texture and hides the parts where the mask is transparent
+    let shader = Shader::new(
+        ctx,
+        "masked tint",
+        "@group(5) @binding(0)
+        var<uniform> tint: vec4<f32>;
+        @group(5) @binding(1)
+        var mask: texture_2d<f32>;
+        @group(5) @binding(2)
+        var mask_sampler: sampler;
+
+        @fragment
+        fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+            let color = textureSample(t_diffuse, s_diffuse, in.tex_coords) * in.color;
+            return color * tint * textureSample(mask, mask_sampler, in.tex_coords).a;
+        }",
+        1,
+    )
+    .unwrap();
+    let shader = Rc::new(shader);
+
+    // Left half of the mask is opaque
+    let mask = texture(
+        &renderer,
+        image::RgbaImage::from_fn(16, 16, |x, _| {
+            image::Rgba([0, 0, 0, if x < 8 { 255 } else { 0 }])
+        }),
+    );
+    let mut tinted = Material::new(ctx, shader.clone(), [1f32, 0., 1., 1.]);
+    tinted.set_texture(0, mask);
+    let pipelines = ctx.render_pipelines.lock().unwrap().len();
+
+    let white = texture(
+        &renderer,
+        image::RgbaImage::from_pixel(16, 16, image::Rgba([255, 255, 255, 255])),
+    );
+    let mut sprite = Sprite::new(ctx, white.clone());
+    sprite.set_position((8., 8.).into());
+    sprite.mesh_mut().set_material(Some(Rc::new(tinted)));
+
+    // Same shader, same pipeline, and no mask so the whole sprite is drawn
+    let untextured = Material::new(ctx, shader, [0f32; 4]);
+    untextured.set_params([0f32, 1., 0., 1.]);
+    let mut masked = Sprite::new(ctx, white.clone());
+    masked.set_position((40., 8.).into());
+    masked.mesh_mut().set_material(Some(Rc::new(untextured)));
+    assert_eq!(ctx.render_pipelines.lock().unwrap().len(), pipelines);
+
+    // Drawn after a material, so with the standard pipeline again
+    let mut standard = Sprite::new(ctx, white);
+    standard.set_position((8., 40.).into());
+
+    let image = render(&renderer, &[sprite.mesh(), masked.mesh(), standard.mesh()]);
+
+    assert_eq!(image.get_pixel(10, 12).0, [255, 0, 255, 255]);
+    assert_eq!(image.get_pixel(20, 12).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(44, 12).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(16, 48).0, [255, 255, 255, 255]);
+    assert_golden("material", &image);
+
+    assert!(Shader::new(ctx, "broken", "fn fs_main() -> f32 {}", 0).is_err());
+    // Valid WGSL reading a binding missing from the layout only fails when
+    // the pipeline is created, and still fails when compiled again
+    let unbound = "@group(7) @binding(0) var<uniform> tint: vec4<f32>;
+        @fragment
+        fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> { return tint; }";
+    assert!(Shader::new(ctx, "unbound", unbound, 0).is_err());
+    assert!(Shader::new(ctx, "unbound", unbound, 0).is_err());
+}
+
+#[test]
+fn blend_modes() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+
+    let mut background = RectangleShape::new(ctx, (SIZE as f32, SIZE as f32).into());
+    background.set_fill_color(color::BLUE);
+
+    let square = |x: f32, y: f32, color: Color, blend_mode: BlendMode| {
+        let mut square = RectangleShape::new(ctx, (12., 12.).into());
+        square.set_position((x, y).into());
+        square.set_fill_color(color);
+        square.mesh_mut().set_blend_mode(blend_mode);
+        square
+    };
+    let squares = [
+        square(2., 2., color::RED, BlendMode::Additive),
+        square(17., 2., color::RED, BlendMode::Multiply),
+        square(32., 2., color::RED, BlendMode::Screen),
+        // Fully transparent but not premultiplied, so added to the target
+        square(
+            47.,
+            2.,
+            Color::rgba(255, 0, 0, 0),
+            BlendMode::PremultipliedAlpha,
+        ),
+        square(2., 20., Color::rgba(255, 0, 0, 128), BlendMode::Opaque),
+        // Back to alpha blending
+        square(47., 20., Color::rgba(255, 0, 0, 0), BlendMode::Alpha),
+    ];
+
+    // The blend mode of a material replaces the one of the mesh
+    let shader = Shader::new(
+        ctx,
+        "vertex color",
+        "@fragment
+        fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+            return in.color;
+        }",
+        0,
+    )
+    .unwrap();
+    let mut material = Material::new(ctx, Rc::new(shader), [0f32; 4]);
+    material.set_blend_mode(BlendMode::Additive);
+    let mut with_material = square(17., 20., color::RED, BlendMode::Opaque);
+    with_material
+        .mesh_mut()
+        .set_material(Some(Rc::new(material)));
+
+    let mut meshes = vec![background.mesh()];
+    meshes.extend(squares.iter().map(|square| square.mesh()));
+    meshes.push(with_material.mesh());
+    let image = render(&renderer, &meshes);
+
+    assert_eq!(image.get_pixel(8, 8).0, [255, 0, 255, 255]);
+    assert_eq!(image.get_pixel(23, 8).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(38, 8).0, [255, 0, 255, 255]);
+    assert_eq!(image.get_pixel(53, 8).0, [255, 0, 255, 255]);
+    assert_eq!(image.get_pixel(8, 26).0, [255, 0, 0, 128]);
+    assert_eq!(image.get_pixel(23, 26).0, [255, 0, 255, 255]);
+    assert_eq!(image.get_pixel(53, 26).0, [0, 0, 255, 255]);
+    assert_golden("blend_modes", &image);
+}
+
+#[test]
+fn texture_options() {
+    let Some(renderer) = renderer() else { return };
+    let ctx = renderer.context();
+    let upload = |image: image::RgbaImage, options: TextureOptions| {
+        let image = image::DynamicImage::ImageRgba8(image);
+        Texture::from_image(ctx, &image, None, options).unwrap()
+    };
+
+    // 2x2 texture scaled 12 times, blurred across the whole sprite unless
+    // filtered as pixel art
+    let quadrants = image::RgbaImage::from_fn(2, 2, |x, y| match (x, y) {
+        (0, 0) => image::Rgba([255, 0, 0, 255]),
+        (1, 0) => image::Rgba([0, 255, 0, 255]),
+        (0, 1) => image::Rgba([0, 0, 255, 255]),
+        _ => image::Rgba([255, 255, 255, 255]),
+    });
+    let mut pixel_art = Sprite::new(
+        ctx,
+        Rc::new(upload(quadrants.clone(), TextureOptions::pixel_art())),
+    );
+    pixel_art.set_position((2., 2.).into());
+    pixel_art.set_scale(12.);
+
+    // Made sharp after creation, through the texture shared with the sprite
+    let texture = Rc::new(upload(quadrants, TextureOptions::default()));
+    let mut sharpened = Sprite::new(ctx, texture.clone());
+    assert!(!texture.is_smooth());
+    texture.set_smooth(ctx, true);
+    assert!(texture.is_smooth());
+    texture.set_smooth(ctx, false);
+    assert!(!texture.is_smooth());
+    sharpened.set_position((2., 34.).into());
+    sharpened.set_scale(12.);
+
+    // Repeated twice horizontally
+    let texture = upload(
+        image::RgbaImage::from_fn(8, 8, |x, _| {
+            if x < 4 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        }),
+        TextureOptions::default(),
+    );
+    texture.set_repeated(ctx, true);
+    let mut repeated = Sprite::new(ctx, Rc::new(texture));
+    repeated.set_texture_rect(Rect {
+        x: 0.,
+        y: 0.,
+        width: 16.,
+        height: 8.,
+    });
+    repeated.set_position((32., 2.).into());
+
+    // 1px checkerboard drawn 8 times smaller, averaged by mipmaps
+    let checkerboard = image::RgbaImage::from_fn(16, 16, |x, y| {
+        if (x + y) % 2 == 0 {
+            image::Rgba([255, 0, 0, 255])
+        } else {
+            image::Rgba([0, 0, 255, 255])
+        }
+    });
+    let mut mipmapped = Sprite::new(ctx, Rc::new(upload(checkerboard, TextureOptions::smooth())));
+    mipmapped.set_position((32., 16.).into());
+    mipmapped.set_scale(0.125);
+
+    // Gray stored as is instead of as an sRGB color
+    let mut linear = Sprite::new(
+        ctx,
+        Rc::new(upload(
+            image::RgbaImage::from_pixel(8, 8, image::Rgba([128, 128, 128, 255])),
+            TextureOptions {
+                srgb: false,
+                ..Default::default()
+            },
+        )),
+    );
+    linear.set_position((48., 16.).into());
+
+    let image = render(
+        &renderer,
+        &[
+            pixel_art.mesh(),
+            sharpened.mesh(),
+            repeated.mesh(),
+            mipmapped.mesh(),
+            linear.mesh(),
+        ],
+    );
+
+    // Last pixels before the edges between the quadrants
+    assert_eq!(image.get_pixel(13, 13).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(14, 25).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(13, 45).0, [255, 0, 0, 255]);
+    for x in [33, 41] {
+        assert_eq!(image.get_pixel(x, 4).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(x + 5, 4).0, [0, 0, 255, 255]);
+    }
+    // Averaged in linear space, as bright as the blend of both colors
+    let [r, g, b, _] = image.get_pixel(32, 16).0;
+    assert!(
+        r.abs_diff(188) <= 4 && g == 0 && b.abs_diff(188) <= 4,
+        "{r} {g} {b}"
+    );
+    let [r, ..] = image.get_pixel(52, 20).0;
+    assert!(r.abs_diff(188) <= 2, "{r}");
+    assert_golden("texture_options", &image);
+}
+
+#[test]
+fn texture_updates() {
+    let Some(renderer) = renderer() else {
+        return;
+    };
+    let ctx = renderer.context();
+
+    // 65 pixels wide so rows need padding when read back
+    let canvas = Texture::create(ctx, 65, 32, wgpu::TextureFormat::Rgba8UnormSrgb).unwrap();
+    canvas
+        .update(
+            ctx,
+            &image::RgbaImage::from_pixel(65, 32, image::Rgba([0, 0, 255, 255])),
+            (0, 0),
+        )
+        .unwrap();
+    canvas
+        .update(
+            ctx,
+            &image::RgbaImage::from_pixel(16, 8, image::Rgba([255, 0, 0, 255])),
+            (8, 4),
+        )
+        .unwrap();
+    assert!(canvas
+        .update(ctx, &image::RgbaImage::new(16, 8), (50, 0))
+        .is_err());
+    assert!(canvas
+        .update(ctx, &image::RgbaImage::new(16, 8), (u32::MAX - 8, 0))
+        .is_err());
+
+    let pixels = pollster::block_on(canvas.read_pixels(ctx)).unwrap();
+    assert_eq!(pixels.dimensions(), (65, 32));
+    assert_eq!(pixels.get_pixel(8, 4).0, [255, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(23, 11).0, [255, 0, 0, 255]);
+    assert_eq!(pixels.get_pixel(24, 11).0, [0, 0, 255, 255]);
+    assert_eq!(pixels.get_pixel(64, 31).0, [0, 0, 255, 255]);
+    assert_eq!(canvas.read_pixels_blocking(ctx).unwrap(), pixels);
+
+    // BGRA textures are read and written as RGBA too
+    let bgra = Texture::create(ctx, 4, 4, wgpu::TextureFormat::Bgra8Unorm).unwrap();
+    bgra.update(
+        ctx,
+        &image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 128, 0, 255])),
+        (1, 1),
+    )
+    .unwrap();
+    let pixels = pollster::block_on(bgra.read_pixels(ctx)).unwrap();
+    assert_eq!(pixels.get_pixel(0, 0).0, [0, 0, 0, 0]);
+    assert_eq!(pixels.get_pixel(2, 2).0, [255, 128, 0, 255]);
+
+    let mut sprite = Sprite::new(ctx, Rc::new(canvas));
+    sprite.set_position((0., 16.).into());
+    let image = render(&renderer, &[sprite.mesh()]);
+
+    assert_eq!(image.get_pixel(12, 24).0, [255, 0, 0, 255]);
+    assert_golden("texture_updates", &image);
+}
+
+#[test]
+fn texture_errors() {
+    let Some(renderer) = renderer() else {
+        return;
+    };
+    let ctx = renderer.context();
+
+    let err = Texture::from_path(
+        ctx,
+        std::path::Path::new("missing.png"),
+        None,
+        TextureOptions::default(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, Error::Io { .. }));
+
+    let err = Texture::from_bytes(ctx, b"not an image", "garbage", TextureOptions::default())
+        .unwrap_err();
+    assert!(matches!(err, Error::UnsupportedFormat(_)));
+}
+
+fn font() -> Rc<Font> {
+    let bytes = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../assets/fonts/DejaVuSans.ttf"
+    ));
+
+    Rc::new(Font::from_bytes(bytes.to_vec()).unwrap())
+}
+
+#[test]
+fn text() {
+    let Some(renderer) = renderer() else {
+        return;
+    };
+    let ctx = renderer.context();
+    let font = font();
+
+    let mut title = Text::new(ctx, font.clone(), "AVa", 20.);
+    title.set_color(color::YELLOW);
+    title.set_underlined(true);
+    title.set_position((2., 0.).into());
+
+    let mut wrapped = Text::new(ctx, font.clone(), "one two", 12.);
+    wrapped.set_max_width(Some(60.));
+    wrapped.set_alignment(TextAlignment::Right);
+    wrapped.set_line_spacing(0.8);
+    let single_line = wrapped.bounds();
+    wrapped.set_max_width(Some(24.));
+    assert!(wrapped.bounds().height > single_line.height);
+    assert!(wrapped.bounds().width <= 24.);
+    wrapped.set_position((38., 26.).into());
+
+    let mut styled = Text::new(ctx, font.clone(), "Ab", 14.);
+    styled.set_bold(true);
+    styled.set_italic(true);
+    styled.set_strike_through(true);
+    styled.set_color(color::CYAN);
+    styled.set_position((2., 44.).into());
+
+    assert!([&title, &wrapped, &styled]
+        .iter()
+        .all(|t| t.error().is_none()));
+
+    let image = render(&renderer, &[title.mesh(), wrapped.mesh(), styled.mesh()]);
+    assert_golden("text", &image);
+
+    // Filling the glyph cache replaces its texture, texts laid out before
+    // keep the previous one
+    let texture = font.texture().unwrap();
+    let alphabet: String = ('!'..='~').collect();
+    let _large = Text::new(ctx, font.clone(), alphabet, 60.);
+    assert!(!Rc::ptr_eq(&texture, &font.texture().unwrap()));
+    assert!(Rc::ptr_eq(&texture, title.mesh().texture().unwrap()));
+    assert_eq!(
+        render(&renderer, &[title.mesh(), wrapped.mesh(), styled.mesh()]),
+        image
+    );
+}
+
+#[cfg(feature = "ui")]
+#[test]
+fn label() {
+    use rusty_core::ui::label::{Appearence, Label, Style};
+
+    let Some(renderer) = renderer() else {
+        return;
+    };
+    let ctx = renderer.context();
+
+    let style = Style {
+        font_size: 24.,
+        appearence: Appearence::Underlined,
+        ..Default::default()
+    };
+    let mut label = Label::new(ctx, font(), "Hi", style);
+    label.set_position((4., 4.).into());
+    assert!(label.text_mut().error().is_none());
+    let bounds = label.bounds();
+    assert!(bounds.width > 20. && bounds.height > 24.);
+
+    let image = render(&renderer, &[label.mesh()]);
+    let lit = image.pixels().filter(|pixel| pixel.0[0] > 128).count();
+    assert!(lit > 50);
+
+    label.set_text("");
+    assert_eq!(label.bounds().width, 0.);
+    assert_eq!(label.text(), "");
+}
+
+#[cfg(feature = "dds")]
+#[test]
+fn compressed_texture() {
+    let Some(renderer) = renderer() else {
+        return;
+    };
+    let ctx = renderer.context();
+
+    // 8x8 DXT1 texture made of 4x4 blocks of a single color, as RGB565
+    let mut dds = vec![0; 128];
+    dds[..4].copy_from_slice(b"DDS ");
+    for (offset, value) in [(4, 124), (12, 8), (16, 8), (28, 1), (76, 32)] {
+        dds[offset..offset + 4].copy_from_slice(&u32::to_le_bytes(value));
+    }
+    dds[84..88].copy_from_slice(b"DXT1");
+    for color in [0xF800u16, 0x07E0, 0x001F, 0xFFFF] {
+        dds.extend_from_slice(&color.to_le_bytes());
+        dds.extend_from_slice(&[0; 6]);
+    }
+
+    let texture = match Texture::from_bytes(ctx, &dds, "dxt1", TextureOptions::pixel_art()) {
+        Ok(texture) => texture,
+        Err(err) => {
+            assert!(matches!(err, Error::UnsupportedByAdapter(_)));
+            eprintln!("skipping compressed texture test: {err}");
+            return;
+        }
+    };
+    assert_eq!(
+        texture.texture.format(),
+        wgpu::TextureFormat::Bc1RgbaUnormSrgb
+    );
+
+    let mut sprite = Sprite::new(ctx, Rc::new(texture));
+    sprite.set_scale(4.);
+    let image = render(&renderer, &[sprite.mesh()]);
+
+    assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(24, 8).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(8, 24).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(24, 24).0, [255, 255, 255, 255]);
+    assert_golden("compressed_texture", &image);
+}
@@ -1,17 +1,28 @@
-use anyhow::Result;
-use rusty_core::graphics::texture::Texture;
+use rusty_core::{
+    graphics::{
+        texture::{Texture, TextureOptions},
+        AtlasRegion, TextureAtlas,
+    },
+    Context, Ctx, Result,
+};
 use std::{collections::HashMap, rc::Rc};
 
+use crate::sprite_sheet::{SpriteSheet, SpriteSheetDescriptor};
+
 #[derive(Default)]
 pub struct AssetManager {
     // textures: HashMap<String, Texture>,
     textures: HashMap<String, Rc<Texture>>,
+    atlases: HashMap<String, Rc<TextureAtlas>>,
+    sprite_sheets: HashMap<String, Rc<SpriteSheet>>,
 }
 
 impl AssetManager {
     pub fn new() -> Self {
         Self {
             textures: HashMap::new(),
+            atlases: HashMap::new(),
+            sprite_sheets: HashMap::new(),
         }
     }
 
@@ -22,15 +33,17 @@ impl AssetManager {
     //     self.textures.get(name)
     // }
 
-    pub fn load_texture(&mut self, path: &std::path::Path) -> Result<Rc<Texture>> {
-        let texture = Texture::from_path(path, None)?;
-        let name = path.file_name().unwrap().to_string_lossy().to_string();
-
-        self.textures.insert(name.clone(), Rc::new(texture));
+    pub fn load_texture(
+        &mut self,
+        ctx: &Context,
+        path: &std::path::Path,
+        options: TextureOptions,
+    ) -> Result<Rc<Texture>> {
+        let texture = Rc::new(Texture::from_path(ctx, path, None, options)?);
 
-        let texture = self.get_texture(&name);
+        self.textures.insert(asset_name(path), texture.clone());
 
-        Ok(texture.unwrap())
+        Ok(texture)
     }
     // pub fn load_texture(&mut self, ctx: Ctx, path: &std::path::Path) -> Result<Option<&Texture>> {
     //     let texture = Texture::from_path(ctx, path, None)?;
@@ -39,4 +52,67 @@ impl AssetManager {
 
     //     Ok(self.get_texture(&name))
     // }
+
+    /// Load an atlas description and its pages, see [`TextureAtlas::load`].
+    pub fn load_atlas(
+        &mut self,
+        ctx: &Ctx,
+        path: &std::path::Path,
+        options: TextureOptions,
+    ) -> Result<Rc<TextureAtlas>> {
+        let atlas = Rc::new(TextureAtlas::load(ctx, path, options)?);
+
+        self.add_atlas(&asset_name(path), atlas.clone());
+
+        Ok(atlas)
+    }
+
+    /// Register an atlas packed at runtime.
+    pub fn add_atlas(&mut self, name: &str, atlas: Rc<TextureAtlas>) {
+        self.atlases.insert(name.to_owned(), atlas);
+    }
+
+    pub fn get_atlas(&self, name: &str) -> Option<Rc<TextureAtlas>> {
+        self.atlases.get(name).cloned()
+    }
+
+    /// Region named `name` in any of the loaded atlases.
+    pub fn get_region(&self, name: &str) -> Option<AtlasRegion> {
+        self.atlases
+            .values()
+            .find_map(|atlas| atlas.region(name))
+            .cloned()
+    }
+
+    /// Load a sprite sheet descriptor, and its texture with `options` if it
+    /// is not loaded yet.
+    pub fn load_sprite_sheet(
+        &mut self,
+        ctx: &Context,
+        path: &std::path::Path,
+        options: TextureOptions,
+    ) -> Result<Rc<SpriteSheet>> {
+        let descriptor = SpriteSheetDescriptor::load(path)?;
+        let texture = match self.get_texture(&descriptor.texture) {
+            Some(texture) => texture,
+            None => self.load_texture(ctx, &path.with_file_name(&descriptor.texture), options)?,
+        };
+        let sheet = Rc::new(SpriteSheet::from_descriptor(texture, &descriptor)?);
+
+        self.sprite_sheets.insert(asset_name(path), sheet.clone());
+
+        Ok(sheet)
+    }
+
+    pub fn get_sprite_sheet(&self, name: &str) -> Option<Rc<SpriteSheet>> {
+        self.sprite_sheets.get(name).cloned()
+    }
+}
+
+/// Name under which an asset loaded from `path` is stored, its file name.
+fn asset_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
 }
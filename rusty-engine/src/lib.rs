@@ -2,3 +2,5 @@ pub use rusty_core::winit;
 
 pub mod animation;
 pub mod asset_manager;
+pub mod scene_graph;
+pub mod sprite_sheet;
@@ -0,0 +1,404 @@
+use rusty_core::{
+    glam::{Mat4, Vec2},
+    graphics::{
+        shape::{CircleShape, RectangleShape},
+        sprite::Sprite,
+        Drawable, Mesh, Transform,
+    },
+};
+
+/// Handle on a node of a [`SceneGraph`].
+///
+/// Slots of removed nodes are reused, the generation tells the nodes which
+/// took the same slot apart so that handles on removed nodes stay invalid.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+/// Drawable attached to a node, positioned relative to it.
+pub enum Payload {
+    None,
+    Sprite(Sprite),
+    Rectangle(RectangleShape),
+    Circle(CircleShape),
+}
+
+impl Payload {
+    fn mesh(&self) -> Option<&Mesh> {
+        match self {
+            Payload::None => None,
+            Payload::Sprite(sprite) => Some(sprite.mesh()),
+            Payload::Rectangle(rectangle) => Some(rectangle.mesh()),
+            Payload::Circle(circle) => Some(&circle.mesh),
+        }
+    }
+
+    fn mesh_mut(&mut self) -> Option<&mut Mesh> {
+        match self {
+            Payload::None => None,
+            Payload::Sprite(sprite) => Some(sprite.mesh_mut()),
+            Payload::Rectangle(rectangle) => Some(rectangle.mesh_mut()),
+            Payload::Circle(circle) => Some(&mut circle.mesh),
+        }
+    }
+}
+
+/// Node stored at an index of the graph, or the generation the next node
+/// stored there gets.
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+struct Node {
+    local: Transform,
+    world: Mat4,
+    dirty: bool,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    payload: Payload,
+}
+
+/// Hierarchy of nodes, each one transformed relative to its parent.
+///
+/// World matrices are cached and only recomputed by [`SceneGraph::update`]
+/// for nodes whose transform, or the transform of an ancestor, changed.
+///
+/// ```ignore
+/// let player = graph.add(Payload::Sprite(player_sprite));
+/// let weapon = graph.add_child(player, Payload::Sprite(weapon_sprite));
+/// graph.transform_mut(weapon).position = (12., 4.).into();
+///
+/// graph.transform_mut(player).position += velocity * dt;
+/// graph.update();
+/// graph.draw(&mut render_pass);
+/// ```
+#[derive(Default)]
+pub struct SceneGraph {
+    nodes: Vec<Slot>,
+    free: Vec<usize>,
+    roots: Vec<NodeId>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a node at the root of the graph.
+    pub fn add(&mut self, payload: Payload) -> NodeId {
+        let node = Node {
+            local: Transform::default(),
+            world: Mat4::IDENTITY,
+            dirty: true,
+            parent: None,
+            children: Vec::new(),
+            payload,
+        };
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.nodes.push(Slot {
+                    generation: 0,
+                    node: None,
+                });
+                self.nodes.len() - 1
+            }
+        };
+        let slot = &mut self.nodes[index];
+        slot.node = Some(node);
+        let id = NodeId {
+            index,
+            generation: slot.generation,
+        };
+        self.roots.push(id);
+
+        id
+    }
+
+    /// Add a node transformed relative to `parent`.
+    pub fn add_child(&mut self, parent: NodeId, payload: Payload) -> NodeId {
+        let id = self.add(payload);
+        self.set_parent(id, Some(parent));
+
+        id
+    }
+
+    /// Move `id` under `parent`, or at the root of the graph when `None`.
+    ///
+    /// The local transform is kept, so the node moves with its new parent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parent` is `id` or one of its descendants.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        if let Some(parent) = parent {
+            assert!(
+                !self.is_ancestor(id, parent),
+                "a node cannot be attached to itself or to one of its descendants"
+            );
+        }
+
+        match self.node(id).parent {
+            Some(old) => self.node_mut(old).children.retain(|child| *child != id),
+            None => self.roots.retain(|root| *root != id),
+        }
+        match parent {
+            Some(parent) => self.node_mut(parent).children.push(id),
+            None => self.roots.push(id),
+        }
+
+        let node = self.node_mut(id);
+        node.parent = parent;
+        node.dirty = true;
+    }
+
+    /// Remove a node and all its descendants, returning the payload of `id`.
+    pub fn remove(&mut self, id: NodeId) -> Payload {
+        match self.node(id).parent {
+            Some(parent) => self.node_mut(parent).children.retain(|child| *child != id),
+            None => self.roots.retain(|root| *root != id),
+        }
+
+        let mut stack = self.node(id).children.clone();
+        while let Some(child) = stack.pop() {
+            let node = self.take(child);
+            stack.extend(node.children);
+        }
+
+        self.take(id).payload
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes
+            .get(id.index)
+            .is_some_and(|slot| slot.generation == id.generation && slot.node.is_some())
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).parent
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.node(id).children
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// Transform of the node relative to its parent.
+    pub fn transform(&self, id: NodeId) -> &Transform {
+        &self.node(id).local
+    }
+
+    /// Mutable access to the local transform, marking the node dirty.
+    pub fn transform_mut(&mut self, id: NodeId) -> &mut Transform {
+        let node = self.node_mut(id);
+        node.dirty = true;
+
+        &mut node.local
+    }
+
+    /// World matrix of the node as of the last [`SceneGraph::update`].
+    pub fn world_matrix(&self, id: NodeId) -> Mat4 {
+        self.node(id).world
+    }
+
+    /// Position of the origin of the node in world coordinates, as of the
+    /// last [`SceneGraph::update`].
+    pub fn world_position(&self, id: NodeId) -> Vec2 {
+        self.node(id)
+            .world
+            .transform_point3(Vec2::ZERO.extend(0.))
+            .truncate()
+    }
+
+    pub fn payload(&self, id: NodeId) -> &Payload {
+        &self.node(id).payload
+    }
+
+    /// Mutable access to the payload, its world matrix is refreshed by the
+    /// next [`SceneGraph::update`].
+    pub fn payload_mut(&mut self, id: NodeId) -> &mut Payload {
+        let node = self.node_mut(id);
+        node.dirty = true;
+
+        &mut node.payload
+    }
+
+    /// Recompute the world matrices of dirty nodes and their descendants, and
+    /// send them to the payloads.
+    pub fn update(&mut self) {
+        let mut stack: Vec<(NodeId, Mat4, bool)> = self
+            .roots
+            .iter()
+            .map(|root| (*root, Mat4::IDENTITY, false))
+            .collect();
+
+        while let Some((id, parent_world, parent_dirty)) = stack.pop() {
+            let node = self.node_mut(id);
+            let dirty = node.dirty || parent_dirty;
+
+            if dirty {
+                node.world = parent_world * node.local.to_model_matrix();
+                node.dirty = false;
+                let world = node.world;
+                if let Some(mesh) = node.payload.mesh_mut() {
+                    mesh.set_parent_transform(world);
+                }
+            }
+
+            let node = self.node(id);
+            stack.extend(
+                node.children
+                    .iter()
+                    .map(|child| (*child, node.world, dirty)),
+            );
+        }
+    }
+
+    /// Draw the payloads, parents before their children.
+    pub fn draw<'a, D: Drawable<'a>>(&'a self, drawable: &mut D) {
+        let mut stack: Vec<NodeId> = self.roots.iter().rev().copied().collect();
+
+        while let Some(id) = stack.pop() {
+            let node = self.node(id);
+            if let Some(mesh) = node.payload.mesh() {
+                drawable.draw_mesh(mesh);
+            }
+            stack.extend(node.children.iter().rev());
+        }
+    }
+
+    fn is_ancestor(&self, ancestor: NodeId, mut id: NodeId) -> bool {
+        loop {
+            if id == ancestor {
+                return true;
+            }
+            match self.node(id).parent {
+                Some(parent) => id = parent,
+                None => return false,
+            }
+        }
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        let slot = &self.nodes[id.index];
+        slot.node
+            .as_ref()
+            .filter(|_| slot.generation == id.generation)
+            .expect("node was removed")
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        let slot = &mut self.nodes[id.index];
+        slot.node
+            .as_mut()
+            .filter(|_| slot.generation == id.generation)
+            .expect("node was removed")
+    }
+
+    /// Empty the slot of `id`, invalidating the handles on it.
+    fn take(&mut self, id: NodeId) -> Node {
+        let slot = &mut self.nodes[id.index];
+        assert_eq!(slot.generation, id.generation, "node was removed");
+        let node = slot.node.take().expect("node was removed");
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+
+        node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn children_follow_their_parent() {
+        let mut graph = SceneGraph::new();
+        let parent = graph.add(Payload::None);
+        let child = graph.add_child(parent, Payload::None);
+        graph.transform_mut(parent).position = Vec2::new(10., 20.);
+        graph.transform_mut(child).position = Vec2::new(5., 0.);
+        graph.update();
+
+        assert_eq!(graph.world_position(child), Vec2::new(15., 20.));
+
+        graph.transform_mut(parent).rotation = std::f32::consts::FRAC_PI_2;
+        graph.update();
+
+        assert!(graph
+            .world_position(child)
+            .abs_diff_eq(Vec2::new(10., 25.), 1e-5));
+    }
+
+    #[test]
+    fn reparenting_keeps_the_local_transform() {
+        let mut graph = SceneGraph::new();
+        let a = graph.add(Payload::None);
+        let b = graph.add(Payload::None);
+        let child = graph.add_child(a, Payload::None);
+        graph.transform_mut(a).position = Vec2::new(10., 0.);
+        graph.transform_mut(b).position = Vec2::new(0., 10.);
+        graph.transform_mut(child).position = Vec2::new(1., 1.);
+
+        graph.set_parent(child, Some(b));
+        graph.update();
+
+        assert_eq!(graph.world_position(child), Vec2::new(1., 11.));
+        assert_eq!(graph.children(a), &[]);
+        assert_eq!(graph.children(b), &[child]);
+    }
+
+    #[test]
+    fn removing_a_node_removes_its_subtree() {
+        let mut graph = SceneGraph::new();
+        let parent = graph.add(Payload::None);
+        let child = graph.add_child(parent, Payload::None);
+        let grandchild = graph.add_child(child, Payload::None);
+
+        graph.remove(child);
+
+        assert!(graph.contains(parent));
+        assert!(!graph.contains(child));
+        assert!(!graph.contains(grandchild));
+        assert!(graph.children(parent).is_empty());
+    }
+
+    #[test]
+    fn removed_ids_stay_invalid_when_their_slot_is_reused() {
+        let mut graph = SceneGraph::new();
+        let removed = graph.add(Payload::None);
+        graph.remove(removed);
+        let reused = graph.add(Payload::None);
+
+        assert_ne!(removed, reused);
+        assert!(!graph.contains(removed));
+        assert!(graph.contains(reused));
+    }
+
+    #[test]
+    #[should_panic(expected = "node was removed")]
+    fn stale_ids_are_rejected() {
+        let mut graph = SceneGraph::new();
+        let removed = graph.add(Payload::None);
+        graph.remove(removed);
+        graph.add(Payload::None);
+
+        graph.transform_mut(removed);
+    }
+
+    #[test]
+    #[should_panic]
+    fn cycles_are_rejected() {
+        let mut graph = SceneGraph::new();
+        let parent = graph.add(Payload::None);
+        let child = graph.add_child(parent, Payload::None);
+
+        graph.set_parent(parent, Some(child));
+    }
+}
@@ -0,0 +1,442 @@
+use std::{collections::HashMap, path::Path, rc::Rc};
+
+use rusty_core::{
+    glam::Vec2,
+    graphics::texture::{Texture, TextureOptions},
+    math::Rect,
+    Error, Result,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::animation::Animation;
+
+/// Frames of a named sequence, as indices in the sheet or rows and columns
+/// of its grid.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Frames {
+    /// Every frame of a row, left to right.
+    Row(usize),
+    /// Every frame of a column, top to bottom.
+    Column(usize),
+    /// Consecutive frames, from the first index to the second one excluded.
+    Range(usize, usize),
+    /// Any frames, in order.
+    List(Vec<usize>),
+}
+
+/// Layout of a sprite sheet and its named sequences, stored as RON.
+///
+/// ```ron
+/// (
+///     texture: "GR-panda.png",
+///     cell_size: (33, 36),
+///     sequences: {
+///         "walk_down": Row(0),
+///         "idle": List([0, 4]),
+///     },
+/// )
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpriteSheetDescriptor {
+    /// Path of the texture, relative to the descriptor.
+    pub texture: String,
+    pub cell_size: (u32, u32),
+    #[serde(default)]
+    pub margin: u32,
+    #[serde(default)]
+    pub spacing: u32,
+    #[serde(default)]
+    pub sequences: HashMap<String, Frames>,
+}
+
+impl SpriteSheetDescriptor {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|err| Error::io(path, err))?;
+
+        ron::from_str(&contents).map_err(|err| Error::parse(path, err))
+    }
+}
+
+/// Texture sliced into a grid of frames of the same size.
+///
+/// Frames are indexed from the top-left cell, row by row. Cells are
+/// `margin` pixels away from the edges of the texture and `spacing` pixels
+/// away from each other.
+///
+/// ```ignore
+/// let mut sheet = SpriteSheet::new(texture, (33, 36));
+/// sheet.add_sequence("walk_down", Frames::Row(0));
+///
+/// let walk_down = sheet.animation("walk_down", 0.2).unwrap();
+/// ```
+pub struct SpriteSheet {
+    texture: Rc<Texture>,
+    grid: Grid,
+    sequences: HashMap<String, Sequence>,
+}
+
+/// Named sequence, with the frames it was defined with to resolve them again
+/// when the grid changes.
+#[derive(Clone, Debug)]
+struct Sequence {
+    frames: Frames,
+    indices: Vec<usize>,
+}
+
+impl SpriteSheet {
+    pub fn new(texture: Rc<Texture>, cell_size: (u32, u32)) -> Self {
+        let size = texture.texture.size();
+
+        Self {
+            texture,
+            grid: Grid {
+                size: (size.width, size.height),
+                cell_size,
+                margin: 0,
+                spacing: 0,
+            },
+            sequences: HashMap::new(),
+        }
+    }
+
+    /// Create a sheet from a descriptor whose texture was already loaded.
+    pub fn from_descriptor(
+        texture: Rc<Texture>,
+        descriptor: &SpriteSheetDescriptor,
+    ) -> Result<Self> {
+        let mut sheet = Self::new(texture, descriptor.cell_size);
+        sheet.set_margin(descriptor.margin)?;
+        sheet.set_spacing(descriptor.spacing)?;
+        for (name, frames) in &descriptor.sequences {
+            sheet.add_sequence(name, frames.clone())?;
+        }
+
+        Ok(sheet)
+    }
+
+    /// Load a RON descriptor and the texture it refers to, with `options`
+    /// (e.g. [`TextureOptions::pixel_art`] for pixel art sheets).
+    pub fn load(ctx: &rusty_core::Context, path: &Path, options: TextureOptions) -> Result<Self> {
+        let descriptor = SpriteSheetDescriptor::load(path)?;
+        let texture = Texture::from_path(
+            ctx,
+            &path.with_file_name(&descriptor.texture),
+            None,
+            options,
+        )?;
+
+        Self::from_descriptor(Rc::new(texture), &descriptor)
+    }
+
+    pub fn texture(&self) -> &Rc<Texture> {
+        &self.texture
+    }
+
+    pub fn cell_size(&self) -> (u32, u32) {
+        self.grid.cell_size
+    }
+
+    /// Set the space between the cells and the edges of the texture,
+    /// resolving the sequences again on the new grid.
+    ///
+    /// Fails, leaving the sheet unchanged, if a frame of a sequence would be
+    /// outside of the sheet.
+    pub fn set_margin(&mut self, margin: u32) -> Result<()> {
+        self.set_grid(Grid {
+            margin,
+            ..self.grid
+        })
+    }
+
+    pub fn margin(&self) -> u32 {
+        self.grid.margin
+    }
+
+    /// Set the space between adjacent cells, resolving the sequences again
+    /// on the new grid.
+    ///
+    /// Fails, leaving the sheet unchanged, if a frame of a sequence would be
+    /// outside of the sheet.
+    pub fn set_spacing(&mut self, spacing: u32) -> Result<()> {
+        self.set_grid(Grid {
+            spacing,
+            ..self.grid
+        })
+    }
+
+    pub fn spacing(&self) -> u32 {
+        self.grid.spacing
+    }
+
+    pub fn columns(&self) -> usize {
+        self.grid.columns()
+    }
+
+    pub fn rows(&self) -> usize {
+        self.grid.rows()
+    }
+
+    /// Number of frames in the sheet.
+    pub fn len(&self) -> usize {
+        self.grid.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Area of the texture covered by the frame at `index`.
+    pub fn frame(&self, index: usize) -> Option<Rect> {
+        self.grid.frame(index)
+    }
+
+    /// Area of the texture covered by the cell at `column`, `row`.
+    pub fn frame_at(&self, column: usize, row: usize) -> Option<Rect> {
+        (column < self.columns() && row < self.rows()).then(|| self.grid.cell(column, row))
+    }
+
+    /// Name a sequence of frames, replacing any sequence with the same name.
+    ///
+    /// Fails if a frame is outside of the sheet.
+    pub fn add_sequence(&mut self, name: &str, frames: Frames) -> Result<()> {
+        let sequence = self.grid.sequence(name, frames)?;
+        self.sequences.insert(name.to_owned(), sequence);
+
+        Ok(())
+    }
+
+    /// Areas of the texture covered by the frames of a named sequence.
+    pub fn frames(&self, name: &str) -> Option<Vec<Rect>> {
+        let sequence = self.sequences.get(name)?;
+
+        sequence
+            .indices
+            .iter()
+            .map(|index| self.frame(*index))
+            .collect()
+    }
+
+    /// Animation playing a named sequence, showing each frame for
+    /// `frame_time` seconds.
+    pub fn animation(&self, name: &str, frame_time: f32) -> Option<Animation> {
+        Some(Animation::new(&self.frames(name)?, frame_time))
+    }
+
+    /// Names of the sequences.
+    pub fn sequences(&self) -> impl Iterator<Item = &str> {
+        self.sequences.keys().map(String::as_str)
+    }
+
+    fn set_grid(&mut self, grid: Grid) -> Result<()> {
+        self.sequences = grid.resolve(&self.sequences)?;
+        self.grid = grid;
+
+        Ok(())
+    }
+}
+
+/// Cells of a texture of `size` pixels.
+#[derive(Copy, Clone, Debug)]
+struct Grid {
+    size: (u32, u32),
+    cell_size: (u32, u32),
+    margin: u32,
+    spacing: u32,
+}
+
+impl Grid {
+    fn columns(&self) -> usize {
+        cell_count(self.size.0, self.cell_size.0, self.margin, self.spacing)
+    }
+
+    fn rows(&self) -> usize {
+        cell_count(self.size.1, self.cell_size.1, self.margin, self.spacing)
+    }
+
+    fn len(&self) -> usize {
+        self.columns() * self.rows()
+    }
+
+    fn frame(&self, index: usize) -> Option<Rect> {
+        let columns = self.columns();
+
+        (index < self.len()).then(|| self.cell(index % columns, index / columns))
+    }
+
+    fn cell(&self, column: usize, row: usize) -> Rect {
+        let (width, height) = self.cell_size;
+        let position = Vec2::new(
+            (self.margin + column as u32 * (width + self.spacing)) as f32,
+            (self.margin + row as u32 * (height + self.spacing)) as f32,
+        );
+
+        Rect {
+            x: position.x,
+            y: position.y,
+            width: width as f32,
+            height: height as f32,
+        }
+    }
+
+    /// Sequence `name` playing `frames`, failing if one is outside of the
+    /// grid.
+    fn sequence(&self, name: &str, frames: Frames) -> Result<Sequence> {
+        let indices = self.indices(frames.clone()).map_err(|reason| {
+            Error::InvalidArgument(format!("invalid sequence {name}: {reason}"))
+        })?;
+
+        Ok(Sequence { frames, indices })
+    }
+
+    /// `sequences` resolved again on this grid.
+    fn resolve(&self, sequences: &HashMap<String, Sequence>) -> Result<HashMap<String, Sequence>> {
+        sequences
+            .iter()
+            .map(|(name, sequence)| {
+                Ok((name.clone(), self.sequence(name, sequence.frames.clone())?))
+            })
+            .collect()
+    }
+
+    /// Indices of `frames`, failing if one is outside of the grid.
+    fn indices(&self, frames: Frames) -> Result<Vec<usize>, String> {
+        let (columns, rows) = (self.columns(), self.rows());
+        let indices: Vec<usize> = match frames {
+            Frames::Row(row) if row < rows => (row * columns..(row + 1) * columns).collect(),
+            Frames::Column(column) if column < columns => {
+                (0..rows).map(|row| row * columns + column).collect()
+            }
+            Frames::Range(start, end) => (start..end).collect(),
+            Frames::List(indices) => indices,
+            frames => {
+                return Err(format!(
+                    "{frames:?} is outside of the {columns}x{rows} sheet"
+                ))
+            }
+        };
+        if let Some(index) = indices.iter().find(|index| **index >= self.len()) {
+            return Err(format!(
+                "frame {index} is outside of the {columns}x{rows} sheet"
+            ));
+        }
+
+        Ok(indices)
+    }
+}
+
+/// Number of cells of `cell` pixels fitting in `size` pixels.
+fn cell_count(size: u32, cell: u32, margin: u32, spacing: u32) -> usize {
+    if cell == 0 {
+        return 0;
+    }
+
+    let available = size.saturating_sub(2 * margin) + spacing;
+
+    (available / (cell + spacing)) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_fit_between_margins_and_spacing() {
+        assert_eq!(cell_count(132, 33, 0, 0), 4);
+        // 2 + 3 * 10 + 2 * 1 + 2 = 36
+        assert_eq!(cell_count(36, 10, 2, 1), 3);
+        assert_eq!(cell_count(35, 10, 2, 1), 2);
+        assert_eq!(cell_count(8, 0, 0, 0), 0);
+    }
+
+    /// Grid of the player's spritesheet, 4x4 frames of 33x36 pixels.
+    fn panda() -> Grid {
+        Grid {
+            size: (132, 144),
+            cell_size: (33, 36),
+            margin: 0,
+            spacing: 0,
+        }
+    }
+
+    #[test]
+    fn frames_are_indexed_row_by_row() {
+        let grid = panda();
+
+        assert_eq!(grid.len(), 16);
+        assert_eq!(
+            grid.frame(5),
+            Some(Rect {
+                x: 33.,
+                y: 36.,
+                width: 33.,
+                height: 36.,
+            })
+        );
+        assert_eq!(grid.frame(16), None);
+    }
+
+    #[test]
+    fn margin_and_spacing_offset_cells() {
+        let grid = Grid {
+            size: (36, 36),
+            cell_size: (10, 10),
+            margin: 2,
+            spacing: 1,
+        };
+
+        assert_eq!(grid.cell(2, 1).position(), Vec2::new(24., 13.));
+    }
+
+    #[test]
+    fn sequences_resolve_to_indices() {
+        let grid = panda();
+
+        assert_eq!(grid.indices(Frames::Row(2)).unwrap(), [8, 9, 10, 11]);
+        assert_eq!(grid.indices(Frames::Column(1)).unwrap(), [1, 5, 9, 13]);
+        assert_eq!(grid.indices(Frames::Range(3, 6)).unwrap(), [3, 4, 5]);
+        assert!(grid.indices(Frames::Row(4)).is_err());
+        assert!(grid.indices(Frames::List(vec![0, 16])).is_err());
+    }
+
+    #[test]
+    fn sequences_follow_the_grid() {
+        let grid = panda();
+        let sequences = HashMap::from([
+            (
+                "down".to_owned(),
+                grid.sequence("down", Frames::Row(0)).unwrap(),
+            ),
+            (
+                "last".to_owned(),
+                grid.sequence("last", Frames::List(vec![15])).unwrap(),
+            ),
+        ]);
+
+        // A margin leaves room for 3x3 cells, rows are shorter
+        let smaller = Grid { margin: 8, ..grid };
+        assert!(smaller.resolve(&sequences).is_err());
+        let sequences = HashMap::from([("down".to_owned(), sequences["down"].clone())]);
+        let resolved = smaller.resolve(&sequences).unwrap();
+        assert_eq!(resolved["down"].indices, [0, 1, 2]);
+    }
+
+    #[test]
+    fn descriptor_from_ron() {
+        let descriptor: SpriteSheetDescriptor = ron::from_str(
+            r#"(
+                texture: "GR-panda.png",
+                cell_size: (33, 36),
+                sequences: {
+                    "walk_down": Row(0),
+                    "idle": List([0, 4]),
+                },
+            )"#,
+        )
+        .unwrap();
+
+        assert_eq!(descriptor.cell_size, (33, 36));
+        assert_eq!(descriptor.margin, 0);
+        assert_eq!(descriptor.sequences["walk_down"], Frames::Row(0));
+        assert_eq!(descriptor.sequences["idle"], Frames::List(vec![0, 4]));
+    }
+}
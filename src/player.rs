@@ -2,12 +2,9 @@ use rusty_core::graphics::shape::Shape;
 use rusty_core::graphics::Transformable;
 use rusty_core::winit::keyboard::KeyCode;
 use rusty_core::winit::{event::WindowEvent, keyboard::PhysicalKey};
-use rusty_core::{
-    graphics::{shape::RectangleShape, texture::Texture},
-    math::Rect,
-};
-use rusty_engine::animation::Animation;
-use std::{collections::HashMap, rc::Rc};
+use rusty_core::{graphics::shape::RectangleShape, Ctx};
+use rusty_engine::{animation::Animation, sprite_sheet::SpriteSheet};
+use std::collections::HashMap;
 
 const SPEED: f32 = 200.;
 
@@ -25,151 +22,24 @@ pub struct Player {
     pub rect: RectangleShape,
     animations: HashMap<String, Animation>,
     current_animation: String,
-    pub texture: Rc<Texture>,
     direction: Direction,
 }
 
 impl Player {
-    pub fn new(texture: Rc<Texture>) -> Self {
-        let width = 33.;
-        let height = 36.;
-        let rect = RectangleShape::new((width, height).into());
-        let mut animations = HashMap::new();
+    pub fn new(ctx: &Ctx, sheet: &SpriteSheet) -> Self {
+        let (width, height) = sheet.cell_size();
+        let mut rect = RectangleShape::new(ctx, (width as f32, height as f32).into());
+        rect.mesh_mut().set_texture(Some(sheet.texture().clone()));
         let current_animation = String::from("down");
 
-        animations.insert(
-            current_animation.clone(),
-            Animation::new(
-                &[
-                    Rect {
-                        x: 0.,
-                        y: 0.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 33.,
-                        y: 0.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 66.,
-                        y: 0.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 99.,
-                        y: 0.,
-                        width,
-                        height,
-                    },
-                ],
-                0.20,
-            ),
-        );
-        animations.insert(
-            "left".to_string(),
-            Animation::new(
-                &[
-                    Rect {
-                        x: 0.,
-                        y: 36.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 33.,
-                        y: 36.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 66.,
-                        y: 36.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 99.,
-                        y: 36.,
-                        width,
-                        height,
-                    },
-                ],
-                0.20,
-            ),
-        );
-        animations.insert(
-            "up".to_string(),
-            Animation::new(
-                &[
-                    Rect {
-                        x: 0.,
-                        y: 108.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 33.,
-                        y: 108.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 66.,
-                        y: 108.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 99.,
-                        y: 108.,
-                        width,
-                        height,
-                    },
-                ],
-                0.20,
-            ),
-        );
-        animations.insert(
-            "right".to_string(),
-            Animation::new(
-                &[
-                    Rect {
-                        x: 0.,
-                        y: 72.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 33.,
-                        y: 72.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 66.,
-                        y: 72.,
-                        width,
-                        height,
-                    },
-                    Rect {
-                        x: 99.,
-                        y: 72.,
-                        width,
-                        height,
-                    },
-                ],
-                0.20,
-            ),
-        );
+        let animations = ["down", "left", "right", "up"]
+            .into_iter()
+            .filter_map(|name| Some((name.to_owned(), sheet.animation(name, 0.20)?)))
+            .collect();
 
         Self {
             rect,
             animations,
-            texture,
             current_animation,
             direction: Direction::None,
         }